@@ -7,6 +7,10 @@ function on_loaded() {
     update_value();
     enable_menu();
 
+    for (var radio of document.querySelectorAll('input[name="units"]')) {
+        radio.addEventListener('change', on_units_changed);
+    }
+
     // autofocus attribute doesn't work within modals so we need to do it manually
     let modal = document.getElementById("add_modal");
     let weight = document.getElementById("weight-input");
@@ -16,6 +20,22 @@ function on_loaded() {
     })
 }
 
+function units_label() {
+    const kgs = document.getElementById("Kgs-btn");
+    return kgs && kgs.checked ? "kg" : "lbs";
+}
+
+// The weights aren't converted when the units change: the numbers are what is written on
+// the plates.
+function on_units_changed() {
+    const list = document.getElementById('list');
+    for (var child of list.children) {
+        let parts = child.innerText.split(" ");
+        parts[1] = units_label();
+        child.innerText = parts.join(" ");
+    }
+}
+
 function get_values() {
     let values = [];
 
@@ -116,7 +136,7 @@ function add_weight(weight) {
         let item = document.createElement("li");
         item.classList.add("list-group-item");
         item.setAttribute("onclick", "on_click(this)");
-        item.innerText = `${weight} lbs`;
+        item.innerText = `${weight} ${units_label()}`;
 
         const list = document.getElementById('list');
         list.appendChild(item);
@@ -17,16 +17,19 @@ function on_units_change(event) {
     if (secs.selected) {
         convert_times("times-input", "secs", 0)
         convert_time("target-input", "secs", 0)
+        convert_time("step-input", "secs", 0)
         old_units = "secs";
 
     } else if (mins.selected) {
         convert_times("times-input", "mins", 2)
         convert_time("target-input", "mins", 2)
+        convert_time("step-input", "mins", 2)
         old_units = "mins";
 
     } else if (hours.selected) {
         convert_times("times-input", "hours", 4)
         convert_time("target-input", "hours", 4)
+        convert_time("step-input", "hours", 4)
         old_units = "hours";
     }
 }
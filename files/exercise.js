@@ -247,6 +247,9 @@ function post_next_set() {
         form.action += `&advance=${advance.getAttribute("value")}`;
     } else {
         form.action = `/exercise/${workout}/${exercise}/next-set`;
+
+        const advance = document.getElementById('advance_button');
+        form.action += `?advance=${advance.getAttribute("value")}`;
     }
 
     document.body.appendChild(form); // forms cannot be submitted outside of body
@@ -6,17 +6,39 @@ function on_loaded() {
     update_value();
     enable_menu();
 
+    for (var radio of document.querySelectorAll('input[name="units"]')) {
+        radio.addEventListener('change', on_units_changed);
+    }
+
     // autofocus attribute doesn't work within modals so we need to do it manually
     let modal = document.getElementById("add_modal");
     let weight = document.getElementById("weight-input");
     let count = document.getElementById("count-input");
+    let bumper = document.getElementById("bumper-input");
     modal.addEventListener('shown.bs.modal', () => {
         weight.value = "";
         count.value = "";
+        bumper.checked = false;
         weight.focus()
     })
 }
 
+function units_label() {
+    const kgs = document.getElementById("Kgs-btn");
+    return kgs && kgs.checked ? "kg" : "lbs";
+}
+
+// The weights aren't converted when the units change: the numbers are what is written on
+// the plates.
+function on_units_changed() {
+    const list = document.getElementById('list');
+    for (var child of list.children) {
+        let parts = child.innerText.split(" ");
+        parts[1] = units_label();
+        child.innerText = parts.join(" ");
+    }
+}
+
 function get_values() {
     let values = [];
 
@@ -45,6 +67,20 @@ function get_counts() {
     return values;
 }
 
+function get_bumpers() {
+    let values = [];
+
+    const list = document.getElementById('list');
+    const len = list.children.length;
+    for (let i = 0; i < len; i++) {
+        let child = list.children[i];
+        let parts = child.innerText.split(" ");
+        values.push(parts.length > 3 && parts[3] == "bumper");
+    }
+
+    return values;
+}
+
 function find_value(value) {
     let values = get_values();
 
@@ -93,6 +129,7 @@ function parse_count() {
 function on_save() {
     const weight = parse_value();
     const count = parse_count();
+    const bumper = document.getElementById("bumper-input").checked;
 
     let help = document.getElementById("weight-help");
     if (weight) {
@@ -109,7 +146,7 @@ function on_save() {
     }
 
     if (weight && count) {
-        add_plate(weight, count);
+        add_plate(weight, count, bumper);
         resort();
         update_value();
         enable_menu();
@@ -119,7 +156,7 @@ function on_save() {
     }
 }
 
-function add_plate(weight, count) {
+function add_plate(weight, count, bumper) {
     let i = find_value(weight);
     if (i !== undefined) {
         const list = document.getElementById('list');
@@ -130,7 +167,8 @@ function add_plate(weight, count) {
     let item = document.createElement("li");
     item.classList.add("list-group-item");
     item.setAttribute("onclick", "on_click(this)");
-    item.innerText = `${weight} lbs x${count}`;
+    const units = units_label();
+    item.innerText = bumper ? `${weight} ${units} x${count} bumper` : `${weight} ${units} x${count}`;
 
     const list = document.getElementById('list');
     list.appendChild(item);
@@ -164,12 +202,17 @@ function on_click(item) {
 function update_value() {
     let values = get_values();
     let counts = get_counts();
+    let bumpers = get_bumpers();
     values = values.map((s) => s.toFixed(3));
     console.assert(values.length == counts.length);
 
     let items = [];
     for (let i = 0; i < values.length; i++) {
-        items.push(`${values[i]}x${counts[i]}`)
+        if (bumpers[i]) {
+            items.push(`${values[i]}x${counts[i]}xbumper`)
+        } else {
+            items.push(`${values[i]}x${counts[i]}`)
+        }
     }
 
     let input = document.getElementById('list-button');
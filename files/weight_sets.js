@@ -4,6 +4,13 @@
 "use strict";
 
 function on_loaded() {
+    // Sources tell the server which existing set each item came from, see
+    // parse_weight_sets.
+    const list = document.getElementById('list');
+    for (var child of list.children) {
+        child.dataset.source = "set:" + child.innerText;
+    }
+
     update_value();
     enable_menu();
 }
@@ -56,6 +63,7 @@ function on_add(prefix) {
             item.classList.add("list-group-item");
             item.setAttribute("onclick", "on_click(this)");
             item.innerText = name;
+            item.dataset.source = "new";
             list.appendChild(item);
 
             resort()
@@ -79,24 +87,100 @@ function on_delete() {
     }
 }
 
+function selected_item() {
+    const list = document.getElementById('list');
+    for (var child of list.children) {
+        if (child.classList.contains('active')) {
+            return child;
+        }
+    }
+    return null;
+}
+
+function on_duplicate() {
+    const original = selected_item();
+    if (original) {
+        let name = `${original.innerText} copy`;
+        for (let i = 2; has_block(name); i++) {
+            name = `${original.innerText} copy ${i}`;
+        }
+
+        let item = document.createElement("li");
+        item.classList.add("list-group-item");
+        item.setAttribute("onclick", "on_click(this)");
+        item.innerText = name;
+        if (original.dataset.source === "new") {
+            item.dataset.source = "new";
+        } else {
+            item.dataset.source = original.dataset.source.replace(/^set:/, "copy:");
+        }
+
+        const list = document.getElementById('list');
+        list.appendChild(item);
+        resort();
+        update_value();
+    }
+}
+
+function on_rename() {
+    const item = selected_item();
+    if (item) {
+        let input = document.getElementById('rename-input');
+        input.value = item.innerText;
+    }
+}
+
+function on_save_rename() {
+    const item = selected_item();
+    const name = document.getElementById('rename-input').value.trim();
+    const help = document.getElementById('rename-help');
+    if (!name) {
+        help.innerText = "The name cannot be empty.";
+    } else if (name.includes("¦")) {
+        help.innerText = "The name cannot contain ¦.";
+    } else if (item && name !== item.innerText && has_block(name)) {
+        help.innerText = "There is already a weight set with that name.";
+    } else {
+        help.classList.remove("text-danger");
+        help.innerText = "Exercises using the set will be updated to use the new name.";
+        if (item) {
+            item.innerText = name;
+            resort();
+            update_value();
+        }
+
+        let modal = document.getElementById("rename_modal");
+        bootstrap.Modal.getInstance(modal).hide();
+        return;
+    }
+    help.classList.add("text-danger");
+}
+
 function update_value() {
     let names = "";
+    let sources = "";
     const list = document.getElementById('list');
     for (var child of list.children) {
         if (names) {
             names += "¦";
+            sources += "¦";
         }
         names += child.innerText;
+        sources += child.dataset.source;
     }
     let input = document.getElementById('list-button');
     input.value = names;
+
+    input = document.getElementById('sources-btn');
+    input.value = sources;
 }
 
 function enable_menu() {
     const list = document.getElementById('list');
     const len = list.children.length;
 
-    for (var name of ["delete-btn"]) {
+    const buttons = ["delete-btn", "duplicate-btn", "rename-btn"];
+    for (var name of buttons) {
         let button = document.getElementById(name);
         button.classList.add('disabled');
     }
@@ -104,8 +188,10 @@ function enable_menu() {
     for (let i = 0; i < len; i++) {
         let child = list.children[i];
         if (child.classList.contains('active')) {
-            let button = document.getElementById('delete-btn');
-            button.classList.remove('disabled');
+            for (var name of buttons) {
+                let button = document.getElementById(name);
+                button.classList.remove('disabled');
+            }
             break;
         }
     }
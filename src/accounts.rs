@@ -0,0 +1,276 @@
+//! User accounts and login sessions. Passwords are hashed with argon2 and stored in the
+//! data directory along with the user data files. Sessions are only kept in memory so
+//! users have to log back in after the server restarts.
+use crate::errors::Error;
+use crate::validation_err;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const SESSION_COOKIE: &str = "session";
+pub const SESSION_DAYS: i64 = 30;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Accounts {
+    users: HashMap<String, Account>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Account {
+    password: String, // argon2 hash in PHC format (includes the salt)
+    #[serde(default)]
+    calendar_token: Option<String>, // for the calendar feed which can't use sessions
+}
+
+impl Accounts {
+    pub fn any(&self) -> bool {
+        !self.users.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.users.contains_key(name)
+    }
+
+    /// Adds a new user or changes the password of an existing user.
+    pub fn try_set_password(&mut self, name: &str, password: &str) -> Result<(), Error> {
+        self.validate_set_password(name, password)?;
+        self.do_set_password(name, password)
+    }
+
+    /// Returns the user's password hash. Hashing is slow so this allows passwords to be
+    /// checked, with [`verify_password`], without holding a lock on the accounts.
+    pub fn password_hash(&self, name: &str) -> Option<String> {
+        self.users.get(name).map(|a| a.password.clone())
+    }
+
+    fn validate_set_password(&self, name: &str, password: &str) -> Result<(), Error> {
+        validate_name(name)?;
+        if password.is_empty() {
+            return validation_err!("The password cannot be empty.");
+        }
+        Ok(())
+    }
+
+    fn do_set_password(&mut self, name: &str, password: &str) -> Result<(), Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = match Argon2::default().hash_password(password.as_bytes(), &salt) {
+            Ok(h) => h.to_string(),
+            Err(e) => {
+                return Err(Error::InternalError(format!(
+                    "failed to hash password: {e}"
+                )))
+            }
+        };
+        match self.users.get_mut(name) {
+            Some(account) => account.password = hash,
+            None => {
+                let account = Account {
+                    password: hash,
+                    calendar_token: None,
+                };
+                self.users.insert(name.to_owned(), account);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn calendar_token(&self, name: &str) -> Option<&str> {
+        self.users.get(name)?.calendar_token.as_deref()
+    }
+
+    /// Gives the user a new calendar token, invalidating the old one (if any).
+    pub fn reset_calendar_token(&mut self, name: &str) -> Option<&str> {
+        let account = self.users.get_mut(name)?;
+        account.calendar_token = Some(new_token());
+        account.calendar_token.as_deref()
+    }
+
+    /// Returns the name of the user with the calendar token.
+    pub fn find_calendar_user(&self, token: &str) -> Option<&str> {
+        self.users
+            .iter()
+            .find(|(_, a)| a.calendar_token.as_deref() == Some(token))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns true if password matches hash (from [`Accounts::password_hash`]).
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Hash to verify against for unknown user names so that logins take about as long
+/// whether or not the name exists.
+pub fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(b"", &salt)
+            .expect("hashing the dummy password should work")
+            .to_string()
+    })
+}
+
+/// User names are used as file names so we keep them simple.
+pub fn validate_name(name: &str) -> Result<(), Error> {
+    if name.trim().is_empty() {
+        return validation_err!("The user name cannot be empty.");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return validation_err!("User names should only use letters, numbers, '-', and '_'.");
+    }
+    Ok(())
+}
+
+/// Maps session tokens (stored in a cookie) to user names.
+#[derive(Debug, Default)]
+pub struct Sessions {
+    sessions: HashMap<String, Session>,
+}
+
+#[derive(Debug)]
+struct Session {
+    name: String,
+    expires: DateTime<Local>,
+}
+
+impl Sessions {
+    /// Returns the token for a new session.
+    pub fn create(&mut self, name: &str) -> String {
+        let now = Local::now();
+        self.sessions.retain(|_, s| s.expires > now);
+
+        let token = new_token();
+        let session = Session {
+            name: name.to_owned(),
+            expires: now + Duration::days(SESSION_DAYS),
+        };
+        self.sessions.insert(token.clone(), session);
+        token
+    }
+
+    /// Returns the user name for a (non-expired) session.
+    pub fn find(&self, token: &str) -> Option<&str> {
+        self.sessions
+            .get(token)
+            .filter(|s| s.expires > Local::now())
+            .map(|s| s.name.as_str())
+    }
+
+    pub fn remove(&mut self, token: &str) {
+        self.sessions.remove(token);
+    }
+}
+
+/// Returns the session token from the request's cookies.
+pub fn find_token(cookies: &str) -> Option<&str> {
+    cookies
+        .split(';')
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(key, _)| *key == SESSION_COOKIE)
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify(accounts: &Accounts, name: &str, password: &str) -> bool {
+        accounts
+            .password_hash(name)
+            .is_some_and(|hash| verify_password(&hash, password))
+    }
+
+    #[test]
+    fn passwords() {
+        let mut accounts = Accounts::default();
+        assert!(accounts.try_set_password("alice", "secret").is_ok());
+        assert!(accounts.contains("alice"));
+        assert!(verify(&accounts, "alice", "secret"));
+        assert!(!verify(&accounts, "alice", "Secret"));
+        assert!(!verify(&accounts, "bob", "secret"));
+        assert!(PasswordHash::new(dummy_hash()).is_ok()); // else verify would return early
+
+        // Hashes are salted.
+        assert!(accounts.try_set_password("bob", "secret").is_ok());
+        assert_ne!(
+            accounts.users["alice"].password,
+            accounts.users["bob"].password
+        );
+
+        // Passwords can be changed.
+        assert!(accounts.try_set_password("alice", "hunter2").is_ok());
+        assert!(verify(&accounts, "alice", "hunter2"));
+        assert!(!verify(&accounts, "alice", "secret"));
+
+        assert!(accounts.try_set_password("carol", "").is_err());
+        assert!(accounts.try_set_password("", "secret").is_err());
+        assert!(accounts.try_set_password("../carol", "secret").is_err());
+        assert!(!accounts.contains("carol"));
+    }
+
+    #[test]
+    fn calendar_tokens() {
+        let mut accounts = Accounts::default();
+        assert!(accounts.try_set_password("alice", "secret").is_ok());
+        assert_eq!(accounts.calendar_token("alice"), None);
+        assert_eq!(accounts.reset_calendar_token("bob"), None);
+
+        let token = accounts.reset_calendar_token("alice").unwrap().to_owned();
+        assert_eq!(accounts.find_calendar_user(&token), Some("alice"));
+        assert_eq!(accounts.find_calendar_user("bogus"), None);
+
+        // Changing the password keeps the token.
+        assert!(accounts.try_set_password("alice", "hunter2").is_ok());
+        assert_eq!(accounts.calendar_token("alice"), Some(token.as_str()));
+
+        // Resetting invalidates the old token.
+        let new_token = accounts.reset_calendar_token("alice").unwrap().to_owned();
+        assert_ne!(token, new_token);
+        assert_eq!(accounts.find_calendar_user(&token), None);
+    }
+
+    #[test]
+    fn sessions() {
+        let mut sessions = Sessions::default();
+        let alice = sessions.create("alice");
+        let bob = sessions.create("bob");
+        assert_ne!(alice, bob);
+        assert_eq!(sessions.find(&alice), Some("alice"));
+        assert_eq!(sessions.find(&bob), Some("bob"));
+        assert_eq!(sessions.find("bogus"), None);
+
+        sessions.remove(&alice);
+        assert_eq!(sessions.find(&alice), None);
+
+        sessions.sessions.get_mut(&bob).unwrap().expires = Local::now() - Duration::minutes(1);
+        assert_eq!(sessions.find(&bob), None);
+    }
+
+    #[test]
+    fn cookies() {
+        assert_eq!(find_token("session=abc"), Some("abc"));
+        assert_eq!(find_token("theme=dark; session=abc; other=1"), Some("abc"));
+        assert_eq!(find_token("theme=dark"), None);
+        assert_eq!(find_token(""), None);
+    }
+}
@@ -1,6 +1,14 @@
+use crate::accounts::{Accounts, Sessions};
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::import::Import;
+use crate::options::Options;
+use crate::weights::{Units, WeightSet};
 use crate::{history::History, notes::Notes, program::Program, weights::Weights};
 use handlebars::Handlebars;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
 /// State associated with a user.
@@ -11,20 +19,102 @@ pub struct UserState {
     pub weights: Weights,
     pub program: Program,
     pub errors: Vec<String>,
+    pub import_names: HashMap<String, String>, // other app's exercise name => our name (empty to skip)
+    pub options: Options,
 }
 
-/// Global state passed into axum handlers.
+/// Per-user state passed into axum handlers. The handler's user is resolved from their
+/// session cookie by middleware before the handler runs.
 pub struct AppState {
     pub handlebars: Handlebars<'static>, // templating engine
-    pub name: String,
+    pub name: String,                    // also the name of the user's data file
+    pub data_dir: PathBuf,               // where the user's data file lives
     pub user: UserState,
+    pub pending_import: Option<Import>, // uploaded history waiting for names to be mapped
+    pub readonly: bool, // the data file couldn't be loaded so saving would clobber it
 }
 
 /// [`AppState`] is shared across threaded handlers so we need to protect access.
 pub type SharedState = Arc<RwLock<AppState>>;
 
+/// State shared by all users of the server.
+pub struct ServerState {
+    pub handlebars: Handlebars<'static>, // for pages like login that don't have a user
+    pub data_dir: PathBuf,
+    pub accounts: Accounts,
+    pub sessions: Sessions,
+    users: HashMap<String, SharedState>, // users are loaded on demand
+}
+
+pub type SharedServer = Arc<RwLock<ServerState>>;
+
+impl ServerState {
+    pub fn new(data_dir: PathBuf, accounts: Accounts) -> ServerState {
+        ServerState {
+            handlebars: Handlebars::new(),
+            data_dir,
+            accounts,
+            sessions: Sessions::default(),
+            users: HashMap::new(),
+        }
+    }
+
+    /// Returns the state for the user logged into the session, loading it if needed.
+    pub fn find_user(&mut self, token: &str) -> Option<SharedState> {
+        let name = self.sessions.find(token)?.to_owned();
+        if !self.accounts.contains(&name) {
+            return None;
+        }
+        Some(self.load_user(name))
+    }
+
+    /// Returns the state for the user with the calendar token, loading it if needed.
+    pub fn find_calendar_user(&mut self, token: &str) -> Option<SharedState> {
+        let name = self.accounts.find_calendar_user(token)?.to_owned();
+        Some(self.load_user(name))
+    }
+
+    fn load_user(&mut self, name: String) -> SharedState {
+        let data_dir = &self.data_dir;
+        let state = self.users.entry(name.clone()).or_insert_with(|| {
+            let state = crate::default::make_state(data_dir, &name, false);
+            SharedState::new(RwLock::new(state))
+        });
+        state.clone()
+    }
+}
+
 impl UserState {
     pub fn fixup(&mut self) {
         self.weights.fixup();
     }
+
+    /// Changes the old_name weight set to set. If new_name is different the set is renamed
+    /// and every exercise that used old_name is updated to use new_name.
+    pub fn try_change_set(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        set: WeightSet,
+        units: Units,
+    ) -> Result<(), Error> {
+        // Weights does all the validation so once it succeeds the program can't fail.
+        self.weights
+            .try_change_set(old_name, new_name, set, units)?;
+        if !old_name.is_empty() && old_name != new_name {
+            let renames = [(old_name.to_owned(), new_name.to_owned())];
+            self.program.rename_weight_sets(&renames);
+        }
+        Ok(())
+    }
+
+    /// Units used to show weights for an exercise, see [`Weights::display_units`].
+    pub fn exercise_units(&self, workout: &str, exercise: &ExerciseName) -> Units {
+        let weightset = self
+            .program
+            .find(workout)
+            .and_then(|w| w.find(exercise))
+            .and_then(|e| e.data().weightset.clone());
+        self.weights.display_units(&weightset, self.options.units)
+    }
 }
@@ -0,0 +1,167 @@
+//! Deployment settings: where to listen and where to keep data.
+//! These come from an optional TOML (or JSON) config file and can be overridden with
+//! command line flags.
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// Command line flags, these take precedence over the config file.
+#[derive(Debug, Parser)]
+#[command(version, about = "Web app for weight lifting")]
+pub struct Args {
+    /// Path to a TOML or JSON config file. Defaults to web-lifter/config.toml within the
+    /// system config directory (if it exists).
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// IP address to bind to, e.g. 127.0.0.1.
+    #[arg(long)]
+    pub address: Option<String>,
+
+    /// Port to listen on.
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Directory used to store user data.
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Adds a user (or changes the password of an existing user). The password is read
+    /// from the terminal.
+    AddUser {
+        name: String,
+
+        /// New users start out with an example program and history instead of an empty
+        /// program.
+        #[arg(long)]
+        example: bool,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_address")]
+    pub address: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,
+}
+
+impl Config {
+    /// Builds the config from the config file (if any) and the command line flags.
+    pub fn load(args: &Args) -> Result<Config> {
+        let mut config = match &args.config {
+            Some(path) => Config::from_file(path)?,
+            None => match dirs::config_dir().map(|d| d.join("web-lifter").join("config.toml")) {
+                Some(path) if path.exists() => Config::from_file(&path)?,
+                _ => Config::default(),
+            },
+        };
+
+        if let Some(address) = &args.address {
+            config.address = address.clone();
+        }
+        if let Some(port) = args.port {
+            config.port = port;
+        }
+        if let Some(data_dir) = &args.data_dir {
+            config.data_dir = data_dir.clone();
+        }
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let contents = std::fs::read_to_string(path)?;
+        let config = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents).map_err(|e| Error::other(format!("{path:?}: {e}")))?
+        };
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            address: default_address(),
+            port: default_port(),
+            data_dir: default_data_dir(),
+        }
+    }
+}
+
+fn default_address() -> String {
+    "0.0.0.0".to_owned()
+}
+
+fn default_port() -> u16 {
+    3000
+}
+
+// On my machine this is /Users/jessejones/Library/Application\ Support/web-lifter
+fn default_data_dir() -> PathBuf {
+    match dirs::data_dir() {
+        Some(dir) => dir.join("web-lifter"),
+        None => PathBuf::from("web-lifter"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml() {
+        let text = r#"
+            address = "127.0.0.1"
+            port = 8080
+            data_dir = "/var/lib/web-lifter"
+        "#;
+        let config: Config = toml::from_str(text).unwrap();
+        assert_eq!(config.address, "127.0.0.1");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.data_dir, PathBuf::from("/var/lib/web-lifter"));
+
+        assert!(toml::from_str::<Config>("users = []").is_err());
+    }
+
+    #[test]
+    fn json_defaults() {
+        let config: Config = serde_json::from_str(r#"{"port": 80}"#).unwrap();
+        assert_eq!(config.address, default_address());
+        assert_eq!(config.port, 80);
+        assert_eq!(config.data_dir, default_data_dir());
+    }
+
+    #[test]
+    fn flags() {
+        let args = Args::parse_from(["web-lifter", "--address", "10.0.0.1", "--port", "80"]);
+        assert_eq!(args.address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(args.port, Some(80));
+        assert!(args.command.is_none());
+
+        let args = Args::parse_from(["web-lifter", "add-user", "alice", "--example"]);
+        match args.command {
+            Some(Command::AddUser { name, example }) => {
+                assert_eq!(name, "alice");
+                assert!(example);
+            }
+            None => panic!("expected add-user"),
+        }
+
+        assert!(Args::try_parse_from(["web-lifter", "--port", "x"]).is_err());
+        assert!(Args::try_parse_from(["web-lifter", "add-user"]).is_err());
+    }
+}
@@ -1,13 +1,17 @@
 use self::{
     history::History,
     notes::Notes,
+    options::Options,
     program::{Block, Program},
-    weights::{Plate, WeightSet, Weights},
+    weights::{AddOn, Plate, WeightSet, Weights},
     workout::{Schedule, Workout},
 };
 use crate::app_state::{AppState, UserState};
 use crate::*;
 use chrono::{Local, Weekday};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
 
 pub fn default_plates() -> WeightSet {
     WeightSet::DualPlates(
@@ -18,74 +22,58 @@ pub fn default_plates() -> WeightSet {
             Plate::new(45.0, 4),
         ],
         Some(45.0),
+        None,
     )
 }
 
-pub fn default_discrete() -> WeightSet {
-    WeightSet::Discrete((10..=100).step_by(10).map(|i| i as f32).collect())
+pub fn default_single_plates() -> WeightSet {
+    WeightSet::SinglePlates(
+        vec![
+            Plate::new(2.5, 2),
+            Plate::new(5.0, 2),
+            Plate::new(10.0, 2),
+            Plate::new(25.0, 2),
+            Plate::new(45.0, 2),
+        ],
+        None,
+    )
 }
 
-pub fn make_my_program() -> AppState {
-    let blocks = vec![
-        Block::new(
-            "Heavy".to_owned(),
-            vec!["Heavy Bench".to_owned(), "Heavy OHP".to_owned()],
-            1,
-        ),
-        Block::new(
-            "Medium".to_owned(),
-            vec!["Medium Bench".to_owned(), "Medium OHP".to_owned()],
-            1,
-        ),
-        Block::new("Light".to_owned(), vec!["Light".to_owned()], 1),
-    ];
-    let pname = "My".to_owned();
-    let mut default_program = Program::with_blocks(pname, blocks, Local::now(), 2);
-    default_program.add_workout(create_heavy_bench());
-    default_program.add_workout(create_heavy_ohp());
-    default_program.add_workout(create_medium_bench());
-    default_program.add_workout(create_medium_ohp());
-    default_program.add_workout(create_light());
-    default_program.add_workout(create_test());
-
-    let mut user = match persist::load("mine") {
-        Ok(u) => u,
-        Err(e) => {
-            let errors = vec![format!("load had error {}", e.kind())];
-            UserState {
-                notes: Notes::new(),
-                history: create_history(),
-                weights: creat_weight_sets(),
-                program: default_program,
-                errors,
-            }
-        }
-    };
-
-    user.fixup();
-    user.program.fixup();
-
-    AppState {
-        handlebars: Handlebars::new(),
-        name: "mine".to_string(),
-        user,
-    }
+pub fn default_discrete() -> WeightSet {
+    WeightSet::Discrete(
+        (10..=100).step_by(10).map(|i| i as f32).collect(),
+        Vec::new(),
+    )
 }
 
-pub fn make_her_program() -> AppState {
-    let pname = "My".to_owned();
-    let default_program = Program::new(pname);
-
-    let mut user = match persist::load("victoria") {
+/// Loads the user's data or, if that fails, creates new data for them. If example is
+/// set new data starts out with an example program and history.
+/// If the data file exists but couldn't be loaded (e.g. it's from a newer build) then
+/// the state is marked readonly so that it isn't overwritten.
+pub fn make_state(data_dir: &Path, name: &str, example: bool) -> AppState {
+    let mut readonly = false;
+    let mut user = match persist::load(data_dir, name) {
         Ok(u) => u,
         Err(e) => {
-            let errors = vec![format!("load had error {}", e.kind())];
+            let errors = if e.kind() == ErrorKind::NotFound {
+                vec![format!("load had error {}", e.kind())]
+            } else {
+                readonly = true;
+                vec![format!("Failed to load data ({e}), changes won't be saved")]
+            };
+            let (program, history) = if example {
+                (make_example_program(), create_history())
+            } else {
+                (Program::new("My".to_owned()), History::new())
+            };
             UserState {
                 notes: Notes::new(),
-                history: create_history(),
+                history,
                 weights: creat_weight_sets(),
-                program: default_program,
+                program,
                 errors,
+                import_names: HashMap::new(),
+                options: Options::default(),
             }
         }
     };
@@ -95,37 +83,37 @@ pub fn make_her_program() -> AppState {
 
     AppState {
         handlebars: Handlebars::new(),
-        name: "victoria".to_string(),
+        name: name.to_owned(),
+        data_dir: data_dir.to_owned(),
         user,
+        pending_import: None,
+        readonly,
     }
 }
 
-pub fn make_test_program() -> AppState {
-    let pname = "Test Program".to_owned();
-    let default_program = Program::new(pname);
-
-    let mut user = match persist::load("test") {
-        Ok(u) => u,
-        Err(e) => {
-            let errors = vec![format!("load had error {}", e.kind())];
-            UserState {
-                notes: Notes::new(),
-                history: create_history(),
-                weights: creat_weight_sets(),
-                program: default_program,
-                errors,
-            }
-        }
-    };
-
-    user.fixup();
-    user.program.fixup();
-
-    AppState {
-        handlebars: Handlebars::new(),
-        name: "test".to_string(),
-        user,
-    }
+fn make_example_program() -> Program {
+    let blocks = vec![
+        Block::new(
+            "Heavy".to_owned(),
+            vec!["Heavy Bench".to_owned(), "Heavy OHP".to_owned()],
+            1,
+        ),
+        Block::new(
+            "Medium".to_owned(),
+            vec!["Medium Bench".to_owned(), "Medium OHP".to_owned()],
+            1,
+        ),
+        Block::new("Light".to_owned(), vec!["Light".to_owned()], 1),
+    ];
+    let pname = "My".to_owned();
+    let mut program = Program::with_blocks(pname, blocks, Local::now(), 2);
+    program.add_workout(create_heavy_bench());
+    program.add_workout(create_heavy_ohp());
+    program.add_workout(create_medium_bench());
+    program.add_workout(create_medium_ohp());
+    program.add_workout(create_light());
+    program.add_workout(create_test());
+    program
 }
 
 fn create_test() -> Workout {
@@ -145,7 +133,9 @@ fn create_test() -> Workout {
     workout.add_exercise(exercise);
 
     // Couch Stretch
-    let e = DurationsExercise::new(vec![20; 4]).with_target_secs(120);
+    let e = DurationsExercise::new(vec![20; 4])
+        .with_target_secs(120)
+        .with_step_secs(10);
     let name = ExerciseName("Couch Stretch".to_owned());
     let formal_name = FormalName("Couch Stretch".to_owned());
     let exercise = BuildExercise::durations(name, formal_name, e).finalize();
@@ -201,7 +191,7 @@ fn create_heavy_bench() -> Workout {
         FixedReps::new(1, 90),
     ];
     let worksets = vec![VariableReps::new(1, 3, 100); 3];
-    let e = VariableRepsExercise::new(warmups, worksets);
+    let e = VariableRepsExercise::new(warmups, worksets).with_deload(Deload::new(3, 10));
     let name = ExerciseName("Heavy Bench".to_owned());
     let formal_name = FormalName("Bench Press".to_owned());
     let exercise = BuildExercise::variable_reps(name.clone(), formal_name, e)
@@ -463,7 +453,9 @@ fn create_light() -> Workout {
     let mut workout = Workout::new("Light".to_owned(), schedule);
 
     // Couch Stretch
-    let e = DurationsExercise::new(vec![20; 4]).with_target_secs(120);
+    let e = DurationsExercise::new(vec![20; 4])
+        .with_target_secs(120)
+        .with_step_secs(10);
     let name = ExerciseName("Couch Stretch".to_owned());
     let formal_name = FormalName("Couch Stretch".to_owned());
     let exercise = BuildExercise::durations(name, formal_name, e).finalize();
@@ -594,6 +586,7 @@ fn creat_weight_sets() -> Weights {
             Plate::new(45.0, 6),
         ],
         Some(45.0),
+        None,
     );
     weights.add("Deadlift".to_owned(), set);
 
@@ -606,6 +599,7 @@ fn creat_weight_sets() -> Weights {
             Plate::new(45.0, 4),
         ],
         Some(45.0),
+        None,
     );
     weights.add("Dual Plates".to_owned(), set);
 
@@ -617,10 +611,16 @@ fn creat_weight_sets() -> Weights {
             Plate::new(45.0, 8),
         ],
         None,
+        None,
     );
     weights.add("Machine Plates".to_owned(), set);
 
-    let set = WeightSet::Discrete((25..=975).step_by(50).map(|i| (i as f32) / 10.0).collect());
+    weights.add("Landmine".to_owned(), default_single_plates());
+
+    let set = WeightSet::Discrete(
+        (25..=975).step_by(50).map(|i| (i as f32) / 10.0).collect(),
+        Vec::new(),
+    );
     weights.add("Cable Machine".to_owned(), set);
 
     let mut w1: Vec<_> = (125..=500).step_by(75).map(|i| (i as f32) / 10.0).collect();
@@ -629,13 +629,16 @@ fn creat_weight_sets() -> Weights {
         .map(|i| (i as f32) / 10.0)
         .collect();
     w1.extend(w2);
-    let set = WeightSet::Discrete(w1);
+    let set = WeightSet::Discrete(w1, Vec::new());
     weights.add("Lat Pulldown".to_owned(), set);
 
-    let set = WeightSet::Discrete((5..=100).step_by(5).map(|i| i as f32).collect());
+    let set = WeightSet::Discrete(
+        (5..=100).step_by(5).map(|i| i as f32).collect(),
+        vec![AddOn::new("magnet", 2.5, 1)],
+    );
     weights.add("Gym Dumbbells".to_owned(), set);
 
-    let set = WeightSet::Discrete(vec![9.0, 13.0, 18.0]);
+    let set = WeightSet::Discrete(vec![9.0, 13.0, 18.0], Vec::new());
     weights.add("Kettlebell".to_owned(), set);
 
     weights
@@ -1,6 +1,7 @@
 //! Exercises are movements for the user to perform, e.g. a barbell squat. These may be
 //! shared across programs and workouts.
 use crate::errors::Error;
+use crate::strength::OneRepMax;
 use crate::validation_err;
 use crate::weights::{Weight, Weights};
 use chrono::{DateTime, Local};
@@ -30,7 +31,7 @@ pub struct ExerciseName(pub String);
 pub struct FormalName(pub String);
 
 /// Not all exercises will support all of these.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum SetIndex {
     Warmup(usize),
     Workset(usize),
@@ -45,6 +46,47 @@ impl SetIndex {
     }
 }
 
+/// Used by percentage based programs like 5/3/1. When an exercise has these its weight
+/// is a training max and each workset uses the percent for the program's current week
+/// instead of its own percent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeekPercents(pub Vec<Vec<i32>>); // [week][workset], weeks wrap around
+
+impl WeekPercents {
+    /// Week is from [`Program::current_week`], if there are no blocks the first week
+    /// is used. Worksets past the end of a week use the week's last percent.
+    ///
+    /// [`Program::current_week`]: crate::program::Program::current_week
+    pub fn percent(&self, week: Option<i32>, workset: usize) -> Option<i32> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let week = (week.unwrap_or(1).max(1) - 1) as usize % self.0.len();
+        let percents = &self.0[week];
+        percents.get(workset).or(percents.last()).copied()
+    }
+
+    /// True if week is the last week of the cycle, i.e. the next week starts over at the
+    /// first row.
+    pub fn last_week(&self, week: Option<i32>) -> bool {
+        let week = (week.unwrap_or(1).max(1) - 1) as usize;
+        !self.0.is_empty() && week % self.0.len() + 1 == self.0.len()
+    }
+}
+
+/// How the reps done on an AMRAP (as many reps as possible) set change the weight, or
+/// training max, for the next cycle. The set's reps are the minimum the user should do.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AmrapRule {
+    /// Advance the weight a step if the minimum was reached plus another step for every
+    /// extra reps past the minimum (zero means only one step). Drops a step if the
+    /// minimum wasn't reached.
+    Steps(i32),
+
+    /// Use this percent of the one rep max estimated from the set.
+    Estimate(i32),
+}
+
 /// State shared across exercise types.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExerciseData {
@@ -59,6 +101,8 @@ pub struct ExerciseData {
     pub weight: Option<f32>, // base weight to use for each workset, often modified by per-set percent
     pub rest: Option<i32>,   // used for work sets
     pub last_rest: Option<i32>, // overrides rest.last()
+    pub week_percents: Option<WeekPercents>, // if set weight is a training max
+    pub amrap: Option<AmrapRule>, // if set the last workset is AMRAP
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -211,9 +255,17 @@ impl Exercise {
     //     }
     // }
 
-    /// Used for warmup sets, returned weight may be over expected weight.
-    pub fn closest_weight(&self, weights: &Weights, index: SetIndex) -> Option<Weight> {
-        let (target, name) = self.target_weight(index);
+    /// Used for warmup sets, returned weight may be over expected weight. Week is from
+    /// [`Program::current_week`] and is used with [`WeekPercents`].
+    ///
+    /// [`Program::current_week`]: crate::program::Program::current_week
+    pub fn closest_weight(
+        &self,
+        weights: &Weights,
+        week: Option<i32>,
+        index: SetIndex,
+    ) -> Option<Weight> {
+        let (target, name) = self.target_weight(week, index);
         if let Some(name) = name {
             target.map(|t| weights.closest(&name, t))
         } else {
@@ -223,8 +275,13 @@ impl Exercise {
 
     /// Used for worksets sets, returns a weight as close as possible to the expected
     /// weight but not over.
-    pub fn lower_weight(&self, weights: &Weights, index: SetIndex) -> Option<Weight> {
-        let (target, name) = self.target_weight(index);
+    pub fn lower_weight(
+        &self,
+        weights: &Weights,
+        week: Option<i32>,
+        index: SetIndex,
+    ) -> Option<Weight> {
+        let (target, name) = self.target_weight(week, index);
         if let Some(name) = name {
             target.map(|t| weights.lower(&name, t))
         } else {
@@ -232,6 +289,38 @@ impl Exercise {
         }
     }
 
+    /// Uses closest_weight for warmups and lower_weight for worksets.
+    pub fn weight_at(
+        &self,
+        weights: &Weights,
+        week: Option<i32>,
+        index: SetIndex,
+    ) -> Option<Weight> {
+        match index {
+            SetIndex::Warmup(_) => self.closest_weight(weights, week, index),
+            SetIndex::Workset(_) => self.lower_weight(weights, week, index),
+        }
+    }
+
+    /// Returns the sets in the order that they are done. VariableSets only returns the
+    /// first set because the number of sets isn't known ahead of time (and they all use
+    /// the same weight).
+    pub fn set_indexes(&self) -> Vec<SetIndex> {
+        fn indexes(num_warmups: usize, num_worksets: usize) -> Vec<SetIndex> {
+            (0..num_warmups)
+                .map(SetIndex::Warmup)
+                .chain((0..num_worksets).map(SetIndex::Workset))
+                .collect()
+        }
+
+        match self {
+            Exercise::Durations(_, e) => indexes(0, e.num_sets()),
+            Exercise::FixedReps(_, e) => indexes(e.num_warmups(), e.num_worksets()),
+            Exercise::VariableReps(_, e) => indexes(e.num_warmups(), e.num_worksets()),
+            Exercise::VariableSets(_, _) => indexes(0, 1),
+        }
+    }
+
     pub fn advance_weight(&self, weights: &Weights) -> Option<Weight> {
         let (target, name) = self.base_weight();
         if let Some(name) = name {
@@ -241,6 +330,79 @@ impl Exercise {
         }
     }
 
+    /// True if index is an AMRAP set, see [`AmrapRule`].
+    pub fn is_amrap(&self, index: SetIndex) -> bool {
+        let num_worksets = match self {
+            Exercise::FixedReps(_, e) => e.num_worksets(),
+            Exercise::VariableReps(_, e) => e.num_worksets(),
+            _ => return false,
+        };
+        self.data().amrap.is_some() && index == SetIndex::Workset(num_worksets - 1)
+    }
+
+    /// The fewest reps the user should do on the AMRAP set.
+    pub fn amrap_min(&self) -> Option<i32> {
+        self.data().amrap?;
+        match self {
+            Exercise::FixedReps(_, e) => e.worksets().last().map(|s| s.reps),
+            Exercise::VariableReps(_, e) => Some(e.workset(e.num_worksets() - 1).min),
+            _ => None,
+        }
+    }
+
+    /// Returns the weight to use for the next cycle after the user did reps with
+    /// set_weight on the AMRAP set.
+    pub fn amrap_weight(
+        &self,
+        weights: &Weights,
+        formula: OneRepMax,
+        reps: i32,
+        set_weight: f32,
+    ) -> Option<Weight> {
+        let rule = self.data().amrap?;
+        let min = self.amrap_min()?;
+        let (target, name) = self.base_weight();
+        let target = target?;
+        match rule {
+            AmrapRule::Steps(extra) => {
+                let name = name.as_ref()?; // steps come from the weight set
+                if reps < min {
+                    return Some(weights.lower(name, target - 0.01));
+                }
+                let steps = if extra > 0 {
+                    1 + (reps - min) / extra
+                } else {
+                    1
+                };
+                let mut weight = weights.advance(name, target);
+                for _ in 1..steps {
+                    weight = weights.advance(name, weight.value());
+                }
+                Some(weight)
+            }
+            AmrapRule::Estimate(percent) => {
+                let max = formula.estimate(reps, set_weight)?;
+                let target = max * percent as f32 / 100.0;
+                if let Some(name) = name {
+                    Some(weights.lower(name, target))
+                } else {
+                    Some(weights.closest("", target))
+                }
+            }
+        }
+    }
+
+    /// Returns a weight about percent below the current weight (but not over it).
+    pub fn deload_weight(&self, weights: &Weights, percent: i32) -> Option<Weight> {
+        let (target, name) = self.base_weight();
+        let target = target.map(|t| t * (100 - percent) as f32 / 100.0);
+        if let Some(name) = name {
+            target.map(|t| weights.lower(name, t))
+        } else {
+            target.map(|t| weights.closest("", t))
+        }
+    }
+
     pub fn try_set_formal_name(&mut self, name: &str) -> Result<(), Error> {
         self.validate_formal_name(name)?;
         self.do_set_formal_name(name);
@@ -274,6 +436,26 @@ impl Exercise {
     //     self.do_set_weight_set(name);
     // }
 
+    /// Weight is the training max, percents are applied to it to get the weight for
+    /// each set. Nothing changes unless both are valid.
+    pub fn try_set_training_max(
+        &mut self,
+        weight: Option<f32>,
+        percents: Option<WeekPercents>,
+    ) -> Result<(), Error> {
+        self.validate_weight(weight)?;
+        self.validate_week_percents(&percents)?;
+        self.do_set_weight(weight);
+        self.data_mut().week_percents = percents;
+        Ok(())
+    }
+
+    pub fn try_set_amrap(&mut self, amrap: Option<AmrapRule>) -> Result<(), Error> {
+        self.validate_amrap(amrap)?;
+        self.data_mut().amrap = amrap;
+        Ok(())
+    }
+
     pub fn try_set_rest(&mut self, rest: Option<i32>) -> Result<(), Error> {
         self.validate_rest(rest)?;
         self.do_set_rest(rest);
@@ -333,15 +515,25 @@ impl Exercise {
         }
     }
 
-    fn target_weight(&self, index: SetIndex) -> (Option<f32>, &Option<String>) {
+    /// Returns the percent of the weight (or training max) to use for a set.
+    pub fn percent(&self, week: Option<i32>, index: SetIndex) -> i32 {
+        let percent = match self {
+            Exercise::Durations(_, _) => 100,
+            Exercise::FixedReps(_, e) => e.set(index).percent,
+            Exercise::VariableReps(_, e) => e.expected_range(index).percent,
+            Exercise::VariableSets(_, _) => 100,
+        };
+        match (&self.data().week_percents, index) {
+            (Some(percents), SetIndex::Workset(i)) => percents.percent(week, i).unwrap_or(percent),
+            _ => percent,
+        }
+    }
+
+    fn target_weight(&self, week: Option<i32>, index: SetIndex) -> (Option<f32>, &Option<String>) {
         match self {
             Exercise::Durations(d, _) => (d.weight, &d.weightset),
-            Exercise::FixedReps(d, e) => {
-                let percent = e.set(index).percent as f32;
-                (d.weight.map(|w| (percent * w) / 100.0), &d.weightset)
-            }
-            Exercise::VariableReps(d, e) => {
-                let percent = e.expected_range(index).percent as f32;
+            Exercise::FixedReps(d, _) | Exercise::VariableReps(d, _) => {
+                let percent = self.percent(week, index) as f32;
                 (d.weight.map(|w| (percent * w) / 100.0), &d.weightset)
             }
             Exercise::VariableSets(d, _) => (d.weight, &d.weightset),
@@ -416,6 +608,50 @@ impl Exercise {
         }
     }
 
+    fn validate_amrap(&self, amrap: Option<AmrapRule>) -> Result<(), Error> {
+        if let Some(amrap) = amrap {
+            if matches!(
+                self,
+                Exercise::Durations(_, _) | Exercise::VariableSets(_, _)
+            ) {
+                return validation_err!("AMRAP sets can only be used with reps exercises.");
+            }
+            match amrap {
+                AmrapRule::Steps(extra) if extra < 0 => {
+                    return validation_err!("Extra reps cannot be negative.");
+                }
+                AmrapRule::Estimate(percent) if percent <= 0 || percent > 100 => {
+                    return validation_err!("Max percent should be between 1 and 100.");
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_week_percents(&self, percents: &Option<WeekPercents>) -> Result<(), Error> {
+        if let Some(percents) = percents {
+            if matches!(
+                self,
+                Exercise::Durations(_, _) | Exercise::VariableSets(_, _)
+            ) {
+                return validation_err!("Week percents can only be used with reps exercises.");
+            }
+            if percents.0.is_empty() {
+                return validation_err!("There should be at least one week of percents.");
+            }
+            for week in percents.0.iter() {
+                if week.is_empty() {
+                    return validation_err!("Each week should have at least one percent.");
+                }
+                if week.iter().any(|p| *p <= 0) {
+                    return validation_err!("Percents should be larger than zero.");
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn validate_rest(&self, rest: Option<i32>) -> Result<(), Error> {
         if let Some(rest) = rest {
             if rest < 0 {
@@ -576,6 +812,8 @@ impl ExerciseData {
             weight: None,
             rest: None,
             last_rest: None,
+            week_percents: None,
+            amrap: None,
         }
     }
 }
@@ -591,3 +829,90 @@ impl fmt::Display for FormalName {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weights::WeightSet;
+
+    #[test]
+    fn week_percents() {
+        let percents = WeekPercents(vec![vec![65, 75, 85], vec![70, 80]]);
+        assert_eq!(percents.percent(None, 0), Some(65));
+        assert_eq!(percents.percent(Some(1), 2), Some(85));
+        assert_eq!(percents.percent(Some(2), 1), Some(80));
+        assert_eq!(percents.percent(Some(2), 2), Some(80)); // uses the last percent
+        assert_eq!(percents.percent(Some(3), 0), Some(65)); // weeks wrap
+        assert_eq!(WeekPercents(vec![]).percent(Some(1), 0), None);
+    }
+
+    #[test]
+    fn training_max() {
+        let name = ExerciseName("Squat".to_owned());
+        let formal = FormalName("Low bar Squat".to_owned());
+        let mut exercise =
+            BuildExercise::fixed_reps(name, formal, FixedRepsExercise::with_reps(vec![5; 3]))
+                .with_weight(200.0)
+                .finalize();
+        let percents = WeekPercents(vec![vec![65, 75, 85]]);
+        assert!(exercise
+            .try_set_training_max(Some(250.0), Some(percents.clone()))
+            .is_ok());
+        assert_eq!(exercise.data().weight, Some(250.0));
+
+        // A bad weight leaves the percents alone and vice versa.
+        let other = WeekPercents(vec![vec![70, 80, 90]]);
+        assert!(exercise
+            .try_set_training_max(Some(-1.0), Some(other))
+            .is_err());
+        assert_eq!(exercise.data().week_percents, Some(percents.clone()));
+        assert!(exercise
+            .try_set_training_max(Some(300.0), Some(WeekPercents(vec![])))
+            .is_err());
+        assert_eq!(exercise.data().weight, Some(250.0));
+        assert_eq!(exercise.data().week_percents, Some(percents));
+    }
+
+    #[test]
+    fn amrap() {
+        let mut weights = Weights::new();
+        let dumbbells: Vec<f32> = (2..=10).map(|i| 5.0 * i as f32).collect();
+        weights.add(
+            "Dumbbells".to_owned(),
+            WeightSet::Discrete(dumbbells, vec![]),
+        );
+        let name = ExerciseName("Curls".to_owned());
+        let formal = FormalName("Dumbbell Curls".to_owned());
+        let mut exercise =
+            BuildExercise::fixed_reps(name, formal, FixedRepsExercise::with_reps(vec![5; 3]))
+                .with_weightset("Dumbbells".to_owned())
+                .with_weight(30.0)
+                .finalize();
+        assert!(!exercise.is_amrap(SetIndex::Workset(2)));
+        assert!(exercise.try_set_amrap(Some(AmrapRule::Steps(2))).is_ok());
+        assert!(exercise.is_amrap(SetIndex::Workset(2)));
+        assert!(!exercise.is_amrap(SetIndex::Workset(1)));
+        assert_eq!(exercise.amrap_min(), Some(5));
+
+        let next = |e: &Exercise, reps| {
+            e.amrap_weight(&weights, OneRepMax::Epley, reps, 30.0)
+                .map(|w| w.value())
+        };
+        assert_eq!(next(&exercise, 5), Some(35.0));
+        assert_eq!(next(&exercise, 8), Some(40.0)); // one extra step
+        assert_eq!(next(&exercise, 9), Some(45.0)); // two extra steps
+        assert_eq!(next(&exercise, 4), Some(25.0)); // missed the min
+
+        // 30 x 5 is about a 35 lb max so 90% of that is a bit over 30
+        assert!(exercise
+            .try_set_amrap(Some(AmrapRule::Estimate(90)))
+            .is_ok());
+        assert_eq!(next(&exercise, 5), Some(30.0));
+        assert_eq!(next(&exercise, 10), Some(35.0));
+
+        assert!(exercise
+            .try_set_amrap(Some(AmrapRule::Estimate(0)))
+            .is_err());
+        assert!(exercise.try_set_amrap(Some(AmrapRule::Steps(-1))).is_err());
+    }
+}
@@ -4,11 +4,13 @@ use serde::{Deserialize, Serialize};
 
 /// Used for stuff like 3x60s planks. Target is used to signal the user to increase
 /// difficulty (typically by switching to a harder variant of the exercise or adding
-/// weight).
+/// weight). If there's a step then the user is offered the chance to increase secs by
+/// that much each time they complete all their sets until they reach the target.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DurationsExercise {
     secs: Vec<i32>,
-    target_secs: Option<i32>, // TODO: support this
+    target_secs: Option<i32>,
+    step_secs: Option<i32>,
 }
 
 impl DurationsExercise {
@@ -17,6 +19,7 @@ impl DurationsExercise {
         DurationsExercise {
             secs,
             target_secs: None,
+            step_secs: None,
         }
     }
 
@@ -27,6 +30,13 @@ impl DurationsExercise {
         }
     }
 
+    pub fn with_step_secs(self, secs: i32) -> DurationsExercise {
+        DurationsExercise {
+            step_secs: Some(secs),
+            ..self
+        }
+    }
+
     pub fn num_sets(&self) -> usize {
         self.secs.len()
     }
@@ -35,6 +45,45 @@ impl DurationsExercise {
         self.target_secs
     }
 
+    pub fn step(&self) -> Option<i32> {
+        self.step_secs
+    }
+
+    /// Returns true if every set in done (the times from the latest record) was held for
+    /// at least the target.
+    pub fn reached_target(&self, done: &[i32]) -> bool {
+        match self.target_secs {
+            Some(target) => !done.is_empty() && done.iter().all(|d| *d >= target),
+            None => false,
+        }
+    }
+
+    /// Returns the times to use after advancing or None if the exercise cannot be
+    /// advanced, i.e. there's no step or target, the sets are already at the target, or
+    /// not all the sets in done were completed.
+    pub fn advanced(&self, done: &[i32]) -> Option<Vec<i32>> {
+        let target = self.target_secs?;
+        let step = self.step_secs?;
+        if done.len() < self.secs.len() || done.iter().zip(self.secs.iter()).any(|(d, s)| d < s) {
+            return None;
+        }
+        if self.secs.iter().all(|s| *s >= target) {
+            return None;
+        }
+        Some(
+            self.secs
+                .iter()
+                .map(|s| {
+                    if *s >= target {
+                        *s
+                    } else {
+                        (s + step).min(target)
+                    }
+                })
+                .collect(),
+        )
+    }
+
     pub fn set(&self, index: SetIndex) -> i32 {
         match index {
             SetIndex::Workset(i) => self.secs[i],
@@ -64,6 +113,12 @@ impl DurationsExercise {
     //     self.do_set_target(target);
     // }
 
+    pub fn try_set_step(&mut self, step: Option<i32>) -> Result<(), Error> {
+        self.validate_step(step)?;
+        self.do_set_step(step);
+        Ok(())
+    }
+
     fn validate_durations(&self, durations: &Vec<i32>) -> Result<(), Error> {
         if durations.is_empty() {
             return validation_err!("durations cannot be empty");
@@ -98,4 +153,51 @@ impl DurationsExercise {
     fn do_set_target(&mut self, target: Option<i32>) {
         self.target_secs = target;
     }
+
+    fn validate_step(&self, step: Option<i32>) -> Result<(), Error> {
+        if let Some(step) = step {
+            if step < 0 {
+                return validation_err!("step cannot be negative");
+            }
+            if step == 0 {
+                return validation_err!("step cannot be zero");
+            }
+        }
+        Ok(())
+    }
+
+    fn do_set_step(&mut self, step: Option<i32>) {
+        self.step_secs = step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advancing() {
+        let e = DurationsExercise::new(vec![30, 45]);
+        assert_eq!(e.advanced(&[30, 45]), None); // no target or step
+        assert!(!e.reached_target(&[30, 45]));
+
+        let e = e.with_target_secs(60).with_step_secs(10);
+        assert_eq!(e.advanced(&[30, 45]), Some(vec![40, 55]));
+        assert_eq!(e.advanced(&[30, 50]), Some(vec![40, 55]));
+        assert_eq!(e.advanced(&[30]), None); // didn't do all the sets
+        assert_eq!(e.advanced(&[20, 45]), None); // didn't hold the first set long enough
+        assert!(!e.reached_target(&[30, 45]));
+
+        let e = DurationsExercise::new(vec![55, 60])
+            .with_target_secs(60)
+            .with_step_secs(10);
+        assert_eq!(e.advanced(&[55, 60]), Some(vec![60, 60])); // capped at the target
+
+        let e = DurationsExercise::new(vec![60, 60])
+            .with_target_secs(60)
+            .with_step_secs(10);
+        assert_eq!(e.advanced(&[60, 60]), None);
+        assert!(e.reached_target(&[60, 60]));
+        assert!(!e.reached_target(&[]));
+    }
 }
@@ -17,12 +17,28 @@ impl VariableReps {
     }
 }
 
-/// Used for stuff like 4-8 squats.
+/// How to drop the weight when the user stops making progress.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Deload {
+    pub sessions: i32, // number of stalled sessions in a row before deloading
+    pub percent: i32,  // how much to drop the weight by, e.g. 10
+}
+
+impl Deload {
+    pub fn new(sessions: i32, percent: i32) -> Deload {
+        Deload { sessions, percent }
+    }
+}
+
+/// Used for stuff like 4-8 squats. If there's a deload then the weight is lowered after
+/// the user stalls for too many sessions in a row, see stalled.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VariableRepsExercise {
     warmups: Vec<FixedReps>,
     worksets: Vec<VariableReps>,
     expected: Vec<i32>,
+    deload: Option<Deload>,
+    stalls: i32, // stalled sessions in a row
 }
 
 impl VariableRepsExercise {
@@ -32,6 +48,15 @@ impl VariableRepsExercise {
             warmups,
             worksets,
             expected: Vec::new(),
+            deload: None,
+            stalls: 0,
+        }
+    }
+
+    pub fn with_deload(self, deload: Deload) -> VariableRepsExercise {
+        VariableRepsExercise {
+            deload: Some(deload),
+            ..self
         }
     }
 
@@ -62,6 +87,59 @@ impl VariableRepsExercise {
         &self.expected
     }
 
+    pub fn deload(&self) -> Option<Deload> {
+        self.deload
+    }
+
+    pub fn stalls(&self) -> i32 {
+        self.stalls
+    }
+
+    /// Returns true if done (the reps from the latest session) isn't progress. Progress
+    /// is doing every set with at least min reps and either beating expected or reaching
+    /// max reps on every set (at which point the user should advance the weight).
+    pub fn stalled(&self, done: &[i32]) -> bool {
+        if done.len() < self.worksets.len() {
+            return true;
+        }
+        let min = self.min_expected();
+        if done.iter().zip(min.iter()).any(|(d, m)| d < m) {
+            return true;
+        }
+        let max = self.max_expected();
+        if done.iter().zip(max.iter()).all(|(d, m)| d >= m) {
+            return false;
+        }
+
+        let expected = if self.expected.len() == self.worksets.len() {
+            &self.expected
+        } else {
+            &min
+        };
+        let done: i32 = done.iter().take(self.worksets.len()).sum();
+        let expected: i32 = expected.iter().sum();
+        done <= expected
+    }
+
+    /// Called when the user finishes a session without advancing the weight. Returns
+    /// the deload to use if the user has now stalled too many times in a row.
+    pub fn record_session(&mut self, done: &[i32]) -> Option<Deload> {
+        if self.stalled(done) {
+            self.stalls += 1;
+        } else {
+            self.stalls = 0;
+        }
+
+        let deload = self.deload.filter(|d| self.stalls >= d.sessions)?;
+        self.stalls = 0;
+        Some(deload)
+    }
+
+    /// Called when the weight is advanced.
+    pub fn reset_stalls(&mut self) {
+        self.stalls = 0;
+    }
+
     /// What the user wants to do up to the most the user is expected to do. Can be larger
     /// than what they did last time, or sometimes even smaller.
     pub fn expected_range(&self, index: SetIndex) -> VariableReps {
@@ -114,6 +192,12 @@ impl VariableRepsExercise {
     //     self.do_set_warmups(warmups);
     // }
 
+    pub fn try_set_deload(&mut self, deload: Option<Deload>) -> Result<(), Error> {
+        self.validate_deload(deload)?;
+        self.do_set_deload(deload);
+        Ok(())
+    }
+
     pub fn try_set_worksets(&mut self, worksets: Vec<VariableReps>) -> Result<(), Error> {
         self.validate_worksets(&worksets)?;
         self.do_set_worksets(worksets);
@@ -172,6 +256,21 @@ impl VariableRepsExercise {
         Ok(())
     }
 
+    fn validate_deload(&self, deload: Option<Deload>) -> Result<(), Error> {
+        if let Some(deload) = deload {
+            if deload.sessions <= 0 {
+                return validation_err!("stalled sessions should be at least one");
+            }
+            if deload.percent <= 0 {
+                return validation_err!("deload percent should be larger than zero");
+            }
+            if deload.percent >= 100 {
+                return validation_err!("deload percent should be less than 100%");
+            }
+        }
+        Ok(())
+    }
+
     fn do_set_warmups(&mut self, warmups: Vec<FixedReps>) {
         self.warmups = warmups;
     }
@@ -179,5 +278,49 @@ impl VariableRepsExercise {
     fn do_set_worksets(&mut self, worksets: Vec<VariableReps>) {
         self.worksets = worksets;
         self.expected = Vec::new();
+        self.stalls = 0;
+    }
+
+    fn do_set_deload(&mut self, deload: Option<Deload>) {
+        self.deload = deload;
+        self.stalls = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stalls() {
+        let worksets = vec![VariableReps::new(4, 8, 100); 2];
+        let mut e = VariableRepsExercise::new(Vec::new(), worksets);
+        assert!(!e.stalled(&[5, 4])); // beat min
+        assert!(e.stalled(&[4, 4])); // didn't beat min
+        assert!(e.stalled(&[8, 3])); // missed min on a set
+        assert!(e.stalled(&[8])); // didn't do every set
+        assert!(!e.stalled(&[8, 8])); // time to advance
+
+        e.set_expected(vec![6, 5]);
+        assert!(e.stalled(&[6, 5]));
+        assert!(e.stalled(&[7, 4]));
+        assert!(!e.stalled(&[6, 6]));
+
+        // without a deload stalls are tracked but nothing happens
+        assert_eq!(e.record_session(&[6, 5]), None);
+        assert_eq!(e.stalls(), 1);
+
+        let deload = Deload::new(2, 10);
+        assert!(e.try_set_deload(Some(deload)).is_ok());
+        assert_eq!(e.stalls(), 0);
+        assert_eq!(e.record_session(&[6, 5]), None);
+        assert_eq!(e.record_session(&[7, 5]), None); // progress resets the count
+        assert_eq!(e.record_session(&[6, 5]), None);
+        assert_eq!(e.record_session(&[6, 4]), Some(deload));
+        assert_eq!(e.stalls(), 0);
+
+        assert!(e.try_set_deload(Some(Deload::new(0, 10))).is_err());
+        assert!(e.try_set_deload(Some(Deload::new(3, 0))).is_err());
+        assert!(e.try_set_deload(Some(Deload::new(3, 100))).is_err());
     }
 }
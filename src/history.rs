@@ -1,13 +1,15 @@
+use crate::days::Days;
 use crate::errors::Error;
 use crate::exercise::ExerciseName;
 use crate::internal_err;
-use chrono::{DateTime, Local};
+use crate::validation_err;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 const RECENT_MINS: i64 = 3 * 60;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum CompletedSets {
     Durations(Vec<(i32, Option<f32>)>),
     Reps(Vec<(i32, Option<f32>)>),
@@ -25,6 +27,14 @@ pub struct Record {
     pub id: u64,
 }
 
+/// A scheduled workout that the user decided not to do.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Skipped {
+    pub program: String,
+    pub workout: String,
+    pub date: NaiveDate,
+}
+
 /// Records details about the completion of each exercise. Note that this is shared across
 /// workouts and programs.
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,6 +42,7 @@ pub struct History {
     records: HashMap<ExerciseName, Vec<Record>>, // most recent record is last
     empty: Vec<Record>,
     next_id: u64,
+    skipped: Vec<Skipped>,
 }
 
 impl History {
@@ -40,6 +51,7 @@ impl History {
             records: HashMap::new(),
             empty: Vec::new(),
             next_id: 1,
+            skipped: Vec::new(),
         }
     }
 
@@ -117,11 +129,50 @@ impl History {
         }
     }
 
+    /// Adds a record imported from some other app (the record's id is ignored). Returns
+    /// false if the record was already present.
+    pub fn import(&mut self, exercise: &ExerciseName, mut record: Record) -> bool {
+        let records = self.records.entry(exercise.clone()).or_default();
+        let minute = record.started.timestamp() / 60;
+        if records
+            .iter()
+            .any(|r| r.started.timestamp() / 60 == minute && r.sets == record.sets)
+        {
+            return false;
+        }
+
+        record.id = self.next_id;
+        self.next_id += 1;
+        let index = records.partition_point(|r| r.started <= record.started);
+        records.insert(index, record);
+        true
+    }
+
+    pub fn exercise_names(&self) -> impl Iterator<Item = &ExerciseName> + '_ {
+        self.records.keys()
+    }
+
     /// Returns records from oldest to newest.
     pub fn records(&self, name: &ExerciseName) -> impl DoubleEndedIterator<Item = &Record> + '_ {
         self.records.get(name).unwrap_or(&self.empty).iter()
     }
 
+    /// Returns the records for every exercise sorted from oldest to newest.
+    pub fn all_records(&self) -> Vec<(&ExerciseName, &Record)> {
+        let mut records: Vec<_> = self
+            .records
+            .iter()
+            .flat_map(|(name, records)| records.iter().map(move |r| (name, r)))
+            .collect();
+        records.sort_by(|lhs, rhs| {
+            lhs.1
+                .started
+                .cmp(&rhs.1.started)
+                .then_with(|| lhs.0 .0.cmp(&rhs.0 .0))
+        });
+        records
+    }
+
     pub fn find_record(&self, exercise: &ExerciseName, id: u64) -> Result<&Record, Error> {
         if let Some(records) = self.records.get(exercise) {
             if let Some(r) = records.iter().rev().find(|r| r.id == id) {
@@ -150,6 +201,37 @@ impl History {
         }
     }
 
+    /// Removes the records with the given ids, returning how many were removed. Ids
+    /// that aren't found are ignored (e.g. from a stale page) but the record of an
+    /// exercise that's in progress cannot be removed.
+    pub fn try_delete_records(&mut self, ids: &HashSet<u64>) -> Result<usize, Error> {
+        self.validate_delete_records(ids)?;
+        Ok(self.do_delete_records(ids))
+    }
+
+    fn validate_delete_records(&self, ids: &HashSet<u64>) -> Result<(), Error> {
+        for (name, records) in self.records.iter() {
+            if records
+                .iter()
+                .any(|r| r.completed.is_none() && ids.contains(&r.id))
+            {
+                return validation_err!("Can't delete {name} while it's in progress.");
+            }
+        }
+        Ok(())
+    }
+
+    fn do_delete_records(&mut self, ids: &HashSet<u64>) -> usize {
+        let mut count = 0;
+        for records in self.records.values_mut() {
+            let old_len = records.len();
+            records.retain(|r| !ids.contains(&r.id));
+            count += old_len - records.len();
+        }
+        self.records.retain(|_, records| !records.is_empty());
+        count
+    }
+
     pub fn is_completed(&self, name: &ExerciseName) -> bool {
         self.records(name)
             .last()
@@ -180,6 +262,79 @@ impl History {
         None
     }
 
+    /// Returns when an exercise in program's workout was last finished. This is used as
+    /// the day the workout was last done.
+    pub fn workout_completed(&self, program: &str, workout: &str) -> Option<DateTime<Local>> {
+        self.records
+            .values()
+            .filter_map(|records| {
+                records
+                    .iter()
+                    .rev()
+                    .filter(|r| r.program == program && r.workout == workout)
+                    .find_map(|r| r.completed)
+            })
+            .max()
+    }
+
+    /// Returns the days on which an exercise in program's workout was finished.
+    pub fn completed_days(&self, program: &str, workout: &str) -> BTreeSet<NaiveDate> {
+        self.records
+            .values()
+            .flatten()
+            .filter(|r| r.program == program && r.workout == workout)
+            .filter_map(|r| r.completed.map(|d| d.date_naive()))
+            .collect()
+    }
+
+    /// Called when the program is renamed so that its sessions still count for scheduling.
+    pub fn rename_program(&mut self, old_name: &str, new_name: &str) {
+        for record in self.records.values_mut().flatten() {
+            if record.program == old_name {
+                record.program = new_name.to_owned();
+            }
+        }
+        for skipped in self.skipped.iter_mut() {
+            if skipped.program == old_name {
+                skipped.program = new_name.to_owned();
+            }
+        }
+    }
+
+    /// Records that the user skipped a scheduled workout.
+    pub fn skip(&mut self, program: &str, workout: &str, date: NaiveDate) {
+        let skipped = Skipped {
+            program: program.to_owned(),
+            workout: workout.to_owned(),
+            date,
+        };
+        if !self.skipped.contains(&skipped) {
+            self.skipped.push(skipped);
+        }
+    }
+
+    pub fn was_skipped(&self, program: &str, workout: &str, date: NaiveDate) -> bool {
+        self.skipped
+            .iter()
+            .any(|s| s.program == program && s.workout == workout && s.date == date)
+    }
+
+    /// Returns the number of days in date's week (which starts on Monday), up to and
+    /// including date, on which an exercise in program's workout was finished.
+    pub fn week_sessions(&self, program: &str, workout: &str, date: DateTime<Local>) -> usize {
+        let end = Days::new(date);
+        let start = end + -(date.weekday().num_days_from_monday() as i32);
+        let days: HashSet<Days> = self
+            .records
+            .values()
+            .flatten()
+            .filter(|r| r.program == program && r.workout == workout)
+            .filter_map(|r| r.completed.map(Days::new))
+            .filter(|d| start <= *d && *d <= end)
+            .collect();
+        days.len()
+    }
+
     /// Returns the oldest date at which a recently completed exercise in workout was
     /// started.
     pub fn first_started(&self, workout: &str) -> Option<DateTime<Local>> {
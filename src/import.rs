@@ -0,0 +1,508 @@
+//! Imports history from other lifting apps. Currently this supports the CSV files
+//! exported by Strong and FitNotes. Other apps use their own exercise names so the user
+//! maps those onto our names (see [`UserState::import_names`]) before records are added.
+//!
+//! [`UserState::import_names`]: crate::app_state::UserState
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::history::{CompletedSets, History, Record};
+use crate::validation_err;
+use crate::weights::LBS_PER_KG;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use std::collections::{BTreeSet, HashMap};
+
+/// History parsed from another app's export.
+#[derive(Debug)]
+pub struct Import {
+    pub source: &'static str, // e.g. "Strong"
+    exercises: Vec<ImportedExercise>,
+}
+
+/// All the sets for one exercise within one workout.
+#[derive(Debug)]
+struct ImportedExercise {
+    name: String, // the other app's name
+    workout: String,
+    started: DateTime<Local>,
+    completed: DateTime<Local>,
+    sets: CompletedSets,
+    comment: Option<String>,
+}
+
+/// Result of [`Import::apply`].
+#[derive(Debug, PartialEq)]
+pub struct ImportCounts {
+    pub added: usize,
+    pub duplicates: usize,
+    pub unmapped: usize, // records skipped because their exercise wasn't mapped
+}
+
+impl Import {
+    /// Parses a Strong or FitNotes CSV file.
+    pub fn parse(text: &str) -> Result<Import, Error> {
+        let text = text.trim_start_matches('\u{feff}'); // byte order mark
+        let header = text.lines().next().unwrap_or("");
+        let delimiter = if header.contains(';') && !header.contains(',') {
+            b';' // some versions of Strong use semicolons
+        } else {
+            b','
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let headers = match reader.headers() {
+            Ok(h) => h.clone(),
+            Err(e) => return validation_err!("Couldn't read the CSV header: {e}"),
+        };
+        let columns = Columns::new(&headers);
+
+        let mut rows = Vec::new();
+        for (i, row) in reader.records().enumerate() {
+            match row {
+                Ok(row) => rows.push(row),
+                Err(e) => return validation_err!("Couldn't read row {}: {e}", i + 2),
+            }
+        }
+
+        if columns.has(&["Workout Name", "Exercise Name", "Set Order"]) {
+            parse_strong(&columns, &rows)
+        } else if columns.has(&["Exercise", "Category", "Reps"]) {
+            parse_fitnotes(&columns, &rows)
+        } else {
+            validation_err!("Expected a CSV file exported from Strong or FitNotes.")
+        }
+    }
+
+    /// Returns the other app's exercise names sorted alphabetically.
+    pub fn names(&self) -> Vec<String> {
+        let names: BTreeSet<_> = self.exercises.iter().map(|e| e.name.clone()).collect();
+        names.into_iter().collect()
+    }
+
+    /// Adds records to history using mapping to convert exercise names. Exercises that
+    /// aren't mapped (or are mapped to an empty name) are skipped.
+    pub fn apply(self, history: &mut History, mapping: &HashMap<String, String>) -> ImportCounts {
+        let mut counts = ImportCounts {
+            added: 0,
+            duplicates: 0,
+            unmapped: 0,
+        };
+        for exercise in self.exercises {
+            let name = match mapping.get(&exercise.name) {
+                Some(name) if !name.trim().is_empty() => ExerciseName(name.trim().to_owned()),
+                _ => {
+                    counts.unmapped += 1;
+                    continue;
+                }
+            };
+            let record = Record {
+                program: self.source.to_owned(),
+                workout: exercise.workout,
+                started: exercise.started,
+                completed: Some(exercise.completed),
+                sets: Some(exercise.sets),
+                comment: exercise.comment,
+                id: 0,
+            };
+            if history.import(&name, record) {
+                counts.added += 1;
+            } else {
+                counts.duplicates += 1;
+            }
+        }
+        counts
+    }
+}
+
+struct Columns {
+    indexes: HashMap<String, usize>,
+}
+
+impl Columns {
+    fn new(headers: &csv::StringRecord) -> Columns {
+        let indexes = headers
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.trim().to_owned(), i))
+            .collect();
+        Columns { indexes }
+    }
+
+    fn has(&self, names: &[&str]) -> bool {
+        names.iter().all(|n| self.indexes.contains_key(*n))
+    }
+
+    /// Returns the first column in names that is present.
+    fn find<'a>(&self, names: &[&'a str]) -> Option<&'a str> {
+        names
+            .iter()
+            .find(|n| self.indexes.contains_key(**n))
+            .copied()
+    }
+
+    fn get<'a>(&self, row: &'a csv::StringRecord, name: &str) -> &'a str {
+        self.indexes
+            .get(name)
+            .and_then(|i| row.get(*i))
+            .map_or("", |s| s.trim())
+    }
+}
+
+/// Sets for an exercise are accumulated into one of these and then converted into an
+/// [`ImportedExercise`].
+struct Builder {
+    name: String,
+    workout: String,
+    started: DateTime<Local>,
+    completed: DateTime<Local>,
+    reps: Vec<(i32, Option<f32>)>,
+    durations: Vec<(i32, Option<f32>)>,
+    comments: Vec<String>,
+}
+
+impl Builder {
+    fn add(&mut self, reps: i32, secs: i32, weight: Option<f32>, comment: &str) {
+        if reps > 0 {
+            self.reps.push((reps, weight));
+        } else if secs > 0 {
+            self.durations.push((secs, weight));
+        }
+        if !comment.is_empty() && !self.comments.iter().any(|c| c == comment) {
+            self.comments.push(comment.to_owned());
+        }
+    }
+
+    // If an exercise somehow has both reps and durations then we go with whichever
+    // there are more of.
+    fn build(self) -> Option<ImportedExercise> {
+        let sets = if self.reps.is_empty() && self.durations.is_empty() {
+            return None; // e.g. cardio with just distance
+        } else if self.reps.len() >= self.durations.len() {
+            CompletedSets::Reps(self.reps)
+        } else {
+            CompletedSets::Durations(self.durations)
+        };
+        let comment = if self.comments.is_empty() {
+            None
+        } else {
+            Some(self.comments.join("; "))
+        };
+        Some(ImportedExercise {
+            name: self.name,
+            workout: self.workout,
+            started: self.started,
+            completed: self.completed,
+            sets,
+            comment,
+        })
+    }
+}
+
+/// Builders are keyed by (start time, exercise name) and exercises are kept in the order
+/// they first appeared.
+#[derive(Default)]
+struct Builders {
+    builders: Vec<Builder>,
+    index: HashMap<(DateTime<Local>, String), usize>,
+}
+
+impl Builders {
+    fn find(
+        &mut self,
+        started: DateTime<Local>,
+        name: &str,
+        make: impl FnOnce() -> Builder,
+    ) -> &mut Builder {
+        let key = (started, name.to_owned());
+        let i = match self.index.get(&key) {
+            Some(i) => *i,
+            None => {
+                self.builders.push(make());
+                self.index.insert(key, self.builders.len() - 1);
+                self.builders.len() - 1
+            }
+        };
+        &mut self.builders[i]
+    }
+
+    fn build(self, source: &'static str) -> Result<Import, Error> {
+        let exercises: Vec<_> = self
+            .builders
+            .into_iter()
+            .filter_map(|b| b.build())
+            .collect();
+        if exercises.is_empty() {
+            return validation_err!("Didn't find any sets to import.");
+        }
+        Ok(Import { source, exercises })
+    }
+}
+
+// Date,Workout Name,Duration,Exercise Name,Set Order,Weight,Reps,Distance,Seconds,Notes,Workout Notes,RPE
+// Older versions have Weight Unit and Workout Duration columns.
+fn parse_strong(columns: &Columns, rows: &[csv::StringRecord]) -> Result<Import, Error> {
+    let duration_column = columns.find(&["Duration", "Workout Duration"]);
+    let mut builders = Builders::default();
+    for (i, row) in rows.iter().enumerate() {
+        let line = i + 2;
+        if columns.get(row, "Set Order") == "Rest Timer" {
+            continue;
+        }
+        let name = columns.get(row, "Exercise Name");
+        if name.is_empty() {
+            continue;
+        }
+        let started = parse_date_time(columns.get(row, "Date"), line)?;
+        let duration = duration_column.map_or(0, |c| parse_duration(columns.get(row, c)));
+        let completed = started + Duration::seconds(duration as i64);
+
+        let kgs = columns.get(row, "Weight Unit").eq_ignore_ascii_case("kg");
+        let weight = parse_weight(columns.get(row, "Weight"), kgs, line)?;
+        let reps = parse_int(columns.get(row, "Reps"), line)?;
+        let secs = parse_int(columns.get(row, "Seconds"), line)?;
+        let workout = columns.get(row, "Workout Name");
+
+        let builder = builders.find(started, name, || Builder {
+            name: name.to_owned(),
+            workout: workout.to_owned(),
+            started,
+            completed,
+            reps: Vec::new(),
+            durations: Vec::new(),
+            comments: Vec::new(),
+        });
+        builder.add(reps, secs, weight, columns.get(row, "Notes"));
+    }
+    builders.build("Strong")
+}
+
+// Date,Exercise,Category,Weight (lbs),Reps,Distance,Distance Unit,Time,Comment
+// FitNotes only records dates so we pretend that everything happened at noon.
+fn parse_fitnotes(columns: &Columns, rows: &[csv::StringRecord]) -> Result<Import, Error> {
+    let (weight_column, kgs) = match columns.find(&["Weight (lbs)", "Weight (kgs)", "Weight (kg)"])
+    {
+        Some(c) => (c, c != "Weight (lbs)"),
+        None => ("Weight", false),
+    };
+    let mut builders = Builders::default();
+    for (i, row) in rows.iter().enumerate() {
+        let line = i + 2;
+        let name = columns.get(row, "Exercise");
+        if name.is_empty() {
+            continue;
+        }
+        let date = columns.get(row, "Date");
+        let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) => return validation_err!("Expected a date on row {line} but found '{date}'."),
+        };
+        let started = to_local(date.and_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+
+        let weight = parse_weight(columns.get(row, weight_column), kgs, line)?;
+        let reps = parse_int(columns.get(row, "Reps"), line)?;
+        let secs = parse_duration(columns.get(row, "Time"));
+        let category = columns.get(row, "Category");
+
+        let builder = builders.find(started, name, || Builder {
+            name: name.to_owned(),
+            workout: category.to_owned(),
+            started,
+            completed: started,
+            reps: Vec::new(),
+            durations: Vec::new(),
+            comments: Vec::new(),
+        });
+        builder.add(reps, secs, weight, columns.get(row, "Comment"));
+    }
+    builders.build("FitNotes")
+}
+
+fn parse_date_time(text: &str, line: usize) -> Result<DateTime<Local>, Error> {
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(to_local(date));
+        }
+    }
+    validation_err!("Expected a date and time on row {line} but found '{text}'.")
+}
+
+fn to_local(date: NaiveDateTime) -> DateTime<Local> {
+    // earliest handles times that are ambiguous because of daylight savings, the fallback
+    // is for times that don't exist because of daylight savings
+    Local
+        .from_local_datetime(&date)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&date))
+}
+
+fn parse_int(text: &str, line: usize) -> Result<i32, Error> {
+    if text.is_empty() {
+        return Ok(0);
+    }
+    match text.parse::<f32>() {
+        Ok(value) => Ok(value.round() as i32),
+        Err(_) => validation_err!("Expected a number on row {line} but found '{text}'."),
+    }
+}
+
+// Weights of zero are treated as body weight exercises.
+fn parse_weight(text: &str, kgs: bool, line: usize) -> Result<Option<f32>, Error> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    match text.parse::<f32>() {
+        Ok(value) if value > 0.0 && kgs => Ok(Some((value * LBS_PER_KG * 10.0).round() / 10.0)),
+        Ok(value) if value > 0.0 => Ok(Some(value)),
+        Ok(_) => Ok(None),
+        Err(_) => validation_err!("Expected a weight on row {line} but found '{text}'."),
+    }
+}
+
+/// Parses durations like "1h 5m", "45s", or "1:05:00" into seconds. Returns zero if the
+/// duration cannot be parsed.
+fn parse_duration(text: &str) -> i32 {
+    if text.contains(':') {
+        return text
+            .split(':')
+            .try_fold(0, |total, part| {
+                part.trim().parse::<i32>().map(|n| 60 * total + n)
+            })
+            .unwrap_or(0);
+    }
+
+    let mut total = 0;
+    for part in text.split_whitespace() {
+        let unit = part.chars().last().unwrap_or(' ');
+        let value = &part[..part.len() - unit.len_utf8()];
+        let scale = match unit {
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return part.parse().unwrap_or(0),
+        };
+        total += scale * value.parse::<i32>().unwrap_or(0);
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn strong() {
+        let text = "\
+Date,Workout Name,Duration,Exercise Name,Set Order,Weight,Reps,Distance,Seconds,Notes,Workout Notes,RPE
+2024-03-04 18:10:00,Push,1h 5m,Bench Press (Barbell),1,135,5,0,0,,,
+2024-03-04 18:10:00,Push,1h 5m,Bench Press (Barbell),2,185,3,0,0,\"easy, fast\",,
+2024-03-04 18:10:00,Push,1h 5m,Bench Press (Barbell),Rest Timer,0,0,0,90,,,
+2024-03-04 18:10:00,Push,1h 5m,Plank,1,0,0,0,60,,,
+2024-03-06 07:00:00,Legs,45m,Squat (Barbell),1,225,5,0,0,,,
+";
+        let import = Import::parse(text).unwrap_or_else(|_| panic!("parse failed"));
+        assert_eq!(import.source, "Strong");
+        assert_eq!(
+            import.names(),
+            vec!["Bench Press (Barbell)", "Plank", "Squat (Barbell)"]
+        );
+
+        let bench = &import.exercises[0];
+        assert_eq!(bench.workout, "Push");
+        assert_eq!(bench.completed - bench.started, Duration::minutes(65));
+        assert_eq!(
+            bench.sets,
+            CompletedSets::Reps(vec![(5, Some(135.0)), (3, Some(185.0))])
+        );
+        assert_eq!(bench.comment.as_deref(), Some("easy, fast"));
+        assert_eq!(
+            import.exercises[1].sets,
+            CompletedSets::Durations(vec![(60, None)])
+        );
+
+        let mut history = History::new();
+        let mapping = mapping(&[("Bench Press (Barbell)", "Bench"), ("Plank", "")]);
+        let counts = import.apply(&mut history, &mapping);
+        assert_eq!(
+            counts,
+            ImportCounts {
+                added: 1,
+                duplicates: 0,
+                unmapped: 2
+            }
+        );
+        let records: Vec<_> = history.records(&ExerciseName("Bench".to_owned())).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].program, "Strong");
+
+        // Importing the same file again doesn't add anything.
+        let import = Import::parse(text).unwrap_or_else(|_| panic!("parse failed"));
+        let counts = import.apply(&mut history, &mapping);
+        assert_eq!(counts.added, 0);
+        assert_eq!(counts.duplicates, 1);
+    }
+
+    #[test]
+    fn fitnotes() {
+        let text = "\
+Date,Exercise,Category,Weight (kgs),Reps,Distance,Distance Unit,Time,Comment
+2024-03-05,Deadlift,Back,100.0,5,,,,
+2024-03-05,Deadlift,Back,100.0,5,,,,
+2024-03-05,Dead Hang,Back,,,,,0:01:30,
+2024-03-07,Deadlift,Back,110.0,3,,,,PR
+";
+        let import = Import::parse(text).unwrap_or_else(|_| panic!("parse failed"));
+        assert_eq!(import.source, "FitNotes");
+        assert_eq!(import.exercises.len(), 3);
+        assert_eq!(
+            import.exercises[0].sets,
+            CompletedSets::Reps(vec![(5, Some(220.5)), (5, Some(220.5))])
+        );
+        assert_eq!(
+            import.exercises[1].sets,
+            CompletedSets::Durations(vec![(90, None)])
+        );
+        assert_eq!(import.exercises[2].comment.as_deref(), Some("PR"));
+
+        // Imported records are kept in date order.
+        let mut history = History::new();
+        let name = ExerciseName("Deadlift".to_owned());
+        history.start("My", "Pull", &name, Local::now());
+        history.append_reps(&name, 5, Some(315.0));
+        history.finish(&name, Local::now());
+
+        let counts = import.apply(&mut history, &mapping(&[("Deadlift", "Deadlift")]));
+        assert_eq!(counts.added, 2);
+        let weights: Vec<_> = history
+            .records(&name)
+            .map(|r| match &r.sets {
+                Some(CompletedSets::Reps(sets)) => sets[0].1,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(weights, vec![Some(220.5), Some(242.5), Some(315.0)]);
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(Import::parse("foo,bar\n1,2\n").is_err());
+        assert!(Import::parse("").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("1h 5m"), 65 * 60);
+        assert_eq!(parse_duration("45s"), 45);
+        assert_eq!(parse_duration("0:01:30"), 90);
+        assert_eq!(parse_duration("3600"), 3600);
+        assert_eq!(parse_duration(""), 0);
+        assert_eq!(parse_duration("bogus"), 0);
+    }
+}
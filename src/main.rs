@@ -1,35 +1,50 @@
+mod accounts;
 mod app_state;
+mod config;
 mod days;
 mod default;
 mod errors;
 mod exercise;
 mod history;
+mod import;
+mod migrations;
 mod notes;
+mod options;
 mod pages;
 mod persist;
+mod plate_plan;
 mod program;
+mod strength;
 mod weights;
 mod workout;
 
-use app_state::SharedState;
+use app_state::{ServerState, SharedServer, SharedState};
 use axum::{
-    extract::{Extension, Path, Query},
+    extract::{DefaultBodyLimit, Extension, Multipart, Path, Query, Request},
     http::{header, HeaderMap, StatusCode},
-    response::IntoResponse,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Form, Router,
 };
+use chrono::NaiveDate;
+use clap::Parser;
+use config::{Args, Command, Config};
 use errors::{Error, Unwrapper};
 use handlebars::Handlebars;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::RwLock;
 use tower::ServiceBuilder;
 use tower_http::add_extension::AddExtensionLayer;
 
 use crate::exercise::{
-    BuildExercise, DurationsExercise, Exercise, ExerciseName, FixedReps, FixedRepsExercise,
-    FormalName, VariableReps, VariableRepsExercise, VariableSetsExercise,
+    AmrapRule, BuildExercise, Deload, DurationsExercise, Exercise, ExerciseName, FixedReps,
+    FixedRepsExercise, FormalName, VariableReps, VariableRepsExercise, VariableSetsExercise,
+    WeekPercents,
 };
+use crate::strength::OneRepMax;
+use crate::weights::{AddOn, Plate, SetSource, Units, WeightSet};
 
 #[tokio::main]
 async fn main() {
@@ -39,68 +54,135 @@ async fn main() {
     // using port 80 (or 443).
     tracing_subscriber::fmt::init();
 
-    let t0 = tokio::task::spawn(async move { serve(3000).await });
-    let t1 = tokio::task::spawn(async move { serve(3001).await });
-    let t2 = tokio::task::spawn(async move { serve(3002).await });
-    let t3 = tokio::task::spawn(async move { serve(3010).await });
-    let _ = tokio::join!(t0, t1, t2, t3);
+    let args = Args::parse();
+    let config = match Config::load(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("bad config: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    match args.command {
+        Some(Command::AddUser { name, example }) => {
+            if let Err(e) = add_user(&config, &name, example) {
+                eprintln!("failed to add user: {e}");
+                std::process::exit(1);
+            }
+        }
+        None => serve(config).await,
+    }
 }
 
-async fn serve(port: u16) {
-    let state = if port == 3001 {
-        default::make_her_program()
-    } else if port == 3002 {
-        default::make_test_program()
-    } else {
-        default::make_my_program()
+fn add_user(config: &Config, name: &str, example: bool) -> Result<(), String> {
+    let mut accounts = persist::load_accounts(&config.data_dir).map_err(|e| e.to_string())?;
+    let password = rpassword::prompt_password("Password: ").map_err(|e| e.to_string())?;
+    let confirm = rpassword::prompt_password("Confirm password: ").map_err(|e| e.to_string())?;
+    if password != confirm {
+        return Err("passwords didn't match".to_owned());
+    }
+    if let Err(Error::ValidationError(e) | Error::InternalError(e)) =
+        accounts.try_set_password(name, &password)
+    {
+        return Err(e);
+    }
+
+    // Existing data (e.g. from before there were accounts) is left alone.
+    if !persist::data_exists(&config.data_dir, name) {
+        let state = default::make_state(&config.data_dir, name, example);
+        persist::save(&config.data_dir, name, &state.user).map_err(|e| e.to_string())?;
+    }
+    persist::save_accounts(&config.data_dir, &accounts).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn serve(config: Config) {
+    let accounts = match persist::load_accounts(&config.data_dir) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("failed to load accounts: {e}");
+            std::process::exit(1);
+        }
     };
-    let router = Router::new()
+    if !accounts.any() {
+        eprintln!("there are no users, use the add-user command to add one");
+    }
+    accounts::dummy_hash(); // so that the first login with an unknown name isn't slower
+    let server = ServerState::new(config.data_dir.clone(), accounts);
+
+    let public = Router::new()
         // data --------------------------------------------------------------------------
         .route(
             "/scripts/discrete.js",
-            get(|s| get_js(s, include_str!("../files/discrete.js"))),
+            get(|| get_js(include_str!("../files/discrete.js"))),
         )
         .route(
             "/scripts/exercise.js",
-            get(|s| get_js(s, include_str!("../files/exercise.js"))),
+            get(|| get_js(include_str!("../files/exercise.js"))),
         )
         .route(
             "/scripts/editable-list.js",
-            get(|s| get_js(s, include_str!("../files/editable-list.js"))),
+            get(|| get_js(include_str!("../files/editable-list.js"))),
         )
         .route(
             "/scripts/formal_name.js",
-            get(|s| get_js(s, include_str!("../files/formal_name.js"))),
+            get(|| get_js(include_str!("../files/formal_name.js"))),
         )
         .route(
             "/scripts/rest.js",
-            get(|s| get_js(s, include_str!("../files/rest.js"))),
+            get(|| get_js(include_str!("../files/rest.js"))),
         )
         .route(
             "/scripts/durations.js",
-            get(|s| get_js(s, include_str!("../files/durations.js"))),
+            get(|| get_js(include_str!("../files/durations.js"))),
         )
         .route(
             "/styles/style.css",
-            get(|s| get_css(s, include_str!("../files/styles.css"))),
+            get(|| get_css(include_str!("../files/styles.css"))),
         )
+        .route("/calendar.ics", get(get_calendar_feed))
+        .route("/login", get(get_login))
+        .route("/login", post(post_login))
+        .route("/logout", post(post_logout));
+
+    let router = Router::new()
         // get ---------------------------------------------------------------------------
         .route("/", get(get_program))
         .route("/show-overview", get(get_overview))
+        .route("/export/history.csv", get(get_export_csv))
+        .route("/export/history.json", get(get_export_json))
+        .route("/import", get(get_import))
+        .route("/history", get(get_history))
+        .route("/calendar", get(get_calendar))
+        .route("/calendar/:date", get(get_calendar_day))
+        .route("/calendar-feed", get(get_calendar_feed_page))
         .route("/add-workout", get(get_edit_add_workout))
         .route("/edit-discrete-weights", get(get_discrete_weights))
         .route("/edit-plate-weights", get(get_plate_weights))
+        .route("/edit-single-plate-weights", get(get_single_plate_weights))
         .route("/edit-blocks", get(get_blocks))
         .route("/edit-block/:name", get(get_edit_block))
         .route("/edit-week", get(get_edit_set_week))
         .route("/edit-program-name", get(get_edit_program_name))
         .route("/edit-program-notes", get(get_edit_program_notes))
+        .route("/edit-options", get(get_edit_options))
+        .route("/edit-missed", get(get_edit_missed))
         .route("/edit-workouts", get(get_edit_edit_workouts))
         .route("/workout/:name", get(get_workout))
         .route("/schedule-daily/:workout", get(get_schedule_daily))
         .route("/edit-schedule-nth/:workout", get(get_schedule_nth))
         .route("/edit-schedule-weekday/:workout", get(get_schedule_weekday))
+        .route(
+            "/edit-schedule-rotation/:workout",
+            get(get_schedule_rotation),
+        )
+        .route(
+            "/edit-schedule-per-week/:workout",
+            get(get_schedule_per_week),
+        )
+        .route("/edit-schedule-dates/:workout", get(get_schedule_dates))
         .route("/exercise/:workout/:exercise", get(get_exercise))
+        .route("/chart/:workout/:exercise", get(get_chart))
         .route("/add-exercise/:workout", get(get_add_exercise))
         .route("/edit-workout-name/:workout", get(get_edit_workout_name))
         .route("/edit-exercises/:workout", get(get_edit_exercises))
@@ -110,6 +192,11 @@ async fn serve(port: u16) {
             get(get_edit_formal_name),
         )
         .route("/edit-weight/:workout/:exercise", get(get_edit_weight))
+        .route(
+            "/edit-training-max/:workout/:exercise",
+            get(get_edit_training_max),
+        )
+        .route("/edit-amrap/:workout/:exercise", get(get_edit_amrap))
         .route(
             "/edit-any-weight/:workout/:exercise",
             get(get_edit_any_weight),
@@ -122,6 +209,10 @@ async fn serve(port: u16) {
             "/edit-plates-weight/:workout/:exercise",
             get(get_edit_plate_set),
         )
+        .route(
+            "/edit-single-plates-weight/:workout/:exercise",
+            get(get_edit_single_plate_set),
+        )
         .route(
             "/edit-durations/:workout/:exercise",
             get(get_edit_durations),
@@ -144,11 +235,27 @@ async fn serve(port: u16) {
         )
         .route("/edit-current-set/:workout/:exercise", get(get_current_set))
         // post --------------------------------------------------------------------------
+        .route(
+            "/import",
+            post(post_import_file).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/import-names", post(post_import_names))
+        .route("/delete-records", post(post_delete_records))
+        .route("/cancel-import", post(post_cancel_import))
         .route("/set-program-name", post(post_set_program_name))
         .route("/set-week", post(post_set_week))
         .route("/set-notes", post(post_set_notes))
+        .route("/set-options", post(post_set_options))
+        .route("/set-missed", post(post_set_missed))
+        .route("/reset-calendar-token", post(post_reset_calendar_token))
+        .route("/skip-missed/:workout/:date", post(post_skip_missed))
+        .route("/slide-missed/:workout/:date", post(post_slide_missed))
         .route("/set-discrete-weights", post(post_set_discrete_weights))
         .route("/set-plate-weights", post(post_set_plate_weights))
+        .route(
+            "/set-single-plate-weights",
+            post(post_set_single_plate_weights),
+        )
         .route("/set-blocks", post(post_set_blocks))
         .route("/set-block/:name", post(post_set_block))
         .route("/set-workouts", post(post_set_workouts))
@@ -164,6 +271,18 @@ async fn serve(port: u16) {
             "/set-schedule-weekdays/:workout",
             post(post_set_schedule_weekdays),
         )
+        .route(
+            "/set-schedule-rotation/:workout",
+            post(post_set_schedule_rotation),
+        )
+        .route(
+            "/set-schedule-per-week/:workout",
+            post(post_set_schedule_per_week),
+        )
+        .route(
+            "/set-schedule-dates/:workout",
+            post(post_set_schedule_dates),
+        )
         .route("/reset/exercise/:workout/:exercise", post(reset_exercise))
         .route("/append-exercise/:workout", post(post_append_exercise))
         .route("/set-exercises/:workout", post(post_set_exercises))
@@ -176,6 +295,11 @@ async fn serve(port: u16) {
             post(post_set_formal_name),
         )
         .route("/set-weight/:workout/:exercise", post(post_set_weight))
+        .route(
+            "/set-training-max/:workout/:exercise",
+            post(post_set_training_max),
+        )
+        .route("/set-amrap/:workout/:exercise", post(post_set_amrap))
         .route(
             "/set-discrete-set/:workout/:exercise",
             post(post_set_discrete_set),
@@ -184,6 +308,10 @@ async fn serve(port: u16) {
             "/set-plates-set/:workout/:exercise",
             post(post_set_plates_set),
         )
+        .route(
+            "/set-single-plates-set/:workout/:exercise",
+            post(post_set_single_plates_set),
+        )
         .route("/revert-note/:workout/:exercise", post(post_revert_note))
         .route("/set-note/:workout/:exercise", post(post_set_note))
         .route(
@@ -214,18 +342,158 @@ async fn serve(port: u16) {
             post(post_set_reps_record),
         )
         // layer -------------------------------------------------------------------------
+        .route_layer(middleware::from_fn(require_user))
+        .merge(public)
         .layer(
             ServiceBuilder::new() // TODO: more stuff at https://github.com/tokio-rs/axum/blob/dea36db400f27c025b646e5720b9a6784ea4db6e/examples/key-value-store/src/main.rs
-                .layer(AddExtensionLayer::new(SharedState::new(RwLock::new(state))))
+                .layer(AddExtensionLayer::new(SharedServer::new(RwLock::new(
+                    server,
+                ))))
                 .into_inner(),
         );
 
-    let addr = &format!("0.0.0.0:{port}");
+    let addr = &format!("{}:{}", config.address, config.port);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, router).await.unwrap();
 }
 
-async fn get_css(Extension(_state): Extension<SharedState>, contents: &str) -> impl IntoResponse {
+/// Finds the [`SharedState`] for the user logged into the request's session and adds it
+/// to the request so that handlers can extract it. Requests without a valid session are
+/// redirected to the login page.
+async fn require_user(
+    Extension(server): Extension<SharedServer>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let state = request
+        .headers()
+        .get(header::COOKIE)
+        .and_then(|c| c.to_str().ok())
+        .and_then(accounts::find_token)
+        .and_then(|token| server.write().unwrap().find_user(token));
+    match state {
+        Some(state) => {
+            request.extensions_mut().insert(state);
+            next.run(request).await
+        }
+        None => {
+            let mut headers = HeaderMap::new();
+            headers.insert("Location", "/login".parse().unwrap());
+            (StatusCode::SEE_OTHER, headers).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginQuery {
+    failed: Option<bool>,
+}
+
+async fn get_login(
+    Extension(server): Extension<SharedServer>,
+    Query(query): Query<LoginQuery>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_login_page(server, query.failed.unwrap_or(false))?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct Login {
+    name: String,
+    password: String,
+}
+
+async fn post_login(
+    Extension(server): Extension<SharedServer>,
+    Form(payload): Form<Login>,
+) -> impl IntoResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+
+    // Hashing is deliberately slow so it's done without holding the server lock (which
+    // every request needs) and off of the async workers. Unknown names are checked against
+    // a dummy hash so that response times don't reveal which names exist.
+    let hash = server.read().unwrap().accounts.password_hash(&payload.name);
+    let known = hash.is_some();
+    let hash = hash.unwrap_or_else(|| accounts::dummy_hash().to_owned());
+    let password = payload.password;
+    let verified = tokio::task::spawn_blocking(move || accounts::verify_password(&hash, &password))
+        .await
+        .unwrap_or(false)
+        && known;
+    if verified {
+        let token = server.write().unwrap().sessions.create(&payload.name);
+        let max_age = accounts::SESSION_DAYS * 24 * 60 * 60;
+        let cookie = format!(
+            "{}={token}; Max-Age={max_age}; Path=/; HttpOnly; SameSite=Lax",
+            accounts::SESSION_COOKIE
+        );
+        headers.insert(header::SET_COOKIE, cookie.parse().unwrap());
+        headers.insert("Location", "/".parse().unwrap());
+    } else {
+        headers.insert("Location", "/login?failed=true".parse().unwrap());
+    }
+    (StatusCode::SEE_OTHER, headers)
+}
+
+async fn post_logout(
+    Extension(server): Extension<SharedServer>,
+    request_headers: HeaderMap,
+) -> impl IntoResponse {
+    let token = request_headers
+        .get(header::COOKIE)
+        .and_then(|c| c.to_str().ok())
+        .and_then(accounts::find_token);
+    if let Some(token) = token {
+        server.write().unwrap().sessions.remove(token);
+    }
+
+    let mut headers = HeaderMap::new();
+    let cookie = format!("{}=; Max-Age=0; Path=/", accounts::SESSION_COOKIE);
+    headers.insert(header::SET_COOKIE, cookie.parse().unwrap());
+    headers.insert("Location", "/login".parse().unwrap());
+    (StatusCode::SEE_OTHER, headers)
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarFeedQuery {
+    token: String,
+    weeks: Option<i64>,
+}
+
+/// Public so that calendar apps can subscribe, the token identifies the user.
+async fn get_calendar_feed(
+    Extension(server): Extension<SharedServer>,
+    Query(query): Query<CalendarFeedQuery>,
+) -> Result<Response, Error> {
+    let state = server.write().unwrap().find_calendar_user(&query.token);
+    let Some(state) = state else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let weeks = query.weeks.unwrap_or(pages::DEFAULT_FEED_WEEKS);
+    let contents = pages::get_calendar_feed(state, weeks)?;
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store, must-revalidate"),
+        ],
+        contents,
+    )
+        .into_response())
+}
+
+async fn get_css(contents: &str) -> impl IntoResponse {
     (
         StatusCode::OK,
         [(header::CONTENT_TYPE, "text/css")],
@@ -233,7 +501,7 @@ async fn get_css(Extension(_state): Extension<SharedState>, contents: &str) -> i
     )
 }
 
-async fn get_js(Extension(_state): Extension<SharedState>, contents: &str) -> impl IntoResponse {
+async fn get_js(contents: &str) -> impl IntoResponse {
     (
         StatusCode::OK,
         [(header::CONTENT_TYPE, "text/javascript")],
@@ -241,6 +509,83 @@ async fn get_js(Extension(_state): Extension<SharedState>, contents: &str) -> im
     )
 }
 
+async fn get_export_csv(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_history_csv(state)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"history.csv\"",
+            ),
+            (header::CACHE_CONTROL, "no-store, must-revalidate"),
+        ],
+        contents,
+    ))
+}
+
+async fn get_export_json(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_history_json(state)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"history.json\"",
+            ),
+            (header::CACHE_CONTROL, "no-store, must-revalidate"),
+        ],
+        contents,
+    ))
+}
+
+async fn get_history(
+    Extension(state): Extension<SharedState>,
+    Query(filter): Query<pages::HistoryFilter>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_history_page(state, &filter)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportQuery {
+    added: Option<usize>,
+    duplicates: Option<usize>,
+    skipped: Option<usize>,
+}
+
+async fn get_import(
+    Extension(state): Extension<SharedState>,
+    Query(query): Query<ImportQuery>,
+) -> Result<impl IntoResponse, Error> {
+    let message = match query.added {
+        Some(added) => format!(
+            "Imported {added} records ({} duplicates and {} unmapped records were skipped).",
+            query.duplicates.unwrap_or(0),
+            query.skipped.unwrap_or(0)
+        ),
+        None => String::new(),
+    };
+    let contents = pages::get_import_page(state, message)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_program(Extension(state): Extension<SharedState>) -> Result<impl IntoResponse, Error> {
     let contents = pages::get_program_page(state)?;
     Ok((
@@ -353,6 +698,48 @@ async fn get_schedule_weekday(
     ))
 }
 
+async fn get_schedule_rotation(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_schedule_rotation(state, &workout);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_schedule_per_week(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_schedule_per_week(state, &workout);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_schedule_dates(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_schedule_dates(state, &workout);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_exercise(
     Path((workout, exercise)): Path<(String, String)>,
     Extension(state): Extension<SharedState>,
@@ -367,6 +754,80 @@ async fn get_exercise(
     ))
 }
 
+#[derive(Debug, Deserialize)]
+struct CalendarQuery {
+    month: Option<String>, // "2024-03"
+}
+
+async fn get_calendar(
+    Query(query): Query<CalendarQuery>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let month = query.month.unwrap_or_default();
+    let contents = pages::get_calendar_page(state, &month)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_calendar_day(
+    Path(date): Path<String>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_calendar_day_page(state, &date)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_calendar_feed_page(
+    Extension(server): Extension<SharedServer>,
+    Extension(state): Extension<SharedState>,
+    request_headers: HeaderMap,
+) -> Result<impl IntoResponse, Error> {
+    let host = request_headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("localhost");
+    let contents = pages::get_calendar_feed_page(server, state, host)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartQuery {
+    range: Option<String>,
+}
+
+async fn get_chart(
+    Path((workout, exercise)): Path<(String, String)>,
+    Query(query): Query<ChartQuery>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let range = query.range.unwrap_or("all".to_owned());
+    let contents = pages::get_chart_page(state, &workout, &exercise, &range)?;
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_add_exercise(
     Path(workout): Path<String>,
     Extension(_state): Extension<SharedState>,
@@ -421,6 +882,19 @@ async fn get_discrete_weights(
     ))
 }
 
+async fn get_single_plate_weights(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_single_plate_weights(state);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_plate_weights(
     Extension(state): Extension<SharedState>,
 ) -> Result<impl IntoResponse, Error> {
@@ -472,6 +946,32 @@ async fn get_edit_set_week(
     ))
 }
 
+async fn get_edit_options(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_options(state);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_edit_missed(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_missed(state);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_edit_add_workout(
     Extension(_state): Extension<SharedState>,
 ) -> Result<impl IntoResponse, Error> {
@@ -551,6 +1051,34 @@ async fn get_edit_weight(
     ))
 }
 
+async fn get_edit_training_max(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_training_max(state, &workout, &exercise);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
+async fn get_edit_amrap(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_amrap(state, &workout, &exercise);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_edit_durations(
     Path((workout, exercise)): Path<(String, String)>,
     Extension(state): Extension<SharedState>,
@@ -697,6 +1225,20 @@ async fn get_edit_discrete_set(
     ))
 }
 
+async fn get_edit_single_plate_set(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let contents = pages::get_edit_single_plate_set(state, &workout, &exercise);
+    Ok((
+        [
+            ("Cache-Control", "no-store, must-revalidate"),
+            ("Expires", "0"),
+        ],
+        axum::response::Html(contents),
+    ))
+}
+
 async fn get_edit_plate_set(
     Path((workout, exercise)): Path<(String, String)>,
     Extension(state): Extension<SharedState>,
@@ -735,10 +1277,19 @@ struct VarRepsOptions {
 // After posts we do a redirect to a GET page. This prevents silly issues like duplicate
 // POSTs when the user presses the refresh button. See https://www.theserverside.com/news/1365146/Redirect-After-Post
 // for more.
+#[derive(Debug, Deserialize)]
+struct NextSetOptions {
+    advance: Option<i32>, // for durations
+}
+
 async fn post_next_set(
     Path((workout, exercise)): Path<(String, String)>,
+    options: Query<NextSetOptions>,
     Extension(state): Extension<SharedState>,
 ) -> Result<impl IntoResponse, Error> {
+    if options.advance == Some(1) {
+        pages::post_advance_durations(&state, &workout, &exercise)?;
+    }
     let new_url = pages::post_next_exercise(state, &workout, &exercise, None)?;
 
     let mut headers = HeaderMap::new();
@@ -873,14 +1424,44 @@ async fn post_append_exercise(
 
 #[derive(Debug, Deserialize)]
 struct EditWeights {
-    sets: String, // "Block 1¦Block 2"
+    sets: String,    // "Block 1¦Block 2"
+    sources: String, // "set:Block 1¦new", see parse_weight_sets
+}
+
+/// Each source is "new", "set:<old name>" for an existing set, or "copy:<old name>" for
+/// a duplicated set.
+fn parse_weight_sets(payload: &EditWeights) -> Result<Vec<(String, SetSource)>, Error> {
+    if payload.sets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let names: Vec<_> = payload.sets.split("¦").collect();
+    let sources: Vec<_> = payload.sources.split("¦").collect();
+    if names.len() != sources.len() {
+        return validation_err!("Expected a source for each weight set.");
+    }
+
+    let mut sets = Vec::with_capacity(names.len());
+    for (name, source) in names.into_iter().zip(sources) {
+        let source = if source == "new" {
+            SetSource::New
+        } else if let Some(old) = source.strip_prefix("set:") {
+            SetSource::Existing(old.to_owned())
+        } else if let Some(old) = source.strip_prefix("copy:") {
+            SetSource::Copy(old.to_owned())
+        } else {
+            return validation_err!("Bad weight set source '{source}'.");
+        };
+        sets.push((name.to_owned(), source));
+    }
+    Ok(sets)
 }
 
 async fn post_set_discrete_weights(
     Extension(state): Extension<SharedState>,
     Form(payload): Form<EditWeights>,
 ) -> Result<impl IntoResponse, Error> {
-    let sets: Vec<_> = payload.sets.split("¦").map(|s| s.to_string()).collect();
+    let sets = parse_weight_sets(&payload)?;
     let new_url = pages::post_set_discrete_weights(state, sets)?;
 
     let mut headers = HeaderMap::new();
@@ -893,11 +1474,28 @@ async fn post_set_discrete_weights(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
+async fn post_set_single_plate_weights(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<EditWeights>,
+) -> Result<impl IntoResponse, Error> {
+    let sets = parse_weight_sets(&payload)?;
+    let new_url = pages::post_set_single_plate_weights(state, sets)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
 async fn post_set_plate_weights(
     Extension(state): Extension<SharedState>,
     Form(payload): Form<EditWeights>,
 ) -> Result<impl IntoResponse, Error> {
-    let sets: Vec<_> = payload.sets.split("¦").map(|s| s.to_string()).collect();
+    let sets = parse_weight_sets(&payload)?;
     let new_url = pages::post_set_plate_weights(state, sets)?;
 
     let mut headers = HeaderMap::new();
@@ -1022,16 +1620,119 @@ async fn post_set_exercises(
 }
 
 #[derive(Debug, Deserialize)]
-struct SetName {
-    name: String,
+struct SetName {
+    name: String,
+}
+
+async fn post_set_add_workout(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetName>,
+) -> Result<impl IntoResponse, Error> {
+    let name = payload.name.trim();
+    let new_url = pages::post_set_add_workout(state, name)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+async fn post_set_program_name(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetName>,
+) -> Result<impl IntoResponse, Error> {
+    let new_name = payload.name.trim();
+    let new_url = pages::post_set_program_name(state, new_name)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+async fn post_set_workout_name(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetName>,
+) -> Result<impl IntoResponse, Error> {
+    let new_name = payload.name.trim();
+    let new_url = pages::post_set_workout_name(state, &workout, new_name)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetWeek {
+    week: String,
+}
+
+async fn post_set_week(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetWeek>,
+) -> Result<impl IntoResponse, Error> {
+    let week: i32 = payload
+        .week
+        .parse()
+        .unwrap_or_err(&format!("expected int but found '{}'", payload.week))?;
+    let new_url = pages::post_set_week(state, week)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetNotes {
+    notes: String,
+}
+
+async fn post_set_notes(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetNotes>,
+) -> Result<impl IntoResponse, Error> {
+    let new_url = pages::post_set_notes(state, payload.notes)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetOptions {
+    one_rep_max: OneRepMax,
+    units: Units,
 }
 
-async fn post_set_add_workout(
+async fn post_set_options(
     Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetName>,
+    Form(payload): Form<SetOptions>,
 ) -> Result<impl IntoResponse, Error> {
-    let name = payload.name.trim();
-    let new_url = pages::post_set_add_workout(state, name)?;
+    let new_url = pages::post_set_options(state, payload.one_rep_max, payload.units)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1043,12 +1744,16 @@ async fn post_set_add_workout(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
-async fn post_set_program_name(
+#[derive(Debug, Deserialize)]
+struct SetMissed {
+    policy: String, // "move" or "shift"
+}
+
+async fn post_reset_calendar_token(
+    Extension(server): Extension<SharedServer>,
     Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetName>,
 ) -> Result<impl IntoResponse, Error> {
-    let new_name = payload.name.trim();
-    let new_url = pages::post_set_program_name(state, new_name)?;
+    let new_url = pages::post_reset_calendar_token(server, state)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1060,13 +1765,11 @@ async fn post_set_program_name(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
-async fn post_set_workout_name(
-    Path(workout): Path<String>,
+async fn post_set_missed(
     Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetName>,
+    Form(payload): Form<SetMissed>,
 ) -> Result<impl IntoResponse, Error> {
-    let new_name = payload.name.trim();
-    let new_url = pages::post_set_workout_name(state, &workout, new_name)?;
+    let new_url = pages::post_set_missed(state, payload.policy == "shift")?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1078,20 +1781,11 @@ async fn post_set_workout_name(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
-#[derive(Debug, Deserialize)]
-struct SetWeek {
-    week: String,
-}
-
-async fn post_set_week(
+async fn post_skip_missed(
+    Path((workout, date)): Path<(String, NaiveDate)>,
     Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetWeek>,
 ) -> Result<impl IntoResponse, Error> {
-    let week: i32 = payload
-        .week
-        .parse()
-        .unwrap_or_err(&format!("expected int but found '{}'", payload.week))?;
-    let new_url = pages::post_set_week(state, week)?;
+    let new_url = pages::post_skip_missed(state, &workout, date)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1103,16 +1797,11 @@ async fn post_set_week(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
-#[derive(Debug, Deserialize)]
-struct SetNotes {
-    notes: String,
-}
-
-async fn post_set_notes(
+async fn post_slide_missed(
+    Path((workout, date)): Path<(String, NaiveDate)>,
     Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetNotes>,
 ) -> Result<impl IntoResponse, Error> {
-    let new_url = pages::post_set_notes(state, payload.notes)?;
+    let new_url = pages::post_slide_missed(state, &workout, date)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1178,6 +1867,85 @@ async fn post_set_schedule_weekdays(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
+async fn post_set_schedule_rotation(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetDays>,
+) -> Result<impl IntoResponse, Error> {
+    let days = payload
+        .days
+        .trim()
+        .split("¦")
+        .map(|s| s.to_string())
+        .collect();
+    let new_url = pages::post_set_schedule_rotation(state, &workout, days)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetTimes {
+    times: String,
+}
+
+async fn post_set_schedule_per_week(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetTimes>,
+) -> Result<impl IntoResponse, Error> {
+    let n: i32 = payload
+        .times
+        .parse()
+        .unwrap_or_err(&format!("expected int but found '{}'", payload.times))?;
+    let new_url = pages::post_set_schedule_per_week(state, &workout, n)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetDates {
+    dates: String, // "2025-03-01 2025-06-01"
+}
+
+async fn post_set_schedule_dates(
+    Path(workout): Path<String>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetDates>,
+) -> Result<impl IntoResponse, Error> {
+    let dates = payload
+        .dates
+        .split_whitespace()
+        .map(|d| {
+            NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                .unwrap_or_err(&format!("expected a date like 2025-03-01 but found '{d}'"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let new_url = pages::post_set_schedule_dates(state, &workout, dates)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
 async fn post_set_exercise_name(
     Path((workout, exercise)): Path<(String, String)>,
     Extension(state): Extension<SharedState>,
@@ -1216,7 +1984,7 @@ async fn post_set_formal_name(
 
 #[derive(Debug, Deserialize)]
 struct SetWeight {
-    weight: String, // "25 lbs"
+    weight: String, // "25 lbs" or "10 kg"
 }
 
 async fn post_set_weight(
@@ -1232,7 +2000,12 @@ async fn post_set_weight(
         let x: f32 = s
             .parse()
             .unwrap_or_err(&format!("expected f32 but found '{s}'"))?;
-        Some(x)
+        let units = if parts.get(1) == Some(&Units::Kgs.name()) {
+            Units::Kgs
+        } else {
+            Units::Lbs
+        };
+        Some(units.to_lbs(x)) // weights are always saved as lbs
     };
     let new_url = pages::post_set_weight(state, &workout, &exercise, w)?;
 
@@ -1246,10 +2019,111 @@ async fn post_set_weight(
     Ok((StatusCode::SEE_OTHER, headers))
 }
 
+#[derive(Debug, Deserialize)]
+struct SetTrainingMax {
+    training_max: String, // in the exercise's units, empty for None
+    percents: String,     // "65 75 85, 70 80 90", empty for None
+}
+
+async fn post_set_training_max(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetTrainingMax>,
+) -> Result<impl IntoResponse, Error> {
+    let weight = payload.training_max.trim();
+    let weight = if weight.is_empty() {
+        None
+    } else {
+        let weight: f32 = weight.parse().unwrap_or_err(&format!(
+            "expected a number for training max but found '{weight}'"
+        ))?;
+        Some(weight)
+    };
+
+    let percents = if payload.percents.trim().is_empty() {
+        None
+    } else {
+        let weeks = payload
+            .percents
+            .split(",")
+            .map(|week| {
+                week.split_whitespace()
+                    .map(|p| {
+                        p.parse::<i32>()
+                            .unwrap_or_err(&format!("expected an integer percent but found '{p}'"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Some(WeekPercents(weeks))
+    };
+    let new_url = pages::post_set_training_max(state, &workout, &exercise, weight, percents)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetAmrap {
+    rule: String, // "none", "steps", or "estimate"
+    extra_reps: String,
+    max_percent: String,
+}
+
+async fn post_set_amrap(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetAmrap>,
+) -> Result<impl IntoResponse, Error> {
+    let amrap = match payload.rule.as_str() {
+        "none" => None,
+        "steps" => {
+            let extra = payload.extra_reps.trim();
+            let extra: i32 = if extra.is_empty() {
+                0
+            } else {
+                extra.parse().unwrap_or_err(&format!(
+                    "expected integer for extra reps but found '{extra}'"
+                ))?
+            };
+            Some(AmrapRule::Steps(extra))
+        }
+        "estimate" => {
+            let percent = payload.max_percent.trim();
+            if percent.is_empty() {
+                return validation_err!("Max percent should be set when estimating.");
+            }
+            let percent: i32 = percent.parse().unwrap_or_err(&format!(
+                "expected integer for max percent but found '{percent}'"
+            ))?;
+            Some(AmrapRule::Estimate(percent))
+        }
+        _ => return validation_err!("Unknown AMRAP rule '{}'.", payload.rule),
+    };
+    let new_url = pages::post_set_amrap(state, &workout, &exercise, amrap)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
 #[derive(Debug, Deserialize)]
 struct SetDiscreteSet {
     name: String,
     weights: String, // weights like "25.000" separated by "¦"
+    add_ons: String, // add-ons like "2.5 magnet x2" separated by ","
+    units: Units,
 }
 
 async fn post_set_discrete_set(
@@ -1257,6 +2131,20 @@ async fn post_set_discrete_set(
     Extension(state): Extension<SharedState>,
     Form(payload): Form<SetDiscreteSet>,
 ) -> Result<impl IntoResponse, Error> {
+    fn parse_add_on(value: &str) -> Result<AddOn, Error> {
+        let Some((weight, rest)) = value.split_once(" ") else {
+            return validation_err!("Expected an add-on like '2.5 magnet x2' but found '{value}'");
+        };
+        let Ok(weight) = weight.parse::<f32>() else {
+            return validation_err!("Expected a weight for '{value}' but found '{weight}'");
+        };
+        let rest = rest.trim();
+        match rest.rsplit_once(" x").map(|(n, c)| (n, c.parse::<i32>())) {
+            Some((name, Ok(count))) => Ok(AddOn::new(name.trim(), weight, count)),
+            _ => Ok(AddOn::new(rest, weight, 1)),
+        }
+    }
+
     let set_name = payload.name.trim();
     let weights = payload
         .weights
@@ -1264,7 +2152,22 @@ async fn post_set_discrete_set(
         .map(|s| s.parse::<f32>())
         .collect::<Result<Vec<_>, _>>()
         .unwrap_or_err("bad weights list")?;
-    let new_url = pages::post_set_discrete_set(state, &workout, &exercise, set_name, weights)?;
+    let add_ons = payload
+        .add_ons
+        .split(",")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(parse_add_on)
+        .collect::<Result<Vec<_>, _>>()?;
+    let new_url = pages::post_set_discrete_set(
+        state,
+        &workout,
+        &exercise,
+        set_name,
+        weights,
+        add_ons,
+        payload.units,
+    )?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1279,42 +2182,96 @@ async fn post_set_discrete_set(
 #[derive(Debug, Deserialize)]
 struct SetPlateSet {
     name: String,
-    weights: String, // weights like "25.000x4" separated by "¦"
+    weights: String, // weights like "25.000x4" or "25.000x4xbumper" separated by "¦"
     bar: String,
+    max_plates: String,
+    units: Units,
 }
 
-async fn post_set_plates_set(
-    Path((workout, exercise)): Path<(String, String)>,
-    Extension(state): Extension<SharedState>,
-    Form(payload): Form<SetPlateSet>,
-) -> Result<impl IntoResponse, Error> {
-    fn parse_plate(value: &str) -> Result<(f32, i32), Error> {
+#[derive(Debug, Deserialize)]
+struct SetSinglePlateSet {
+    name: String,
+    weights: String, // weights like "25.000x4" or "25.000x4xbumper" separated by "¦"
+    base: String,
+    units: Units,
+}
+
+/// Parses the weights field of plate sets.
+fn parse_plates(weights: &str) -> Result<Vec<Plate>, Error> {
+    fn parse_plate(value: &str) -> Result<Plate, Error> {
         let parts: Vec<_> = value.split("x").collect();
-        if parts.len() == 2 {
+        if parts.len() == 2 || (parts.len() == 3 && parts[2] == "bumper") {
             let weight: f32 = parts[0]
                 .parse()
                 .unwrap_or_err(&format!("expected float for weight but found '{value}'"))?;
             let count: i32 = parts[1]
                 .parse()
                 .unwrap_or_err(&format!("expected int for count but found '{value}'"))?;
-            return Ok((weight, count));
+            let plate = Plate::new(weight, count);
+            return Ok(if parts.len() == 3 {
+                plate.with_bumper()
+            } else {
+                plate
+            });
         } else {
             return validation_err!("Expected weightxcount but found '{value}'");
         }
     }
 
-    let plates = payload
-        .weights
-        .split("¦")
-        .map(|s| parse_plate(s))
-        .collect::<Result<Vec<_>, _>>()?;
+    weights.split("¦").map(|s| parse_plate(s)).collect()
+}
+
+async fn post_set_plates_set(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetPlateSet>,
+) -> Result<impl IntoResponse, Error> {
+    let plates = parse_plates(&payload.weights)?;
     let bar = if payload.bar.is_empty() {
         None
     } else {
         Some(payload.bar.parse::<f32>().unwrap_or_err("bad bar")?)
     };
+    let max_plates = if payload.max_plates.is_empty() {
+        None
+    } else {
+        Some(
+            payload
+                .max_plates
+                .parse::<i32>()
+                .unwrap_or_err("bad max plates")?,
+        )
+    };
+    let set_name = payload.name.trim();
+    let set = WeightSet::DualPlates(plates, bar, max_plates);
+    let new_url =
+        pages::post_set_plate_set(state, &workout, &exercise, set_name, set, payload.units)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+async fn post_set_single_plates_set(
+    Path((workout, exercise)): Path<(String, String)>,
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<SetSinglePlateSet>,
+) -> Result<impl IntoResponse, Error> {
+    let plates = parse_plates(&payload.weights)?;
+    let base = if payload.base.is_empty() {
+        None
+    } else {
+        Some(payload.base.parse::<f32>().unwrap_or_err("bad base")?)
+    };
     let set_name = payload.name.trim();
-    let new_url = pages::post_set_plate_set(state, &workout, &exercise, set_name, plates, bar)?;
+    let set = WeightSet::SinglePlates(plates, base);
+    let new_url =
+        pages::post_set_plate_set(state, &workout, &exercise, set_name, set, payload.units)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1417,6 +2374,7 @@ async fn post_revert_note(
 struct SetDurations {
     times: String,
     target: String,
+    step: String,
     units: String, // "secs", "mins", or "hours"
 }
 
@@ -1432,7 +2390,8 @@ async fn post_set_durations(
         .collect::<Result<Vec<_>, _>>()?;
     let durations = durations.iter().filter_map(|o| *o).collect();
     let target = parse_time("target", &payload.target, &payload.units)?;
-    let new_url = pages::post_set_durations(state, &workout, &exercise, durations, target)?;
+    let step = parse_time("step", &payload.step, &payload.units)?;
+    let new_url = pages::post_set_durations(state, &workout, &exercise, durations, target, step)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1503,6 +2462,8 @@ fn parse_fixed_rep(name: &str, value: &str) -> Result<FixedReps, Error> {
 struct SetVarReps {
     warmups: String,
     worksets: String,
+    stall_sessions: String, // both are empty if there's no deload
+    deload_percent: String,
 }
 
 async fn post_set_var_reps(
@@ -1562,7 +2523,22 @@ async fn post_set_var_reps(
         .split_whitespace()
         .map(|s| parse_var_rep("worksets", s))
         .collect::<Result<Vec<_>, _>>()?;
-    let new_url = pages::post_set_var_reps(state, &workout, &exercise, warmups, worksets)?;
+    let deload = match (payload.stall_sessions.trim(), payload.deload_percent.trim()) {
+        ("", "") => None,
+        ("", _) | (_, "") => {
+            return validation_err!("Stall sessions and deload percent should both be set.")
+        }
+        (sessions, percent) => {
+            let sessions: i32 = sessions.parse().unwrap_or_err(&format!(
+                "expected integer for stall sessions but found '{sessions}'"
+            ))?;
+            let percent: i32 = percent.parse().unwrap_or_err(&format!(
+                "expected integer for deload percent but found '{percent}'"
+            ))?;
+            Some(Deload::new(sessions, percent))
+        }
+    };
+    let new_url = pages::post_set_var_reps(state, &workout, &exercise, warmups, worksets, deload)?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -1738,3 +2714,96 @@ fn parse_time(name: &str, value: &str, units: &str) -> Result<Option<i32>, Error
     }
     Ok(None)
 }
+
+const MAX_IMPORT_BYTES: usize = 50 * 1024 * 1024; // years of history can be fairly large
+
+async fn post_import_file(
+    Extension(state): Extension<SharedState>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, Error> {
+    let mut text = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .unwrap_or_err("failed to read upload")?
+    {
+        if field.name() == Some("file") {
+            text = Some(field.text().await.unwrap_or_err("failed to read file")?);
+        }
+    }
+    let text = text.unwrap_or_err("expected a file")?;
+    let new_url = pages::post_import_file(state, &text)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+// The form has ext-0, map-0, ext-1, map-1, etc.
+async fn post_import_names(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<HashMap<String, String>>,
+) -> Result<impl IntoResponse, Error> {
+    let mut names = Vec::new();
+    for i in 0.. {
+        match (
+            payload.get(&format!("ext-{i}")),
+            payload.get(&format!("map-{i}")),
+        ) {
+            (Some(external), Some(mapped)) => names.push((external.clone(), mapped.clone())),
+            _ => break,
+        }
+    }
+    let new_url = pages::post_import_names(state, names)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.to_string().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+async fn post_delete_records(
+    Extension(state): Extension<SharedState>,
+    Form(payload): Form<HashMap<String, String>>,
+) -> Result<impl IntoResponse, Error> {
+    let ids = payload
+        .keys()
+        .filter_map(|key| key.strip_prefix("rec-"))
+        .filter_map(|id| id.parse().ok())
+        .collect();
+    let query = payload.get("query").map_or("", |q| q.as_str());
+    let new_url = pages::post_delete_records(state, ids, query)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.to_string().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
+
+async fn post_cancel_import(
+    Extension(state): Extension<SharedState>,
+) -> Result<impl IntoResponse, Error> {
+    let new_url = pages::post_cancel_import(state)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        "no-store, must-revalidate".parse().unwrap(),
+    );
+    headers.insert("Expires", "0".parse().unwrap());
+    headers.insert("Location", new_url.path().parse().unwrap());
+    Ok((StatusCode::SEE_OTHER, headers))
+}
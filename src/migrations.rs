@@ -0,0 +1,402 @@
+//! Saved [`UserState`] documents include a schema version. When the layout of anything
+//! that is persisted changes CURRENT_VERSION is bumped and a function is added to
+//! MIGRATIONS to upgrade documents from the previous version. Documents are upgraded one
+//! step at a time when they are loaded so that old files keep working without needing
+//! to sprinkle serde defaults all over the place.
+//!
+//! When bumping the version also freeze a document in the old format into
+//! tests/fixtures so that we know that the whole chain continues to work.
+use serde_json::{Map, Value};
+use std::io::{Error, ErrorKind, Result};
+
+pub const CURRENT_VERSION: u64 = 12;
+
+type Migration = fn(&mut Value) -> Result<()>;
+
+/// MIGRATIONS[i] upgrades a document from version i to version i+1.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+    v9_to_v10, v10_to_v11, v11_to_v12,
+];
+
+/// Upgrades doc in place to CURRENT_VERSION. Documents without a version are assumed to
+/// be version 0 (i.e. from before we started versioning).
+pub fn migrate(doc: &mut Value) -> Result<()> {
+    let mut version = match doc.get("version") {
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| Error::other(format!("expected an integer version but found {v}")))?,
+        None => 0,
+    };
+    if version > CURRENT_VERSION {
+        // Unsupported so that callers can tell this apart from a corrupt document.
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("data file is version {version} but this build only understands up to version {CURRENT_VERSION}"),
+        ));
+    }
+
+    while version < CURRENT_VERSION {
+        MIGRATIONS[version as usize](doc)?;
+        version += 1;
+        set_version(doc, version)?;
+    }
+    Ok(())
+}
+
+fn set_version(doc: &mut Value, version: u64) -> Result<()> {
+    as_object(doc)?.insert("version".to_owned(), Value::from(version));
+    Ok(())
+}
+
+/// Adds a field to the top-level object if it isn't already there.
+fn insert_field(doc: &mut Value, name: &str, value: Value) -> Result<()> {
+    as_object(doc)?.entry(name).or_insert(value);
+    Ok(())
+}
+
+fn as_object(doc: &mut Value) -> Result<&mut Map<String, Value>> {
+    doc.as_object_mut()
+        .ok_or_else(|| Error::other("expected the data file to contain an object"))
+}
+
+/// Returns the type specific part of each exercise of the given kind, e.g. the
+/// DurationsExercise part of each Exercise::Durations.
+fn exercises_mut<'a>(doc: &'a mut Value, kind: &str) -> Vec<&'a mut Map<String, Value>> {
+    exercise_parts_mut(doc, &[kind], 1)
+}
+
+/// Returns the ExerciseData part of every exercise.
+fn exercise_data_mut(doc: &mut Value) -> Vec<&mut Map<String, Value>> {
+    let kinds = ["Durations", "FixedReps", "VariableReps", "VariableSets"];
+    exercise_parts_mut(doc, &kinds, 0)
+}
+
+fn exercise_parts_mut<'a>(
+    doc: &'a mut Value,
+    kinds: &[&str],
+    part: usize,
+) -> Vec<&'a mut Map<String, Value>> {
+    let mut exercises = Vec::new();
+    let workouts = doc
+        .pointer_mut("/program/workouts")
+        .and_then(|w| w.as_array_mut());
+    for workout in workouts.into_iter().flatten() {
+        let list = workout.get_mut("exercises").and_then(|e| e.as_array_mut());
+        for exercise in list.into_iter().flatten() {
+            let kind = kinds.iter().find(|k| exercise.get(**k).is_some());
+            if let Some(e) = kind.and_then(|k| exercise.pointer_mut(&format!("/{k}/{part}"))) {
+                if let Some(e) = e.as_object_mut() {
+                    exercises.push(e);
+                }
+            }
+        }
+    }
+    exercises
+}
+
+// Version 1 only added the version field.
+fn v0_to_v1(_doc: &mut Value) -> Result<()> {
+    Ok(())
+}
+
+// Version 2 added the mapping from exercise names in other apps to our names.
+fn v1_to_v2(doc: &mut Value) -> Result<()> {
+    insert_field(doc, "import_names", Value::Object(Map::new()))
+}
+
+// Version 3 added per-user options.
+fn v2_to_v3(doc: &mut Value) -> Result<()> {
+    insert_field(
+        doc,
+        "options",
+        serde_json::json!({ "one_rep_max": "Epley" }),
+    )
+}
+
+// Version 4 added a step to durations exercises.
+fn v3_to_v4(doc: &mut Value) -> Result<()> {
+    for e in exercises_mut(doc, "Durations") {
+        e.entry("step_secs").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+// Version 5 added bumper plates and a max plates per side limit to DualPlates. The
+// combos are dropped so that they are rebuilt with the new plates.
+fn v4_to_v5(doc: &mut Value) -> Result<()> {
+    if let Some(weights) = doc.get_mut("weights").and_then(|w| w.as_object_mut()) {
+        weights.remove("combos");
+    }
+    let sets = doc
+        .pointer_mut("/weights/sets")
+        .and_then(|s| s.as_object_mut());
+    for set in sets.into_iter().flat_map(|s| s.values_mut()) {
+        if let Some(dual) = set.get_mut("DualPlates").and_then(|d| d.as_array_mut()) {
+            let plates = dual.first_mut().and_then(|p| p.as_array_mut());
+            for plate in plates.into_iter().flatten() {
+                if let Some(plate) = plate.as_object_mut() {
+                    plate.entry("bumper").or_insert(Value::Bool(false));
+                }
+            }
+            if dual.len() == 2 {
+                dual.push(Value::Null);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Version 6 added add-ons to Discrete weight sets.
+fn v5_to_v6(doc: &mut Value) -> Result<()> {
+    let sets = doc
+        .pointer_mut("/weights/sets")
+        .and_then(|s| s.as_object_mut());
+    for set in sets.into_iter().flat_map(|s| s.values_mut()) {
+        if let Some(discrete) = set.get_mut("Discrete") {
+            let weights = discrete.take();
+            *discrete = Value::Array(vec![weights, Value::Array(Vec::new())]);
+        }
+    }
+    Ok(())
+}
+
+// Version 7 added units to options and weight sets.
+fn v6_to_v7(doc: &mut Value) -> Result<()> {
+    if let Some(options) = doc.get_mut("options").and_then(|o| o.as_object_mut()) {
+        options
+            .entry("units")
+            .or_insert(Value::String("Lbs".to_owned()));
+    }
+    if let Some(weights) = doc.get_mut("weights").and_then(|w| w.as_object_mut()) {
+        weights.entry("units").or_insert(Value::Object(Map::new()));
+    }
+    Ok(())
+}
+
+// Version 8 added stall detection and an optional deload to variable reps exercises.
+fn v7_to_v8(doc: &mut Value) -> Result<()> {
+    for e in exercises_mut(doc, "VariableReps") {
+        e.entry("deload").or_insert(Value::Null);
+        e.entry("stalls").or_insert(Value::from(0));
+    }
+    Ok(())
+}
+
+// Version 9 added per-week percents (and training maxes) to exercises.
+fn v8_to_v9(doc: &mut Value) -> Result<()> {
+    for d in exercise_data_mut(doc) {
+        d.entry("week_percents").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+// Version 10 added optional AMRAP sets to exercises.
+fn v9_to_v10(doc: &mut Value) -> Result<()> {
+    for d in exercise_data_mut(doc) {
+        d.entry("amrap").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+// Version 11 removed the never used completed field from workouts, History is used to
+// find when workouts were last done.
+fn v10_to_v11(doc: &mut Value) -> Result<()> {
+    let workouts = doc
+        .pointer_mut("/program/workouts")
+        .and_then(|w| w.as_array_mut());
+    for workout in workouts.into_iter().flatten() {
+        if let Some(workout) = workout.as_object_mut() {
+            workout.remove("completed");
+        }
+    }
+    Ok(())
+}
+
+// Version 12 added handling for missed workouts: the program has a policy for sliding
+// them forward along with the sessions that were moved and history records the ones
+// that were skipped.
+fn v11_to_v12(doc: &mut Value) -> Result<()> {
+    if let Some(program) = doc.get_mut("program").and_then(|p| p.as_object_mut()) {
+        program.entry("shift_missed").or_insert(Value::Bool(false));
+        program.entry("moved").or_insert(Value::Array(Vec::new()));
+    }
+    if let Some(history) = doc.get_mut("history").and_then(|h| h.as_object_mut()) {
+        history.entry("skipped").or_insert(Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::UserState;
+    use crate::exercise::{AmrapRule, Deload, ExerciseName};
+    use crate::strength::OneRepMax;
+    use crate::weights::{AddOn, Units, WeightSet};
+
+    fn load(fixture: &str) -> UserState {
+        let mut doc: Value = serde_json::from_str(fixture).unwrap();
+        migrate(&mut doc).unwrap();
+        assert_eq!(doc["version"], CURRENT_VERSION);
+        serde_json::from_value(doc).unwrap()
+    }
+
+    #[test]
+    fn load_v0() {
+        let state = load(include_str!("../tests/fixtures/v0.json"));
+        assert_eq!(state.program.name, "My");
+        assert_eq!(state.program.workouts().count(), 3);
+        assert_eq!(state.program.blocks().count(), 2);
+
+        let bench = ExerciseName("Bench".to_owned());
+        assert_eq!(state.history.records(&bench).count(), 2);
+        assert!(state.weights.get("Bar").is_some());
+        assert!(state.weights.get("Dumbbells").is_some());
+        assert!(state.import_names.is_empty());
+    }
+
+    #[test]
+    fn load_v1() {
+        let state = load(include_str!("../tests/fixtures/v1.json"));
+        assert_eq!(state.program.name, "My");
+        assert!(state.import_names.is_empty());
+    }
+
+    #[test]
+    fn load_v2() {
+        let state = load(include_str!("../tests/fixtures/v2.json"));
+        assert_eq!(state.import_names["Bench Press (Barbell)"], "Bench");
+        assert_eq!(state.options.one_rep_max, OneRepMax::Epley);
+    }
+
+    #[test]
+    fn load_v3() {
+        let state = load(include_str!("../tests/fixtures/v3.json"));
+        assert_eq!(state.options.one_rep_max, OneRepMax::Brzycki);
+
+        let workout = state.program.find("Light").unwrap();
+        let plank = workout.find(&ExerciseName("Plank".to_owned())).unwrap();
+        let (_, e) = plank.expect_durations();
+        assert_eq!(e.target(), Some(60));
+        assert_eq!(e.step(), None);
+    }
+
+    #[test]
+    fn load_v4() {
+        let state = load(include_str!("../tests/fixtures/v4.json"));
+        let workout = state.program.find("Light").unwrap();
+        let plank = workout.find(&ExerciseName("Plank".to_owned())).unwrap();
+        let (_, e) = plank.expect_durations();
+        assert_eq!(e.step(), Some(5));
+
+        match state.weights.get("Bar") {
+            Some(WeightSet::DualPlates(plates, bar, max_plates)) => {
+                assert_eq!(plates.len(), 4);
+                assert!(plates.iter().all(|p| !p.bumper));
+                assert_eq!(*bar, Some(45.0));
+                assert_eq!(*max_plates, None);
+            }
+            _ => panic!("expected DualPlates"),
+        }
+    }
+
+    #[test]
+    fn load_v5() {
+        let state = load(include_str!("../tests/fixtures/v5.json"));
+        match state.weights.get("Bar") {
+            Some(WeightSet::DualPlates(plates, _, max_plates)) => {
+                assert!(plates[3].bumper);
+                assert_eq!(*max_plates, Some(6));
+            }
+            _ => panic!("expected DualPlates"),
+        }
+        match state.weights.get("Dumbbells") {
+            Some(WeightSet::Discrete(weights, add_ons)) => {
+                assert_eq!(weights.len(), 6);
+                assert!(add_ons.is_empty());
+            }
+            _ => panic!("expected Discrete"),
+        }
+    }
+
+    #[test]
+    fn load_v6() {
+        let state = load(include_str!("../tests/fixtures/v6.json"));
+        assert_eq!(state.options.units, Units::Lbs);
+        assert_eq!(state.weights.units("Bar"), Some(Units::Lbs));
+        match state.weights.get("Dumbbells") {
+            Some(WeightSet::Discrete(_, add_ons)) => {
+                assert_eq!(add_ons, &vec![AddOn::new("magnet", 2.5, 1)]);
+            }
+            _ => panic!("expected Discrete"),
+        }
+    }
+
+    #[test]
+    fn load_v7() {
+        let state = load(include_str!("../tests/fixtures/v7.json"));
+        let workout = state.program.find("Heavy Bench").unwrap();
+        let bench = workout.find(&ExerciseName("Bench".to_owned())).unwrap();
+        let (_, e) = bench.expect_var_reps();
+        assert_eq!(e.deload(), None);
+        assert_eq!(e.stalls(), 0);
+    }
+
+    #[test]
+    fn load_v8() {
+        let state = load(include_str!("../tests/fixtures/v8.json"));
+        let workout = state.program.find("Heavy Bench").unwrap();
+        let bench = workout.find(&ExerciseName("Bench".to_owned())).unwrap();
+        let (d, e) = bench.expect_var_reps();
+        assert_eq!(e.deload(), Some(Deload::new(3, 10)));
+        assert_eq!(e.stalls(), 1);
+        assert_eq!(d.week_percents, None);
+
+        let workout = state.program.find("Light").unwrap();
+        let plank = workout.find(&ExerciseName("Plank".to_owned())).unwrap();
+        assert_eq!(plank.data().week_percents, None);
+    }
+
+    #[test]
+    fn load_v9() {
+        let state = load(include_str!("../tests/fixtures/v9.json"));
+        let workout = state.program.find("Heavy Bench").unwrap();
+        let bench = workout.find(&ExerciseName("Bench".to_owned())).unwrap();
+        let percents = bench.data().week_percents.as_ref().unwrap();
+        assert_eq!(percents.percent(Some(2), 2), Some(90));
+        assert_eq!(bench.data().amrap, None);
+    }
+
+    #[test]
+    fn load_v10() {
+        let mut doc: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/v10.json")).unwrap();
+        migrate(&mut doc).unwrap();
+        assert_eq!(doc.pointer("/program/workouts/0/completed"), None);
+
+        let state: UserState = serde_json::from_value(doc).unwrap();
+        let workout = state.program.find("Heavy Bench").unwrap();
+        let bench = workout.find(&ExerciseName("Bench".to_owned())).unwrap();
+        assert_eq!(bench.data().amrap, Some(AmrapRule::Steps(1)));
+    }
+
+    #[test]
+    fn load_v11() {
+        let state = load(include_str!("../tests/fixtures/v11.json"));
+        assert!(!state.program.shift_missed);
+        assert!(state.program.find("Heavy Bench").is_some());
+    }
+
+    #[test]
+    fn newer_version() {
+        let mut doc = serde_json::json!({ "version": CURRENT_VERSION + 1 });
+        assert!(migrate(&mut doc).is_err());
+    }
+
+    #[test]
+    fn bad_version() {
+        let mut doc = serde_json::json!({ "version": "one" });
+        assert!(migrate(&mut doc).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+//! Per-user settings that aren't tied to a program.
+use crate::strength::OneRepMax;
+use crate::weights::Units;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Options {
+    pub one_rep_max: OneRepMax, // formula used for estimated one rep maxes
+    pub units: Units,           // used to display weights that aren't from a weight set
+}
@@ -14,8 +14,13 @@ pub fn post_epilog(state: SharedState, path: &str) -> Result<Uri, Error> {
     {
         let state = &mut state.write().unwrap();
         let name = state.name.clone();
+        let dir = state.data_dir.clone();
+        let readonly = state.readonly;
         let user = &mut state.user;
-        if let Err(e) = crate::persist::save(&name, user) {
+        if readonly {
+            user.errors
+                .push("Not saving because the data file failed to load.".to_owned());
+        } else if let Err(e) = crate::persist::save(&dir, &name, user) {
             println!("error saving: {e}");
             user.errors.push(format!("{e}")); // not fatal so we don't return an error
         }
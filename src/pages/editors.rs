@@ -1,4 +1,5 @@
 mod edit_add_exercise;
+mod edit_amrap;
 mod edit_any_weight;
 mod edit_block;
 mod edit_blocks;
@@ -9,14 +10,21 @@ mod edit_durs_record;
 mod edit_exercises;
 mod edit_fixed_reps;
 mod edit_formal_name;
+mod edit_missed;
 mod edit_name;
 mod edit_note;
 mod edit_notes;
+mod edit_options;
 mod edit_plate_set;
 mod edit_reps_record;
 mod edit_rest;
 mod edit_schedule;
+mod edit_schedule_dates;
+mod edit_schedule_per_week;
+mod edit_schedule_rotation;
 mod edit_set_week;
+mod edit_single_plate_set;
+mod edit_training_max;
 mod edit_var_reps;
 mod edit_var_sets;
 mod edit_weight;
@@ -24,6 +32,7 @@ mod edit_weight_sets;
 mod edit_workouts;
 
 pub use edit_add_exercise::*;
+pub use edit_amrap::*;
 pub use edit_any_weight::*;
 pub use edit_block::*;
 pub use edit_blocks::*;
@@ -34,14 +43,21 @@ pub use edit_durs_record::*;
 pub use edit_exercises::*;
 pub use edit_fixed_reps::*;
 pub use edit_formal_name::*;
+pub use edit_missed::*;
 pub use edit_name::*;
 pub use edit_note::*;
 pub use edit_notes::*;
+pub use edit_options::*;
 pub use edit_plate_set::*;
 pub use edit_reps_record::*;
 pub use edit_rest::*;
 pub use edit_schedule::*;
+pub use edit_schedule_dates::*;
+pub use edit_schedule_per_week::*;
+pub use edit_schedule_rotation::*;
 pub use edit_set_week::*;
+pub use edit_single_plate_set::*;
+pub use edit_training_max::*;
 pub use edit_var_reps::*;
 pub use edit_var_sets::*;
 pub use edit_weight::*;
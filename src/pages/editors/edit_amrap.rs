@@ -0,0 +1,84 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::{AmrapRule, ExerciseName};
+use crate::pages::editor_builder::*;
+use axum::http::Uri;
+
+/// AMRAP sets are typically used with a training max, see [`AmrapRule`].
+pub fn get_edit_amrap(state: SharedState, workout: &str, exercise: &str) -> String {
+    let post_url = format!("/set-amrap/{workout}/{exercise}");
+    let cancel_url = format!("/exercise/{workout}/{exercise}");
+
+    let user = &state.read().unwrap().user;
+    let name = ExerciseName(exercise.to_owned());
+    let workout = user.program.find(workout).unwrap();
+    let exercise = workout.find(&name).unwrap();
+
+    let (checked, extra, percent) = match exercise.data().amrap {
+        None => ("none", None, None),
+        Some(AmrapRule::Steps(extra)) => ("steps", Some(extra as f32), None),
+        Some(AmrapRule::Estimate(percent)) => ("estimate", None, Some(percent as f32)),
+    };
+    let what = if exercise.data().week_percents.is_some() {
+        "training max"
+    } else {
+        "weight"
+    };
+    let items = vec![
+        ("No AMRAP set".to_owned(), "none".to_owned()),
+        (format!("Step the {what}"), "steps".to_owned()),
+        (format!("Estimate the {what}"), "estimate".to_owned()),
+    ];
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Edit AMRAP")),
+        Box::new(
+            Radio::new(
+                "rule",
+                items,
+                &format!("The last workset is done for as many reps as possible, its reps are the minimum. When the exercise is finished the reps change the {what} for the next cycle. With week percents this happens on the last week."),
+            )
+            .with_checked(checked),
+        ),
+        Box::new(
+            FloatInput::new(
+                "Extra Reps",
+                extra,
+                &format!("For stepping. Reaching the minimum advances the {what} one step plus another step for every this many reps past it, zero for just one step. Missing the minimum drops a step."),
+            )
+            .with_min(0.0)
+            .with_step(1.0),
+        ),
+        Box::new(
+            FloatInput::new(
+                "Max Percent",
+                percent,
+                &format!("For estimating. The {what} becomes this percent of the estimated one rep max, e.g. 90."),
+            )
+            .with_min(1.0)
+            .with_step(1.0),
+        ),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
+
+pub fn post_set_amrap(
+    state: SharedState,
+    workout: &str,
+    exercise: &str,
+    amrap: Option<AmrapRule>,
+) -> Result<Uri, Error> {
+    let path = format!("/exercise/{workout}/{exercise}");
+    let exercise = ExerciseName(exercise.to_owned());
+
+    {
+        let program = &mut state.write().unwrap().user.program;
+        let workout = program.find_mut(workout).unwrap();
+        let exercise = workout.find_mut(&exercise).unwrap();
+        exercise.try_set_amrap(amrap)?;
+    }
+
+    crate::pages::post_epilog(state, &path)
+}
@@ -3,30 +3,56 @@ use crate::errors::Error;
 use crate::pages::editor_builder::*;
 use crate::{
     exercise::ExerciseName,
-    weights::{self, WeightSet},
+    weights::{self, Units, WeightSet},
 };
 use axum::http::Uri;
 
 pub fn get_current_set(state: SharedState, workout: &str, exercise: &str) -> String {
-    fn get_help(ws: &WeightSet) -> String {
+    fn get_help(ws: &WeightSet, units: Units) -> String {
         match ws {
-            WeightSet::Discrete(weights) => {
+            WeightSet::Discrete(weights, add_ons) => {
                 weights
                     .iter()
                     .map(|w| weights::format_weight(*w, ""))
                     .collect::<Vec<_>>()
                     .join(", ")
-                    + " lbs"
+                    + units.suffix()
+                    + &if add_ons.is_empty() {
+                        "".to_owned()
+                    } else {
+                        format!(
+                            " with {}",
+                            add_ons
+                                .iter()
+                                .map(|a| a.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    }
             }
-            WeightSet::DualPlates(plates, bar) => {
+            WeightSet::DualPlates(plates, bar, _) => {
                 plates
                     .iter()
                     .map(|p| weights::format_weight(p.weight, ""))
                     .collect::<Vec<_>>()
                     .join(", ")
-                    + " lbs"
+                    + units.suffix()
                     + &if let Some(bar) = bar {
-                        format!(" with {} bar", weights::format_weight(*bar, " lbs"))
+                        format!(" with {} bar", weights::format_weight(*bar, units.suffix()))
+                    } else {
+                        "".to_owned()
+                    }
+            }
+            WeightSet::SinglePlates(plates, base) => {
+                plates
+                    .iter()
+                    .map(|p| weights::format_weight(p.weight, ""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    + units.suffix()
+                    + " singly"
+                    + &if let Some(base) = base {
+                        format!(" on {} base", weights::format_weight(*base, units.suffix()))
                     } else {
                         "".to_owned()
                     }
@@ -46,7 +72,12 @@ pub fn get_current_set(state: SharedState, workout: &str, exercise: &str) -> Str
     let active = data.weightset.clone().map_or("None".to_owned(), |n| n);
     let mut items: Vec<_> = weights
         .items()
-        .map(|(n, ws)| (n.clone(), get_help(ws)))
+        .map(|(n, ws)| {
+            (
+                n.clone(),
+                get_help(ws, weights.units(n).unwrap_or_default()),
+            )
+        })
         .collect();
     items.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
     items.push(("None".to_string(), "no weights".to_string()));
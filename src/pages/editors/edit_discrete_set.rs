@@ -1,7 +1,7 @@
 use crate::app_state::SharedState;
 use crate::errors::Error;
 use crate::pages::editor_builder::*;
-use crate::weights::WeightSet;
+use crate::weights::{AddOn, Units, WeightSet};
 use crate::{
     exercise::ExerciseName,
     weights::{self, Weights},
@@ -9,17 +9,25 @@ use crate::{
 use axum::http::Uri;
 
 pub fn get_edit_discrete_set(state: SharedState, workout: &str, exercise: &str) -> String {
-    fn make_labels(weights: &Weights, set_name: &str) -> Vec<String> {
+    fn make_labels(weights: &Weights, set_name: &str, units: Units) -> (Vec<String>, String) {
         if let Some(set) = weights.get(set_name) {
             match set {
-                weights::WeightSet::Discrete(values) => values
-                    .iter()
-                    .map(|w| weights::format_weight(*w, " lbs"))
-                    .collect(),
-                weights::WeightSet::DualPlates(_, _) => panic!("expected discrete weights"),
+                weights::WeightSet::Discrete(values, add_ons) => (
+                    values
+                        .iter()
+                        .map(|w| weights::format_weight(*w, units.suffix()))
+                        .collect(),
+                    add_ons
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                weights::WeightSet::DualPlates(_, _, _) => panic!("expected discrete weights"),
+                weights::WeightSet::SinglePlates(_, _) => panic!("expected discrete weights"),
             }
         } else {
-            Vec::new()
+            (Vec::new(), String::new())
         }
     }
 
@@ -39,7 +47,8 @@ pub fn get_edit_discrete_set(state: SharedState, workout: &str, exercise: &str)
         EditButton::new("delete-btn", "on_delete()", "Delete"),
     ];
     let set_name = data.weightset.clone().unwrap(); // only land in this function if there is a weightset
-    let items = make_labels(weights, &set_name);
+    let units = weights.units(&set_name).unwrap_or_default();
+    let (items, add_ons) = make_labels(weights, &set_name, units);
     let javascript = include_str!("../../../files/discrete.js");
     let modal = include_str!("../../../files/discrete-modal.html");
 
@@ -53,10 +62,26 @@ pub fn get_edit_discrete_set(state: SharedState, workout: &str, exercise: &str)
             TextInput::new(
                 "Name",
                 &set_name,
-                "The name of the weight set, e.g. \"Dumbbells\".",
+                "The name of the weight set, e.g. \"Dumbbells\". Renaming it updates every exercise that uses the set.",
             )
             .with_required(),
         ),
+        Box::new(
+            Radio::new(
+                "units",
+                vec![
+                    ("Pounds".to_owned(), "Lbs".to_owned()),
+                    ("Kilograms".to_owned(), "Kgs".to_owned()),
+                ],
+                "Units for the weights below. Changing this does not convert them.",
+            )
+            .with_checked(&format!("{units:?}")),
+        ),
+        Box::new(TextInput::new(
+            "Add Ons",
+            &add_ons,
+            "Optional extra weights like \"2.5 magnet x2, 1.25 clip\" where x2 is the most that can be used at once.",
+        )),
         Box::new(List::with_names("weights", items, "The weights in the weight set.").without_js()),
         Box::new(Html::new(modal)),
         Box::new(StdButtons::new(&cancel_url)),
@@ -71,29 +96,27 @@ pub fn post_set_discrete_set(
     exercise: &str,
     set_name: &str,
     weights: Vec<f32>,
+    add_ons: Vec<AddOn>,
+    units: Units,
 ) -> Result<Uri, Error> {
     let path = format!("/exercise/{workout}/{exercise}");
     let exercise = ExerciseName(exercise.to_owned());
 
     {
+        let user = &mut state.write().unwrap().user;
         let old_name = {
-            let program = &state.write().unwrap().user.program;
-            let workout = program.find(&workout).unwrap();
+            let workout = user.program.find(workout).unwrap();
             let exercise = workout.find(&exercise).unwrap();
             let d = exercise.data();
             d.weightset.clone().map_or("".to_string(), |s| s)
         };
-        {
-            let weights = WeightSet::Discrete(weights);
-            let wghts = &mut state.write().unwrap().user.weights;
-            wghts.try_change_set(&old_name, set_name, weights)?;
-        }
-        if old_name != set_name {
-            let program = &mut state.write().unwrap().user.program;
-            let workout = program.find_mut(&workout).unwrap();
+        let weights = WeightSet::Discrete(weights, add_ons);
+        user.try_change_set(&old_name, set_name, weights, units)?;
+        if old_name.is_empty() {
+            // There wasn't a set to rename so point the exercise at the new one.
+            let workout = user.program.find_mut(workout).unwrap();
             let exercise = workout.find_mut(&exercise).unwrap();
-            let d = exercise.data_mut();
-            d.weightset = Some(set_name.to_string()); // try_change_set has handled name validation
+            exercise.data_mut().weightset = Some(set_name.to_string());
         }
     }
 
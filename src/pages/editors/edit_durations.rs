@@ -21,6 +21,7 @@ pub fn get_edit_durations(state: SharedState, workout: &str, exercise: &str) ->
     let durations = durations.join(" ");
 
     let target = e.target().map(|t| t as f32 / 60.0);
+    let step = e.step().map(|t| t as f32 / 60.0);
     let items = [("Secs", "secs"), ("Mins", "mins"), ("Hours", "hours")];
     let javascript = include_str!("../../../files/durations.js");
 
@@ -42,6 +43,13 @@ pub fn get_edit_durations(state: SharedState, workout: &str, exercise: &str) ->
                 "Optional goal for times. Users may switch to a harder version of the exercise when reaching the target.",
             )
         ),
+        Box::new(
+            FloatInput::new(
+                "Step",
+                step,
+                "Optional amount to increase times by after all the sets are done. Times are only increased up to the target.",
+            )
+        ),
         Box::new(
             Dropdown::new("Units", &items, javascript)
             .with_active("Mins"),
@@ -58,6 +66,7 @@ pub fn post_set_durations(
     exercise: &str,
     durations: Vec<i32>,
     target: Option<i32>,
+    step: Option<i32>,
 ) -> Result<Uri, Error> {
     let path = format!("/exercise/{workout}/{exercise}");
     let exercise = ExerciseName(exercise.to_owned());
@@ -69,6 +78,7 @@ pub fn post_set_durations(
         let (_, e) = exercise.expect_durations_mut();
         e.try_set_durations(durations)?;
         e.try_set_target(target)?;
+        e.try_set_step(step)?;
     }
 
     crate::pages::post_epilog(state, &path)
@@ -15,6 +15,7 @@ pub fn get_edit_durs_record(
     let cancel_url = format!("/exercise/{workout}/{exercise}");
 
     let name = ExerciseName(exercise.to_owned());
+    let units = state.read().unwrap().user.exercise_units(workout, &name);
     let history = &state.read().unwrap().user.history;
 
     let record = history.find_record(&name, id)?;
@@ -25,7 +26,11 @@ pub fn get_edit_durs_record(
                 .collect::<Vec<String>>()
                 .join(" "),
             r.iter()
-                .map(|x| x.1.map_or("".to_owned(), |w| weights::format_weight(w, "")))
+                .map(|x| {
+                    x.1.map_or("".to_owned(), |w| {
+                        weights::format_weight(units.round_lbs(w), "")
+                    })
+                })
                 .collect::<Vec<String>>()
                 .join(" "),
         ),
@@ -55,7 +60,10 @@ pub fn get_edit_durs_record(
             TextInput::new(
                 "Weights",
                 &weights,
-                "Space separated list of weights that were used for each set.",
+                &format!(
+                    "Space separated list of weights (in {}) that were used for each set.",
+                    units.name()
+                ),
             )
             .with_pattern(r#"\s*(\d+(\.\d+)?(\s+\d+(\.\d+)?)*\s*)?"#),
         ),
@@ -82,6 +90,11 @@ pub fn post_set_durs_record(
 ) -> Result<Uri, Error> {
     let path = format!("/exercise/{workout}/{exercise}");
     let exercise = ExerciseName(exercise.to_owned());
+    let units = state
+        .read()
+        .unwrap()
+        .user
+        .exercise_units(workout, &exercise);
 
     {
         let history = &mut state.write().unwrap().user.history;
@@ -90,7 +103,7 @@ pub fn post_set_durs_record(
             durations
                 .iter()
                 .copied()
-                .zip(weights.iter().map(|w| Some(*w)))
+                .zip(weights.iter().map(|w| Some(units.to_lbs(*w))))
                 .collect()
         } else if weights.is_empty() {
             durations.iter().map(|r| (*r, None)).collect()
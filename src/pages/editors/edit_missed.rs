@@ -0,0 +1,44 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::pages::editor_builder::*;
+use axum::http::Uri;
+
+pub fn get_edit_missed(state: SharedState) -> String {
+    let post_url = "/set-missed";
+    let cancel_url = "/";
+
+    let program = &state.read().unwrap().user.program;
+    let items = vec![
+        ("Only move the missed workout".to_owned(), "move".to_owned()),
+        ("Shift the rest of the week".to_owned(), "shift".to_owned()),
+    ];
+    let checked = if program.shift_missed {
+        "shift"
+    } else {
+        "move"
+    };
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Missed Workouts")),
+        Box::new(
+            Radio::new(
+                "policy",
+                items,
+                "What happens when a missed workout is done today. Shifting pushes the week's remaining fixed sessions back by the days missed, sessions that would land after Sunday are done on Sunday.",
+            )
+            .with_checked(checked),
+        ),
+        Box::new(StdButtons::new(cancel_url)),
+    ];
+
+    build_editor(post_url, widgets)
+}
+
+pub fn post_set_missed(state: SharedState, shift: bool) -> Result<Uri, Error> {
+    {
+        let program = &mut state.write().unwrap().user.program;
+        program.shift_missed = shift;
+    }
+
+    crate::pages::post_epilog(state, "/")
+}
@@ -27,9 +27,10 @@ pub fn post_set_program_name(state: SharedState, new_name: &str) -> Result<Uri,
     let path = "/";
 
     {
-        let program = &mut state.write().unwrap().user.program;
-        if program.name != new_name {
-            program.name = new_name.to_string(); // TODO need some validation once we support multiple programs
+        let user = &mut state.write().unwrap().user;
+        if user.program.name != new_name {
+            user.history.rename_program(&user.program.name, new_name);
+            user.program.name = new_name.to_string(); // TODO need some validation once we support multiple programs
         }
     }
 
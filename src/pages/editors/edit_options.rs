@@ -0,0 +1,62 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::pages::editor_builder::*;
+use crate::strength::OneRepMax;
+use crate::weights::Units;
+use axum::http::Uri;
+
+pub fn get_edit_options(state: SharedState) -> String {
+    let post_url = "/set-options";
+    let cancel_url = "/";
+
+    let options = &state.read().unwrap().user.options;
+    let items = [
+        ("Epley: weight × (1 + reps/30)", OneRepMax::Epley),
+        ("Brzycki: weight × 36/(37 - reps)", OneRepMax::Brzycki),
+    ];
+    let items = items
+        .iter()
+        .map(|(l, v)| (l.to_string(), v.name().to_string()))
+        .collect();
+    let units = vec![
+        ("Pounds".to_owned(), "Lbs".to_owned()),
+        ("Kilograms".to_owned(), "Kgs".to_owned()),
+    ];
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Options")),
+        Box::new(
+            Radio::new(
+                "one_rep_max",
+                items,
+                "Formula used to estimate one rep maxes for PRs. The two agree at about ten reps, Brzycki estimates lower below that.",
+            )
+            .with_checked(options.one_rep_max.name()),
+        ),
+        Box::new(
+            Radio::new(
+                "units",
+                units,
+                "Units used for history, charts, and exercises without a weight set. Exercises that use a weight set show that set's units. Saved weights are converted so switching is safe.",
+            )
+            .with_checked(&format!("{:?}", options.units)),
+        ),
+        Box::new(StdButtons::new(cancel_url)),
+    ];
+
+    build_editor(post_url, widgets)
+}
+
+pub fn post_set_options(
+    state: SharedState,
+    one_rep_max: OneRepMax,
+    units: Units,
+) -> Result<Uri, Error> {
+    {
+        let options = &mut state.write().unwrap().user.options;
+        options.one_rep_max = one_rep_max;
+        options.units = units;
+    }
+
+    crate::pages::post_epilog(state, "/")
+}
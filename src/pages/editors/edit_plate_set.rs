@@ -1,7 +1,7 @@
 use crate::app_state::SharedState;
 use crate::errors::Error;
 use crate::pages::editor_builder::*;
-use crate::weights::{Plate, WeightSet};
+use crate::weights::{Units, WeightSet};
 use crate::{
     exercise::ExerciseName,
     weights::{self, Weights},
@@ -9,22 +9,33 @@ use crate::{
 use axum::http::Uri;
 
 pub fn get_edit_plate_set(state: SharedState, workout: &str, exercise: &str) -> String {
-    fn make_labels(weights: &Weights, set_name: &str) -> (Vec<String>, Option<f32>) {
+    fn make_labels(
+        weights: &Weights,
+        set_name: &str,
+        units: Units,
+    ) -> (Vec<String>, Option<f32>, Option<i32>) {
         if let Some(set) = weights.get(set_name) {
             match set {
-                weights::WeightSet::Discrete(_) => panic!("expected plate weights"),
-                weights::WeightSet::DualPlates(plates, bar) => (
+                weights::WeightSet::Discrete(_, _) => panic!("expected plate weights"),
+                weights::WeightSet::SinglePlates(_, _) => panic!("expected dual plate weights"),
+                weights::WeightSet::DualPlates(plates, bar, max_plates) => (
                     plates
                         .iter()
                         .map(|p| {
-                            format!("{} x{}", weights::format_weight(p.weight, " lbs"), p.count,)
+                            format!(
+                                "{} x{}{}",
+                                weights::format_weight(p.weight, units.suffix()),
+                                p.count,
+                                if p.bumper { " bumper" } else { "" }
+                            )
                         })
                         .collect(),
                     *bar,
+                    *max_plates,
                 ),
             }
         } else {
-            (Vec::new(), None)
+            (Vec::new(), None, None)
         }
     }
 
@@ -44,7 +55,8 @@ pub fn get_edit_plate_set(state: SharedState, workout: &str, exercise: &str) ->
         EditButton::new("delete-btn", "on_delete()", "Delete"),
     ];
     let set_name = data.weightset.clone().unwrap(); // only land in this function if there is a weightset
-    let (items, bar) = make_labels(weights, &set_name);
+    let units = weights.units(&set_name).unwrap_or_default();
+    let (items, bar, max_plates) = make_labels(weights, &set_name, units);
     let javascript = include_str!("../../../files/plates.js");
     let modal = include_str!("../../../files/plates-modal.html");
 
@@ -54,16 +66,43 @@ pub fn get_edit_plate_set(state: SharedState, workout: &str, exercise: &str) ->
             TextInput::new(
                 "Name",
                 &set_name,
-                "The name of the weight set, e.g. \"Deadlift\".",
+                "The name of the weight set, e.g. \"Deadlift\". Renaming it updates every exercise that uses the set.",
             )
             .with_required(),
         ),
+        Box::new(
+            Radio::new(
+                "units",
+                vec![
+                    ("Pounds".to_owned(), "Lbs".to_owned()),
+                    ("Kilograms".to_owned(), "Kgs".to_owned()),
+                ],
+                "Units for the weights below. Changing this does not convert them.",
+            )
+            .with_checked(&format!("{units:?}")),
+        ),
         Box::new(FloatInput::new(
             "Bar",
             bar,
             "Optional fixed weight, usually for a barbell.",
         )),
-        Box::new(List::with_names("weights", items, "The plates in the weight set.").without_js()),
+        Box::new(
+            FloatInput::new(
+                "Max Plates",
+                max_plates.map(|n| n as f32),
+                "Optional limit on the number of plates on each side, e.g. for a short sleeve.",
+            )
+            .with_min(1.0)
+            .with_step(1.0),
+        ),
+        Box::new(
+            List::with_names(
+                "weights",
+                items,
+                "The plates in the weight set. Bumper plates are used whenever possible.",
+            )
+            .without_js(),
+        ),
         Box::new(Html::new(modal)),
         Box::new(StdButtons::new(&cancel_url)),
     ];
@@ -76,32 +115,26 @@ pub fn post_set_plate_set(
     workout: &str,
     exercise: &str,
     set_name: &str,
-    plates: Vec<(f32, i32)>,
-    bar: Option<f32>,
+    set: WeightSet,
+    units: Units,
 ) -> Result<Uri, Error> {
     let path = format!("/exercise/{workout}/{exercise}");
     let exercise = ExerciseName(exercise.to_owned());
-    let plates = plates.iter().map(|(w, c)| Plate::new(*w, *c)).collect();
 
     {
+        let user = &mut state.write().unwrap().user;
         let old_name = {
-            let program = &state.write().unwrap().user.program;
-            let workout = program.find(&workout).unwrap();
+            let workout = user.program.find(workout).unwrap();
             let exercise = workout.find(&exercise).unwrap();
             let d = exercise.data();
             d.weightset.clone().map_or("".to_string(), |s| s)
         };
-        {
-            let weights = WeightSet::DualPlates(plates, bar);
-            let wghts = &mut state.write().unwrap().user.weights;
-            wghts.try_change_set(&old_name, set_name, weights)?;
-        }
-        if old_name != set_name {
-            let program = &mut state.write().unwrap().user.program;
-            let workout = program.find_mut(&workout).unwrap();
+        user.try_change_set(&old_name, set_name, set, units)?;
+        if old_name.is_empty() {
+            // There wasn't a set to rename so point the exercise at the new one.
+            let workout = user.program.find_mut(workout).unwrap();
             let exercise = workout.find_mut(&exercise).unwrap();
-            let d = exercise.data_mut();
-            d.weightset = Some(set_name.to_string()); // try_change_set has handled name validation
+            exercise.data_mut().weightset = Some(set_name.to_string());
         }
     }
 
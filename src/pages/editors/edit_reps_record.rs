@@ -20,6 +20,7 @@ pub fn get_edit_reps_record(
 
     let history = &state.read().unwrap().user.history;
     let name = ExerciseName(exercise.to_owned());
+    let units = state.read().unwrap().user.exercise_units(workout, &name);
     let record = history.find_record(&name, id)?;
     let (reps, weights) = match &record.sets {
         Some(CompletedSets::Reps(r)) => (
@@ -28,7 +29,11 @@ pub fn get_edit_reps_record(
                 .collect::<Vec<String>>()
                 .join(" "),
             r.iter()
-                .map(|x| x.1.map_or("".to_owned(), |w| weights::format_weight(w, "")))
+                .map(|x| {
+                    x.1.map_or("".to_owned(), |w| {
+                        weights::format_weight(units.round_lbs(w), "")
+                    })
+                })
                 .collect::<Vec<String>>()
                 .join(" "),
         ),
@@ -56,7 +61,10 @@ pub fn get_edit_reps_record(
             TextInput::new(
                 "Weights",
                 &weights,
-                "Space separated list of weights that were used for each set.",
+                &format!(
+                    "Space separated list of weights (in {}) that were used for each set.",
+                    units.name()
+                ),
             )
             .with_pattern(r#"\s*(\d+(\.\d+)?(\s+\d+(\.\d+)?)*\s*)?"#),
         ),
@@ -81,6 +89,11 @@ pub fn post_set_reps_record(
     id: u64,
 ) -> Result<Uri, Error> {
     let exercise_name = ExerciseName(exercise_name.to_owned());
+    let units = state
+        .read()
+        .unwrap()
+        .user
+        .exercise_units(workout_name, &exercise_name);
 
     {
         let history = &mut state.write().unwrap().user.history;
@@ -88,7 +101,7 @@ pub fn post_set_reps_record(
         let sets = if reps.len() == weights.len() {
             reps.iter()
                 .copied()
-                .zip(weights.iter().map(|w| Some(*w)))
+                .zip(weights.iter().map(|w| Some(units.to_lbs(*w))))
                 .collect()
         } else if weights.is_empty() {
             reps.iter().map(|r| (*r, None)).collect()
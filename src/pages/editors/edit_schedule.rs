@@ -54,20 +54,7 @@ pub fn get_edit_schedule_weekdays(state: SharedState, workout: &str) -> String {
         Schedule::Days(d) => d.clone(),
         _ => Vec::new(),
     };
-    let items = vec![
-        // TODO think we'll need javascript and a hidden button to accumulate values
-        ("Sunday", "sun", days.contains(&Weekday::Sun)),
-        ("Monday", "mon", days.contains(&Weekday::Mon)),
-        ("Tuesday", "tues", days.contains(&Weekday::Tue)),
-        ("Wednesday", "wed", days.contains(&Weekday::Wed)),
-        ("Thursday", "thurs", days.contains(&Weekday::Thu)),
-        ("Friday", "fri", days.contains(&Weekday::Fri)),
-        ("Saturday", "sat", days.contains(&Weekday::Sat)),
-    ];
-    let items = items
-        .iter()
-        .map(|(l, v, e)| (l.to_string(), v.to_string(), *e))
-        .collect();
+    let items = weekday_items(&days);
 
     let widgets: Vec<Box<dyn Widget>> = vec![
         Box::new(Prolog::with_title("Schedule Week Days")),
@@ -99,19 +86,7 @@ pub fn post_set_schedule_weekdays(
     days: Vec<String>,
 ) -> Result<Uri, Error> {
     {
-        let days = days
-            .into_iter()
-            .map(|d| match d.as_ref() {
-                "sun" => Ok(Weekday::Sun),
-                "mon" => Ok(Weekday::Mon),
-                "tues" => Ok(Weekday::Tue),
-                "wed" => Ok(Weekday::Wed),
-                "thurs" => Ok(Weekday::Thu),
-                "fri" => Ok(Weekday::Fri),
-                "sat" => Ok(Weekday::Sat),
-                _ => validation_err!("Expected a weekday name, e.g. 'sun' but found '{d}'."),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let days = parse_weekdays(days)?;
         let program = &mut state.write().unwrap().user.program;
         let workout = program.find_mut(&workout).unwrap();
         workout.try_set_schedule(Schedule::Days(days))?;
@@ -120,3 +95,37 @@ pub fn post_set_schedule_weekdays(
     let path = format!("/workout/{workout}");
     crate::pages::post_epilog(state, &path)
 }
+
+/// Checkbox items for each day of the week with days checked.
+pub(crate) fn weekday_items(days: &[Weekday]) -> Vec<(String, String, bool)> {
+    let items = vec![
+        // TODO think we'll need javascript and a hidden button to accumulate values
+        ("Sunday", "sun", days.contains(&Weekday::Sun)),
+        ("Monday", "mon", days.contains(&Weekday::Mon)),
+        ("Tuesday", "tues", days.contains(&Weekday::Tue)),
+        ("Wednesday", "wed", days.contains(&Weekday::Wed)),
+        ("Thursday", "thurs", days.contains(&Weekday::Thu)),
+        ("Friday", "fri", days.contains(&Weekday::Fri)),
+        ("Saturday", "sat", days.contains(&Weekday::Sat)),
+    ];
+    items
+        .iter()
+        .map(|(l, v, e)| (l.to_string(), v.to_string(), *e))
+        .collect()
+}
+
+/// Inverse of weekday_items.
+pub(crate) fn parse_weekdays(days: Vec<String>) -> Result<Vec<Weekday>, Error> {
+    days.into_iter()
+        .map(|d| match d.as_ref() {
+            "sun" => Ok(Weekday::Sun),
+            "mon" => Ok(Weekday::Mon),
+            "tues" => Ok(Weekday::Tue),
+            "wed" => Ok(Weekday::Wed),
+            "thurs" => Ok(Weekday::Thu),
+            "fri" => Ok(Weekday::Fri),
+            "sat" => Ok(Weekday::Sat),
+            _ => validation_err!("Expected a weekday name, e.g. 'sun' but found '{d}'."),
+        })
+        .collect()
+}
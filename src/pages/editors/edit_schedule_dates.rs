@@ -0,0 +1,51 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::pages::editor_builder::*;
+use crate::workout::Schedule;
+use axum::http::Uri;
+use chrono::NaiveDate;
+
+pub fn get_edit_schedule_dates(state: SharedState, workout: &str) -> String {
+    let post_url = format!("/set-schedule-dates/{workout}");
+    let cancel_url = format!("/workout/{workout}");
+
+    let program = &state.read().unwrap().user.program;
+    let workout = program.find(workout).unwrap();
+    let dates = match &workout.schedule {
+        Schedule::Dates(d) => d.iter().map(|d| d.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Schedule Dates")),
+        Box::new(
+            TextInput::new(
+                "Dates",
+                &dates.join(" "),
+                "Space separated dates for one-off sessions, e.g. \"2025-03-01 2025-06-01\".",
+            )
+            .with_pattern(r#"\s*\d{4}-\d{1,2}-\d{1,2}(\s+\d{4}-\d{1,2}-\d{1,2})*\s*"#)
+            .with_required(),
+        ),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
+
+pub fn post_set_schedule_dates(
+    state: SharedState,
+    workout: &str,
+    mut dates: Vec<NaiveDate>,
+) -> Result<Uri, Error> {
+    {
+        dates.sort();
+        dates.dedup();
+        let program = &mut state.write().unwrap().user.program;
+        let workout = program.find_mut(workout).unwrap();
+        workout.try_set_schedule(Schedule::Dates(dates))?;
+    }
+
+    let path = format!("/workout/{workout}");
+    crate::pages::post_epilog(state, &path)
+}
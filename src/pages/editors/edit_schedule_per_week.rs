@@ -0,0 +1,48 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::pages::editor_builder::*;
+use crate::workout::Schedule;
+use axum::http::Uri;
+use chrono::Local;
+
+pub fn get_edit_schedule_per_week(state: SharedState, workout: &str) -> String {
+    let post_url = format!("/set-schedule-per-week/{workout}");
+    let cancel_url = format!("/workout/{workout}");
+
+    let user = &state.read().unwrap().user;
+    let workout = user.program.find(workout).unwrap();
+    let n = match workout.schedule {
+        Schedule::PerWeek(x) => x as f32,
+        _ => 3.0,
+    };
+    let done = user
+        .history
+        .week_sessions(&user.program.name, &workout.name, Local::now());
+    let help = format!(
+        "The workout is due each day until it's been done this many times in the week (starting on Monday). It's been done {done} times this week."
+    );
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Schedule Times Per Week")),
+        Box::new(
+            FloatInput::new("Times", Some(n), &help)
+                .with_min(1.0)
+                .with_step(1.0)
+                .with_required(),
+        ),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
+
+pub fn post_set_schedule_per_week(state: SharedState, workout: &str, n: i32) -> Result<Uri, Error> {
+    {
+        let program = &mut state.write().unwrap().user.program;
+        let workout = program.find_mut(workout).unwrap();
+        workout.try_set_schedule(Schedule::PerWeek(n))?;
+    }
+
+    let path = format!("/workout/{workout}");
+    crate::pages::post_epilog(state, &path)
+}
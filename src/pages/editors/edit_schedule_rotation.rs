@@ -0,0 +1,59 @@
+use super::edit_schedule::{parse_weekdays, weekday_items};
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::pages::editor_builder::*;
+use crate::workout::Schedule;
+use axum::http::Uri;
+
+pub fn get_edit_schedule_rotation(state: SharedState, workout: &str) -> String {
+    let post_url = format!("/set-schedule-rotation/{workout}");
+    let cancel_url = format!("/workout/{workout}");
+
+    let program = &state.read().unwrap().user.program;
+    let workout = program.find(workout).unwrap();
+    let days = match &workout.schedule {
+        Schedule::Rotation(d) => d.clone(),
+        _ => Vec::new(),
+    };
+    let items = weekday_items(&days);
+
+    // Let the user know which workouts they're currently taking turns with.
+    let others: Vec<_> = program
+        .workouts()
+        .filter(|w| w.name != workout.name)
+        .filter(|w| matches!(&w.schedule, Schedule::Rotation(d) if *d == days))
+        .map(|w| w.name.clone())
+        .collect();
+    let help = if days.is_empty() || others.is_empty() {
+        "Workouts with the same rotation days take turns on those days, e.g. A on Monday, B on Wednesday, A on Friday, and B on the next Monday. Missing a day doesn't skip a workout.".to_owned()
+    } else {
+        format!(
+            "Takes turns with {} on these days. Changing the days removes this workout from that rotation.",
+            others.join(", ")
+        )
+    };
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Schedule Rotation")),
+        Box::new(Checkbox::new("days", items, &help)),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
+
+pub fn post_set_schedule_rotation(
+    state: SharedState,
+    workout: &str,
+    days: Vec<String>,
+) -> Result<Uri, Error> {
+    {
+        let days = parse_weekdays(days)?;
+        let program = &mut state.write().unwrap().user.program;
+        let workout = program.find_mut(workout).unwrap();
+        workout.try_set_schedule(Schedule::Rotation(days))?;
+    }
+
+    let path = format!("/workout/{workout}");
+    crate::pages::post_epilog(state, &path)
+}
@@ -0,0 +1,96 @@
+use crate::app_state::SharedState;
+use crate::pages::editor_builder::*;
+use crate::weights::{Units, WeightSet};
+use crate::{
+    exercise::ExerciseName,
+    weights::{self, Weights},
+};
+
+/// Like get_edit_plate_set except for SinglePlates, i.e. plates that are added one at a
+/// time. Posts are handled by post_set_plate_set.
+pub fn get_edit_single_plate_set(state: SharedState, workout: &str, exercise: &str) -> String {
+    fn make_labels(weights: &Weights, set_name: &str, units: Units) -> (Vec<String>, Option<f32>) {
+        if let Some(set) = weights.get(set_name) {
+            match set {
+                WeightSet::SinglePlates(plates, base) => (
+                    plates
+                        .iter()
+                        .map(|p| {
+                            format!(
+                                "{} x{}{}",
+                                weights::format_weight(p.weight, units.suffix()),
+                                p.count,
+                                if p.bumper { " bumper" } else { "" }
+                            )
+                        })
+                        .collect(),
+                    *base,
+                ),
+                _ => panic!("expected single plate weights"),
+            }
+        } else {
+            (Vec::new(), None)
+        }
+    }
+
+    let post_url = format!("/set-single-plates-set/{workout}/{exercise}");
+    let cancel_url = format!("/exercise/{workout}/{exercise}");
+
+    let weights = &state.read().unwrap().user.weights;
+    let program = &state.read().unwrap().user.program;
+    let workout = program.find(workout).unwrap();
+    let exercise = workout.find(&ExerciseName(exercise.to_owned())).unwrap();
+    let data = exercise.data();
+
+    let buttons = vec![
+        EditButton::new("add-btn", "", "Add…")
+            .with_attr("data-bs-toggle", "modal")
+            .with_attr("data-bs-target", "#add_modal"),
+        EditButton::new("delete-btn", "on_delete()", "Delete"),
+    ];
+    let set_name = data.weightset.clone().unwrap(); // only land in this function if there is a weightset
+    let units = weights.units(&set_name).unwrap_or_default();
+    let (items, base) = make_labels(weights, &set_name, units);
+    let javascript = include_str!("../../../files/plates.js");
+    let modal = include_str!("../../../files/plates-modal.html");
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_edit_menu("Edit Plates", buttons, javascript)),
+        Box::new(
+            TextInput::new(
+                "Name",
+                &set_name,
+                "The name of the weight set, e.g. \"Landmine\". Renaming it updates every exercise that uses the set.",
+            )
+            .with_required(),
+        ),
+        Box::new(
+            Radio::new(
+                "units",
+                vec![
+                    ("Pounds".to_owned(), "Lbs".to_owned()),
+                    ("Kilograms".to_owned(), "Kgs".to_owned()),
+                ],
+                "Units for the weights below. Changing this does not convert them.",
+            )
+            .with_checked(&format!("{units:?}")),
+        ),
+        Box::new(FloatInput::new(
+            "Base",
+            base,
+            "Optional fixed weight, e.g. for a machine's sled.",
+        )),
+        Box::new(
+            List::with_names(
+                "weights",
+                items,
+                "The plates in the weight set. These are added one at a time.",
+            )
+            .without_js(),
+        ),
+        Box::new(Html::new(modal)),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
@@ -0,0 +1,77 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::{ExerciseName, WeekPercents};
+use crate::pages::editor_builder::*;
+use axum::http::Uri;
+
+/// Training maxes are used with percentage based programs like 5/3/1, see
+/// [`WeekPercents`].
+pub fn get_edit_training_max(state: SharedState, workout: &str, exercise: &str) -> String {
+    let post_url = format!("/set-training-max/{workout}/{exercise}");
+    let cancel_url = format!("/exercise/{workout}/{exercise}");
+
+    let user = &state.read().unwrap().user;
+    let name = ExerciseName(exercise.to_owned());
+    let units = user.exercise_units(workout, &name);
+    let program = &user.program;
+    let workout = program.find(workout).unwrap();
+    let exercise = workout.find(&name).unwrap();
+    let data = exercise.data();
+
+    let weight = data.weight.map(|w| units.round_lbs(w));
+    let percents = data.week_percents.as_ref().map_or("".to_owned(), |p| {
+        let weeks: Vec<_> =
+            p.0.iter()
+                .map(|week| {
+                    let week: Vec<_> = week.iter().map(|p| p.to_string()).collect();
+                    week.join(" ")
+                })
+                .collect();
+        weeks.join(", ")
+    });
+    let week = program.current_week().map_or(
+        "There are no blocks so the first week is used.".to_owned(),
+        |w| format!("This is week {w} of the blocks."),
+    );
+
+    let widgets: Vec<Box<dyn Widget>> = vec![
+        Box::new(Prolog::with_title("Edit Training Max")),
+        Box::new(FloatInput::new(
+            "Training Max",
+            weight,
+            &format!("Worksets use a percentage of this, in {}.", units.name()),
+        )),
+        Box::new(
+            TextInput::new(
+                "Percents",
+                &percents,
+                &format!("Comma separated weeks of space separated workset percents, e.g. \"65 75 85, 70 80 90, 75 85 95, 40 50 60\". Weeks repeat to match the program's blocks. {week} Leave empty to use the normal percents and weight."),
+            )
+            .with_pattern(r#"\s*(\d+(\s+\d+)*(\s*,\s*\d+(\s+\d+)*)*)?\s*"#),
+        ),
+        Box::new(StdButtons::new(&cancel_url)),
+    ];
+
+    build_editor(&post_url, widgets)
+}
+
+pub fn post_set_training_max(
+    state: SharedState,
+    workout: &str,
+    exercise: &str,
+    weight: Option<f32>,
+    percents: Option<WeekPercents>,
+) -> Result<Uri, Error> {
+    let path = format!("/exercise/{workout}/{exercise}");
+    let exercise = ExerciseName(exercise.to_owned());
+
+    {
+        let user = &mut state.write().unwrap().user;
+        let units = user.exercise_units(workout, &exercise);
+        let workout = user.program.find_mut(workout).unwrap();
+        let exercise = workout.find_mut(&exercise).unwrap();
+        exercise.try_set_training_max(weight.map(|w| units.to_lbs(w)), percents)?;
+    }
+
+    crate::pages::post_epilog(state, &path)
+}
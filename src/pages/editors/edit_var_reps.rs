@@ -1,7 +1,7 @@
 use crate::app_state::SharedState;
 use crate::errors::Error;
 use crate::exercise::ExerciseName;
-use crate::exercise::{FixedReps, VariableReps};
+use crate::exercise::{Deload, FixedReps, VariableReps};
 use crate::pages::editor_builder::*;
 use axum::http::Uri;
 
@@ -43,11 +43,31 @@ pub fn get_edit_var_reps(state: SharedState, workout: &str, exercise: &str) -> S
         })
         .collect();
     let worksets = worksets.join(" ");
+    let sessions = e.deload().map(|d| d.sessions as f32);
+    let percent = e.deload().map(|d| d.percent as f32);
 
     let widgets: Vec<Box<dyn Widget>> = vec![
         Box::new(Prolog::with_title("Edit Variable Reps")),
         Box::new(TextInput::new("Warmups", &warmups, "Space separated list of rep/percent, e.g. \"5/70 3/80 1/90\". If percent is missing 100 is used.").with_pattern(r#"\s*(\d+(/\d+)?(\s+\d+(/\d+)?)*)?\s*"#)),
         Box::new(TextInput::new("Worksets", &worksets, "Formatted like warmups except min reps may be used, e.g. \"8 4-8 3-6\".").with_pattern(r#"\s*((\d+-)?\d+(/\d+)?)(\s+((\d+-)?\d+(/\d+)?))*\s*"#).with_required()),
+        Box::new(
+            FloatInput::new(
+                "Stall Sessions",
+                sessions,
+                "Optional number of sessions in a row without more reps before the weight is deloaded.",
+            )
+            .with_min(1.0)
+            .with_step(1.0),
+        ),
+        Box::new(
+            FloatInput::new(
+                "Deload Percent",
+                percent,
+                "How much to lower the weight by when deloading, e.g. 10.",
+            )
+            .with_min(1.0)
+            .with_step(1.0),
+        ),
         Box::new(StdButtons::new(&cancel_url)),
     ];
 
@@ -60,6 +80,7 @@ pub fn post_set_var_reps(
     exercise_name: &str,
     warmups: Vec<FixedReps>,
     worksets: Vec<VariableReps>,
+    deload: Option<Deload>,
 ) -> Result<Uri, Error> {
     let exercise_name = ExerciseName(exercise_name.to_owned());
 
@@ -70,6 +91,9 @@ pub fn post_set_var_reps(
         let (d, e) = exercise.expect_var_reps_mut();
         e.try_set_warmups(warmups)?;
         e.try_set_worksets(worksets)?;
+        if e.deload() != deload {
+            e.try_set_deload(deload)?;
+        }
 
         if !d.finished {
             exercise.reset(exercise.started());
@@ -14,18 +14,21 @@ pub fn get_edit_weight(state: SharedState, workout: &str, exercise: &str) -> Str
     let data = exercise.data();
 
     let mut active = "".to_string();
+    let preferred = state.read().unwrap().user.options.units;
+    let units = weights.display_units(&data.weightset, preferred);
+    let range = units.to_lbs(30.0);
     let (items, weight_set) = if let Some(name) = &data.weightset {
         if let Some(current) = data.weight {
             // println!("current: {current}");
-            let min = weights.closest(name, (current - 30.0).max(0.0)).value();
-            let max = current + 30.0;
+            let min = weights.closest(name, (current - range).max(0.0)).value();
+            let max = current + range;
             // println!("min: {min}");
             // println!("max: {max}");
 
             let mut v = vec!["None".to_string()];
             let mut value = min;
             loop {
-                let body = weights::format_weight(value, " lbs");
+                let body = units.format(value);
                 if (value - current).abs() < 0.001 {
                     // Note that if a selection is not found the first weight will be
                     // selected.
@@ -44,7 +47,7 @@ pub fn get_edit_weight(state: SharedState, workout: &str, exercise: &str) -> Str
             let mut v = vec!["None".to_string()];
             let mut value = weights.closest(name, 0.0).value();
             loop {
-                v.push(weights::format_weight(value, " lbs"));
+                v.push(units.format(value));
                 let next = weights.advance(name, value).value();
                 if (next - value).abs() < 0.001 || v.len() > 20 {
                     break;
@@ -56,21 +59,17 @@ pub fn get_edit_weight(state: SharedState, workout: &str, exercise: &str) -> Str
     } else {
         // TODO do better here
         (
-            vec![
-                weights::format_weight(5.0, " lbs"),
-                weights::format_weight(10.0, " lbs"),
-                weights::format_weight(15.0, " lbs"),
-                weights::format_weight(20.0, " lbs"),
-                weights::format_weight(25.0, " lbs"),
-                weights::format_weight(30.0, " lbs"),
-            ],
+            (1..=6)
+                .map(|i| weights::format_weight(5.0 * i as f32, units.suffix()))
+                .collect(),
             "?".to_owned(),
         )
     };
     let items: Vec<_> = items.iter().map(|b| (b.as_ref(), b.as_ref())).collect();
 
     let help = format!(
-        "Using weights from the \"{weight_set}\" weight set. +/- 30 lbs from current weight."
+        "Using weights from the \"{weight_set}\" weight set. +/- 30{} from current weight.",
+        units.suffix()
     );
 
     let widgets: Vec<Box<dyn Widget>> = vec![
@@ -2,10 +2,11 @@ use crate::app_state::SharedState;
 use crate::errors::Error;
 use crate::pages::editor_builder::*;
 use crate::program::Program;
-use crate::weights::WeightSet;
+use crate::weights::{SetSource, WeightSet, Weights};
 use axum::http::Uri;
+use std::collections::HashSet;
 
-const HELP:&'static str = "Weight sets can be generic or specific to an exercise, e.g. Dumbbbells or Deadlift. You can edit a weight set by selecting it within an exercise.";
+const HELP:&'static str = "Weight sets can be generic or specific to an exercise, e.g. Dumbbbells or Deadlift. You can edit a weight set by selecting it within an exercise. Sets that are in use can't be deleted.";
 
 fn get_weights<F>(state: SharedState, prefix: &str, post_url: &str, valid: F) -> String
 where
@@ -49,8 +50,13 @@ where
 
     let buttons = vec![
         EditButton::new("add-discrete-btn", &format!("on_add('{prefix}')"), "Add"),
+        EditButton::new("duplicate-btn", "on_duplicate()", "Duplicate"),
+        EditButton::new("rename-btn", "on_rename()", "Rename…")
+            .with_attr("data-bs-toggle", "modal")
+            .with_attr("data-bs-target", "#rename_modal"),
         EditButton::new("delete-btn", "on_delete()", "Delete"),
     ];
+    let modal = include_str!("../../../files/rename-modal.html");
 
     let widgets: Vec<Box<dyn Widget>> = vec![
         Box::new(Prolog::with_edit_menu(
@@ -59,6 +65,8 @@ where
             javascript,
         )),
         Box::new(List::with_help("sets", items, HELP).without_js()),
+        Box::new(HiddenInput::new("sources")),
+        Box::new(Html::new(modal)),
         Box::new(StdButtons::new(&cancel_url)),
     ];
 
@@ -67,7 +75,7 @@ where
 
 pub fn get_discrete_weights(state: SharedState) -> String {
     let valid = |w: &WeightSet| match w {
-        WeightSet::Discrete(_) => true,
+        WeightSet::Discrete(_, _) => true,
         _ => false,
     };
     get_weights(state, "Discrete", "/set-discrete-weights", valid)
@@ -75,30 +83,56 @@ pub fn get_discrete_weights(state: SharedState) -> String {
 
 pub fn get_plate_weights(state: SharedState) -> String {
     let valid = |w: &WeightSet| match w {
-        WeightSet::DualPlates(_, _) => true,
+        WeightSet::DualPlates(_, _, _) => true,
         _ => false,
     };
     get_weights(state, "Plates", "/set-plate-weights", valid)
 }
 
-pub fn post_set_discrete_weights(state: SharedState, sets: Vec<String>) -> Result<Uri, Error> {
-    let path = "/";
+pub fn get_single_plate_weights(state: SharedState) -> String {
+    let valid = |w: &WeightSet| matches!(w, WeightSet::SinglePlates(_, _));
+    get_weights(state, "Single Plates", "/set-single-plate-weights", valid)
+}
 
-    {
-        let weights = &mut state.write().unwrap().user.weights;
-        weights.try_set_discrete_weights(sets)?;
-    }
+pub fn post_set_discrete_weights(
+    state: SharedState,
+    sets: Vec<(String, SetSource)>,
+) -> Result<Uri, Error> {
+    post_weights(state, sets, Weights::try_set_discrete_weights)
+}
 
-    crate::pages::post_epilog(state, &path)
+pub fn post_set_plate_weights(
+    state: SharedState,
+    sets: Vec<(String, SetSource)>,
+) -> Result<Uri, Error> {
+    post_weights(state, sets, Weights::try_set_plate_weights)
 }
 
-pub fn post_set_plate_weights(state: SharedState, sets: Vec<String>) -> Result<Uri, Error> {
+pub fn post_set_single_plate_weights(
+    state: SharedState,
+    sets: Vec<(String, SetSource)>,
+) -> Result<Uri, Error> {
+    post_weights(state, sets, Weights::try_set_single_plate_weights)
+}
+
+fn post_weights<F>(state: SharedState, sets: Vec<(String, SetSource)>, set: F) -> Result<Uri, Error>
+where
+    F: Fn(
+        &mut Weights,
+        Vec<(String, SetSource)>,
+        &HashSet<String>,
+    ) -> Result<Vec<(String, String)>, Error>,
+{
     let path = "/";
 
     {
-        let weights = &mut state.write().unwrap().user.weights;
-        weights.try_set_plate_weights(sets)?;
+        // Weights are validated before anything is changed so the program only needs to
+        // be updated once they've been set.
+        let user = &mut state.write().unwrap().user;
+        let in_use = user.program.weight_sets_in_use();
+        let renames = set(&mut user.weights, sets, &in_use)?;
+        user.program.rename_weight_sets(&renames);
     }
 
-    crate::pages::post_epilog(state, &path)
+    crate::pages::post_epilog(state, path)
 }
@@ -1,11 +1,27 @@
+mod calendar_feed;
+mod calendar_page;
+mod chart_page;
 mod exercise_page;
 mod exercise_post;
+mod history_export;
+mod history_page;
+mod import_page;
+mod login_page;
 mod overview_page;
 mod program_page;
+mod program_post;
 mod workout_page;
 
+pub use calendar_feed::*;
+pub use calendar_page::*;
+pub use chart_page::*;
 pub use exercise_page::*;
 pub use exercise_post::*;
+pub use history_export::*;
+pub use history_page::*;
+pub use import_page::*;
+pub use login_page::*;
 pub use overview_page::*;
 pub use program_page::*;
+pub use program_post::*;
 pub use workout_page::*;
@@ -0,0 +1,314 @@
+use crate::app_state::{SharedServer, SharedState};
+use crate::errors::Error;
+use crate::history::History;
+use crate::program::Program;
+use crate::validation_err;
+use crate::workout::Schedule;
+use axum::http::Uri;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FEED_WEEKS: i64 = 4;
+
+/// Returns an iCalendar document with an all day event for each workout scheduled over
+/// the next weeks. Calendar apps poll this so it's public and found via a token, see
+/// [`crate::accounts::Accounts::find_calendar_user`].
+pub fn get_calendar_feed(state: SharedState, weeks: i64) -> Result<String, Error> {
+    if !(1..=52).contains(&weeks) {
+        return validation_err!("Weeks should be between 1 and 52 not {weeks}.");
+    }
+    let user = &state.read().unwrap().user;
+    Ok(feed_to_ics(
+        &user.program,
+        &user.history,
+        Local::now(),
+        weeks,
+    ))
+}
+
+/// The feed for the weeks starting at now, split out from get_calendar_feed for testing.
+fn feed_to_ics(program: &Program, history: &History, now: DateTime<Local>, weeks: i64) -> String {
+    let stamp = now.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//web-lifter//calendar feed//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        format!("X-WR-CALNAME:{}", escape(&program.name)),
+    ];
+    for delta in 0..7 * weeks {
+        let date = now + Duration::days(delta);
+        let day = date.date_naive();
+        for workout in program.find_workouts(history, date) {
+            if matches!(workout.schedule, Schedule::AnyDay) {
+                continue; // these are always due so they'd just be noise
+            }
+            if delta == 0
+                && history
+                    .completed_days(&program.name, &workout.name)
+                    .contains(&day)
+            {
+                continue;
+            }
+            lines.push("BEGIN:VEVENT".to_owned());
+            lines.push(format!("UID:{}", event_uid(program, &workout.name, day)));
+            lines.push(format!("DTSTAMP:{stamp}"));
+            lines.push(format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                (day + Duration::days(1)).format("%Y%m%d")
+            ));
+            lines.push(format!("SUMMARY:{}", escape(&workout.name)));
+
+            let mut description = Vec::new();
+            if let Some(block) = program.date_to_block(date) {
+                description.push(format!("{} block", block.name));
+            }
+            description.extend(
+                workout
+                    .exercises()
+                    .filter(|e| e.data().enabled)
+                    .map(|e| e.name().0.clone()),
+            );
+            lines.push(format!("DESCRIPTION:{}", escape(&description.join("\n"))));
+            lines.push("END:VEVENT".to_owned());
+        }
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    let mut contents = String::new();
+    for line in lines {
+        contents += &fold(&line);
+    }
+    contents
+}
+
+/// Page with the link to subscribe to the feed. Host is used to make the link absolute.
+pub fn get_calendar_feed_page(
+    server: SharedServer,
+    state: SharedState,
+    host: &str,
+) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+    let server = &server.read().unwrap();
+
+    let (http_url, webcal_url) = match server.accounts.calendar_token(&app.name) {
+        Some(token) => {
+            let url = format!("{host}/calendar.ics?token={token}");
+            (format!("http://{url}"), format!("webcal://{url}"))
+        }
+        None => (String::new(), String::new()),
+    };
+    let data = FeedData {
+        http_url,
+        webcal_url,
+        weeks: DEFAULT_FEED_WEEKS,
+    };
+
+    let template = include_str!("../../../files/calendar_feed.html");
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+/// Creates a new token for the feed, links using the old token stop working.
+pub fn post_reset_calendar_token(server: SharedServer, state: SharedState) -> Result<Uri, Error> {
+    let name = state.read().unwrap().name.clone();
+    {
+        let server = &mut server.write().unwrap();
+        if server.accounts.reset_calendar_token(&name).is_none() {
+            return validation_err!("Didn't find an account for '{name}'.");
+        }
+        if let Err(e) = crate::persist::save_accounts(&server.data_dir, &server.accounts) {
+            return Err(Error::InternalError(format!(
+                "failed to save accounts: {e}"
+            )));
+        }
+    }
+
+    let uri = "/calendar-feed".parse()?;
+    Ok(uri)
+}
+
+#[derive(Serialize, Deserialize)]
+struct FeedData {
+    http_url: String, // empty if the user doesn't have a token yet
+    webcal_url: String,
+    weeks: i64,
+}
+
+/// UIDs should be stable so that calendar apps update events instead of duplicating
+/// them.
+fn event_uid(program: &Program, workout: &str, day: NaiveDate) -> String {
+    let name: String = format!("{}-{workout}", program.name)
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}-{name}@web-lifter", day.format("%Y%m%d"))
+}
+
+/// Escapes TEXT values, see RFC 5545 section 3.3.11.
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => result += "\\\\",
+            ';' => result += "\\;",
+            ',' => result += "\\,",
+            '\n' => result += "\\n",
+            '\r' => {}
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Lines are limited to 75 octets (not counting the CRLF), longer lines are continued
+/// with a leading space. See RFC 5545 section 3.1.
+fn fold(line: &str) -> String {
+    let mut result = String::with_capacity(line.len() + 2);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            result += "\r\n ";
+            len = 1;
+        }
+        result.push(c);
+        len += c.len_utf8();
+    }
+    result += "\r\n";
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise::{BuildExercise, ExerciseName, FixedRepsExercise, FormalName};
+    use crate::program::Block;
+    use crate::workout::Workout;
+    use chrono::{Datelike, Weekday};
+
+    /// (UID, DTSTART, SUMMARY, DESCRIPTION) for each event.
+    fn parse_events(ics: &str) -> Vec<(String, String, String, String)> {
+        let mut events = Vec::new();
+        let mut fields = Vec::new();
+        for line in ics.replace("\r\n ", "").lines() {
+            if line == "BEGIN:VEVENT" {
+                fields.clear();
+            } else if line == "END:VEVENT" {
+                let find = |prefix: &str| {
+                    fields
+                        .iter()
+                        .find_map(|f: &&str| f.strip_prefix(prefix))
+                        .unwrap_or_default()
+                        .to_owned()
+                };
+                events.push((
+                    find("UID:"),
+                    find("DTSTART;VALUE=DATE:"),
+                    find("SUMMARY:"),
+                    find("DESCRIPTION:"),
+                ));
+            } else {
+                fields.push(line);
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            escape("Heavy; Bench, OHP\nDips\\"),
+            "Heavy\\; Bench\\, OHP\\nDips\\\\"
+        );
+
+        assert_eq!(fold("SUMMARY:Bench"), "SUMMARY:Bench\r\n");
+        let line = format!("DESCRIPTION:{}", "é".repeat(40));
+        let folded = fold(&line);
+        let parts: Vec<_> = folded.trim_end().split("\r\n").collect();
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|p| p.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn feed() {
+        let now = Local::now();
+        let today = now.date_naive();
+        let blocks = vec![
+            Block::new("Heavy".to_owned(), vec!["Heavy Day".to_owned()], 1),
+            Block::new("Light".to_owned(), vec!["Light Day".to_owned()], 1),
+        ];
+        let mut program = Program::with_blocks("My Program".to_owned(), blocks, now, 1);
+        let every_day = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+        let mut heavy = Workout::new("Heavy Day".to_owned(), Schedule::Days(every_day.clone()));
+        let e = FixedRepsExercise::with_reps(vec![5; 3]);
+        let squat = ExerciseName("Squat".to_owned());
+        heavy.add_exercise(
+            BuildExercise::fixed_reps(squat, FormalName("".to_owned()), e).finalize(),
+        );
+        program.add_workout(heavy);
+        program.add_workout(Workout::new(
+            "Light Day".to_owned(),
+            Schedule::Days(every_day),
+        ));
+        program.add_workout(Workout::new("Cardio".to_owned(), Schedule::Every(2)));
+        let meet = today + Duration::days(9);
+        program.add_workout(Workout::new("Meet".to_owned(), Schedule::Dates(vec![meet])));
+        program.add_workout(Workout::new("Stretch".to_owned(), Schedule::AnyDay));
+
+        let history = History::new();
+        let ics = feed_to_ics(&program, &history, now, 2);
+        let events = parse_events(&ics);
+        let day = |delta: i64| (today + Duration::days(delta)).format("%Y%m%d").to_string();
+
+        // The blocks alternate starting with Heavy this week.
+        let week_day = today.weekday().num_days_from_monday() as i64;
+        for delta in 0..14 {
+            let (workout, block) = if (week_day + delta) / 7 % 2 == 0 {
+                ("Heavy Day", "Heavy")
+            } else {
+                ("Light Day", "Light")
+            };
+            let matches: Vec<_> = events
+                .iter()
+                .filter(|e| e.1 == day(delta) && e.2.ends_with(" Day"))
+                .collect();
+            assert_eq!(matches.len(), 1);
+            assert_eq!(matches[0].2, workout);
+            assert!(matches[0].3.starts_with(&format!("{block} block")));
+        }
+        let heavy = events.iter().find(|e| e.2 == "Heavy Day").unwrap();
+        assert_eq!(heavy.3, "Heavy block\\nSquat");
+
+        let cardio: Vec<_> = events.iter().filter(|e| e.2 == "Cardio").collect();
+        let expected: Vec<_> = (0..7).map(|i| day(2 * i)).collect();
+        assert_eq!(
+            cardio.iter().map(|e| e.1.clone()).collect::<Vec<_>>(),
+            expected
+        );
+
+        let meets: Vec<_> = events.iter().filter(|e| e.2 == "Meet").collect();
+        assert_eq!(meets.len(), 1);
+        assert_eq!(meets[0].1, day(9));
+        assert_eq!(meets[0].0, format!("{}-My-Program-Meet@web-lifter", day(9)));
+
+        assert!(!events.iter().any(|e| e.2 == "Stretch"));
+
+        // UIDs are stable so calendar apps update events rather than adding new ones.
+        let uids: Vec<_> = events.iter().map(|e| e.0.clone()).collect();
+        let later = feed_to_ics(&program, &history, now + Duration::minutes(5), 2);
+        let later: Vec<_> = parse_events(&later).into_iter().map(|e| e.0).collect();
+        assert_eq!(uids, later);
+        let unique: std::collections::HashSet<_> = uids.iter().collect();
+        assert_eq!(unique.len(), uids.len());
+    }
+}
@@ -0,0 +1,355 @@
+use super::history_page::record_label;
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::history::{History, Record};
+use crate::program::Program;
+use crate::validation_err;
+use crate::weights::Units;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Month page with the workouts that were done in the past and the ones that are
+/// scheduled for today onwards. Month is formatted like "2024-03", empty for the
+/// current month.
+pub fn get_calendar_page(state: SharedState, month: &str) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+    let first = parse_month(month)?;
+
+    let template = include_str!("../../../files/calendar.html");
+    let data = CalendarData::new(&app.user.program, &app.user.history, first);
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+/// Summary of the sessions for a day, formatted like "2024-03-04".
+pub fn get_calendar_day_page(state: SharedState, date: &str) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+    let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => {
+            return validation_err!("Dates should be formatted like 2024-03-31 not '{date}'.")
+        }
+    };
+
+    let template = include_str!("../../../files/calendar_day.html");
+    let data = DayData::new(
+        &app.user.program,
+        &app.user.history,
+        app.user.options.units,
+        date,
+    );
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+fn parse_month(text: &str) -> Result<NaiveDate, Error> {
+    if text.is_empty() {
+        let today = Local::now().date_naive();
+        return Ok(today.with_day(1).unwrap());
+    }
+    match NaiveDate::parse_from_str(&format!("{text}-01"), "%Y-%m-%d") {
+        // CalendarData does month arithmetic which can overflow near chrono's limits
+        Ok(d) if (1..=9999).contains(&d.year()) => Ok(d),
+        Ok(d) => validation_err!("Year should be between 1 and 9999 not {}.", d.year()),
+        Err(_) => validation_err!("Months should be formatted like 2024-03 not '{text}'."),
+    }
+}
+
+type Sessions<'a> = BTreeMap<&'a str, Vec<(&'a ExerciseName, &'a Record)>>;
+
+/// Completed records for each day in [from, to] grouped by workout.
+fn find_sessions(
+    history: &History,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> BTreeMap<NaiveDate, Sessions<'_>> {
+    let mut sessions: BTreeMap<NaiveDate, Sessions> = BTreeMap::new();
+    for (name, record) in history.all_records() {
+        let Some(completed) = record.completed else {
+            continue;
+        };
+        let date = completed.date_naive();
+        if from <= date && date <= to {
+            sessions
+                .entry(date)
+                .or_default()
+                .entry(record.workout.as_str())
+                .or_default()
+                .push((name, record));
+        }
+    }
+    sessions
+}
+
+/// find_workouts uses the time of day so dates are converted relative to now.
+fn to_local(date: NaiveDate) -> DateTime<Local> {
+    let now = Local::now();
+    now + Duration::days((date - now.date_naive()).num_days())
+}
+
+/// Workouts predicted for date that haven't already been done that day. Empty for
+/// past days.
+fn planned<'a>(
+    program: &'a Program,
+    history: &History,
+    date: NaiveDate,
+    done: Option<&Sessions>,
+) -> Vec<&'a str> {
+    if date < Local::now().date_naive() {
+        return Vec::new();
+    }
+    program
+        .find_workouts(history, to_local(date))
+        .iter()
+        .map(|w| w.name.as_str())
+        .filter(|name| done.is_none_or(|s| !s.contains_key(name)))
+        .collect()
+}
+
+/// Blocks are only shown for days that are still to come.
+fn block_name(program: &Program, date: NaiveDate) -> String {
+    if date < Local::now().date_naive() {
+        return "".to_owned();
+    }
+    program
+        .date_to_block(to_local(date))
+        .map(|b| b.name.clone())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize)]
+struct CalendarItem {
+    name: String,
+    class: String, // "completed" or "planned"
+}
+
+#[derive(Serialize, Deserialize)]
+struct CalendarDay {
+    day: String, // "15"
+    url: String, // "/calendar/2024-03-15"
+    class: String,
+    block: String, // only set when the block changes and empty for past days
+    items: Vec<CalendarItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CalendarWeek {
+    days: Vec<CalendarDay>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CalendarData {
+    title: String, // "March 2024"
+    prev_url: String,
+    next_url: String,
+    weeks: Vec<CalendarWeek>,
+}
+
+impl CalendarData {
+    fn new(program: &Program, history: &History, first: NaiveDate) -> CalendarData {
+        let last = first + Months::new(1) - Duration::days(1);
+        let start = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+        let end = last + Duration::days(6 - last.weekday().num_days_from_monday() as i64);
+        let sessions = find_sessions(history, start, end);
+        let today = Local::now().date_naive();
+
+        let mut weeks = Vec::new();
+        let mut prev_block = "".to_owned();
+        let mut date = start;
+        while date <= end {
+            let mut days = Vec::new();
+            for _ in 0..7 {
+                let done = sessions.get(&date);
+                let mut items: Vec<_> = done
+                    .into_iter()
+                    .flat_map(|s| s.keys())
+                    .map(|name| CalendarItem {
+                        name: name.to_string(),
+                        class: "completed".to_owned(),
+                    })
+                    .collect();
+                let planned = planned(program, history, date, done);
+                items.extend(planned.iter().map(|name| CalendarItem {
+                    name: name.to_string(),
+                    class: "planned".to_owned(),
+                }));
+                let block = block_name(program, date);
+                let mut class = Vec::new();
+                if date.month() != first.month() {
+                    class.push("other_month");
+                }
+                if date == today {
+                    class.push("today");
+                }
+                days.push(CalendarDay {
+                    day: date.day().to_string(),
+                    url: format!("/calendar/{date}"),
+                    class: class.join(" "),
+                    block: if block == prev_block {
+                        "".to_owned()
+                    } else {
+                        prev_block = block.clone();
+                        block
+                    },
+                    items,
+                });
+                date += Duration::days(1);
+            }
+            weeks.push(CalendarWeek { days });
+        }
+
+        CalendarData {
+            title: first.format("%B %Y").to_string(),
+            prev_url: format!(
+                "/calendar?month={}",
+                (first - Months::new(1)).format("%Y-%m")
+            ),
+            next_url: format!(
+                "/calendar?month={}",
+                (first + Months::new(1)).format("%Y-%m")
+            ),
+            weeks,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExerciseRow {
+    name: String,
+    label: String, // "3x5 reps @ 225 lbs, felt heavy"
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionData {
+    workout: String,
+    exercises: Vec<ExerciseRow>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DayData {
+    title: String, // "Monday 4 March 2024"
+    month_url: String,
+    block: String,
+    completed: Vec<SessionData>,
+    planned: Vec<SessionData>,
+}
+
+impl DayData {
+    fn new(program: &Program, history: &History, units: Units, date: NaiveDate) -> DayData {
+        let sessions = find_sessions(history, date, date);
+        let done = sessions.get(&date);
+        let completed = done
+            .into_iter()
+            .flatten()
+            .map(|(workout, records)| {
+                let mut records = records.clone();
+                records.sort_by_key(|(_, r)| r.started);
+                SessionData {
+                    workout: workout.to_string(),
+                    exercises: records
+                        .iter()
+                        .map(|(name, record)| ExerciseRow {
+                            name: name.0.clone(),
+                            label: record_label(record, units),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let planned = planned(program, history, date, done)
+            .iter()
+            .filter_map(|name| program.find(name))
+            .map(|workout| SessionData {
+                workout: workout.name.clone(),
+                exercises: workout
+                    .exercises()
+                    .filter(|e| e.data().enabled)
+                    .map(|e| ExerciseRow {
+                        name: e.name().0.clone(),
+                        label: "".to_owned(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        DayData {
+            title: date.format("%A %-d %B %Y").to_string(),
+            month_url: format!("/calendar?month={}", date.format("%Y-%m")),
+            block: block_name(program, date),
+            completed,
+            planned,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn sessions() {
+        let mut history = History::new();
+        let squat = ExerciseName("Squat".to_owned());
+        let bench = ExerciseName("Bench".to_owned());
+        let started = Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        for (delta, workout, name) in [(0, "A", &squat), (0, "A", &bench), (2, "B", &squat)] {
+            let date = started + Duration::days(delta);
+            history.start("My", workout, name, date);
+            history.append_reps(name, 5, Some(100.0));
+            history.finish(name, date + Duration::minutes(10));
+        }
+
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let sessions = find_sessions(&history, day(1), day(31));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[&day(4)]["A"].len(), 2);
+        assert_eq!(sessions[&day(6)]["B"].len(), 1);
+        assert!(find_sessions(&history, day(5), day(5)).is_empty());
+    }
+
+    #[test]
+    fn months() {
+        assert!(
+            parse_month("2024-03").is_ok_and(|d| d == NaiveDate::from_ymd_opt(2024, 3, 1).unwrap())
+        );
+        assert!(parse_month("2024-13").is_err());
+        assert!(parse_month("March").is_err());
+        assert!(parse_month("+262142-12").is_err());
+        assert!(parse_month("+20000-01").is_err());
+        assert!(parse_month("-0001-01").is_err());
+        assert!(parse_month("9999-12").is_ok());
+    }
+
+    #[test]
+    fn far_future() {
+        // Predictions used to walk day by day (or week by week) up to the date.
+        use crate::program::Block;
+        use crate::workout::{Schedule, Workout};
+        use chrono::Weekday;
+
+        let blocks = vec![
+            Block::new("Heavy".to_owned(), vec!["A".to_owned()], 3),
+            Block::new("Light".to_owned(), vec!["B".to_owned()], 1),
+        ];
+        let mut program = Program::with_blocks("My".to_owned(), blocks, Local::now(), 1);
+        let days = vec![Weekday::Mon, Weekday::Wed, Weekday::Fri];
+        for name in ["A", "B", "C", "D"] {
+            program.add_workout(Workout::new(
+                name.to_owned(),
+                Schedule::Rotation(days.clone()),
+            ));
+        }
+
+        let first = parse_month("9999-12").unwrap_or_else(|_| panic!("should be valid"));
+        let data = CalendarData::new(&program, &History::new(), first);
+        let days: Vec<_> = data.weeks.iter().flat_map(|w| w.days.iter()).collect();
+        assert!(days.iter().any(|d| !d.block.is_empty()));
+        assert!(days
+            .iter()
+            .any(|d| d.items.iter().any(|i| i.class == "planned")));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::history::{CompletedSets, Record};
+use crate::strength::{self, OneRepMax};
+use crate::validation_err;
+use crate::weights::{self, Units};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+// Charts are drawn into a fixed size view box and then scaled by the browser.
+const WIDTH: f32 = 600.0;
+const HEIGHT: f32 = 220.0;
+const LEFT: f32 = 55.0; // room for the y axis labels
+const RIGHT: f32 = 15.0;
+const TOP: f32 = 10.0;
+const BOTTOM: f32 = 30.0; // room for the x axis labels
+
+const RANGES: [(&str, &str); 5] = [
+    ("1m", "1 Month"),
+    ("3m", "3 Months"),
+    ("6m", "6 Months"),
+    ("1y", "1 Year"),
+    ("all", "All"),
+];
+
+/// Returns a page with SVG charts of the exercise's history. Range is one of the
+/// RANGES keys.
+pub fn get_chart_page(
+    state: SharedState,
+    workout: &str,
+    exercise: &str,
+    range: &str,
+) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+    let start = range_start(range, Local::now())?;
+
+    let name = ExerciseName(exercise.to_owned());
+    let records: Vec<_> = app
+        .user
+        .history
+        .records(&name)
+        .filter(|r| r.sets.is_some())
+        .filter(|r| start.is_none_or(|s| r.started >= s))
+        .collect();
+
+    let units = app.user.exercise_units(workout, &name);
+
+    let template = include_str!("../../../files/chart.html");
+    let data = ChartData::new(
+        workout,
+        exercise,
+        range,
+        &records,
+        app.user.options.one_rep_max,
+        units,
+    );
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+fn range_start(range: &str, now: DateTime<Local>) -> Result<Option<DateTime<Local>>, Error> {
+    let days = match range {
+        "1m" => 31,
+        "3m" => 92,
+        "6m" => 183,
+        "1y" => 365,
+        "all" => return Ok(None),
+        _ => return validation_err!("Range should be one of 1m, 3m, 6m, 1y, or all."),
+    };
+    Ok(Some(now - Duration::days(days)))
+}
+
+#[derive(Serialize, Deserialize)]
+struct RangeItem {
+    url: String,
+    label: String,
+    active: String, // "active" or ""
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChartItem {
+    title: String,
+    svg: String, // "" if there's nothing to chart
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChartData {
+    workout: String,
+    exercise: String,
+    ranges: Vec<RangeItem>,
+    charts: Vec<ChartItem>,
+}
+
+impl ChartData {
+    fn new(
+        workout: &str,
+        exercise: &str,
+        range: &str,
+        records: &[&Record],
+        formula: OneRepMax,
+        units: Units,
+    ) -> ChartData {
+        let ranges = RANGES
+            .iter()
+            .map(|(key, label)| RangeItem {
+                url: format!("/chart/{workout}/{exercise}?range={key}"),
+                label: label.to_string(),
+                active: if *key == range { "active" } else { "" }.to_owned(),
+            })
+            .collect();
+
+        let durations = records
+            .iter()
+            .any(|r| matches!(r.sets, Some(CompletedSets::Durations(_))));
+        let series = if durations {
+            vec![Series::new("Longest Set", "s", records, record_duration)]
+        } else {
+            let suffix = units.suffix();
+            vec![
+                Series::new("Top Set Weight", suffix, records, |r| {
+                    strength::record_top_weight(r).map(|w| units.in_units(w))
+                }),
+                Series::new(
+                    &format!("Estimated 1RM ({})", formula.name()),
+                    suffix,
+                    records,
+                    |r| strength::record_e1rm(r, formula).map(|w| units.in_units(w)),
+                ),
+                Series::new("Volume", suffix, records, |r| {
+                    strength::record_volume(r).map(|w| units.in_units(w))
+                }),
+            ]
+        };
+        let charts = series
+            .iter()
+            .map(|s| ChartItem {
+                title: s.title.clone(),
+                svg: s.svg(),
+            })
+            .collect();
+
+        ChartData {
+            workout: workout.to_owned(),
+            exercise: exercise.to_owned(),
+            ranges,
+            charts,
+        }
+    }
+}
+
+fn record_duration(record: &Record) -> Option<f32> {
+    match &record.sets {
+        Some(CompletedSets::Durations(sets)) => {
+            sets.iter().map(|(secs, _)| *secs as f32).reduce(f32::max)
+        }
+        _ => None,
+    }
+}
+
+struct Series {
+    title: String,
+    unit: &'static str,
+    points: Vec<(DateTime<Local>, f32)>, // oldest to newest
+}
+
+impl Series {
+    fn new<F>(title: &str, unit: &'static str, records: &[&Record], value: F) -> Series
+    where
+        F: Fn(&Record) -> Option<f32>,
+    {
+        let points = records
+            .iter()
+            .filter_map(|r| value(r).map(|v| (r.started, v)))
+            .collect();
+        Series {
+            title: title.to_owned(),
+            unit,
+            points,
+        }
+    }
+
+    fn svg(&self) -> String {
+        if self.points.is_empty() {
+            return "".to_owned();
+        }
+
+        // x axis is time
+        let first = self.points.first().unwrap().0;
+        let last = self.points.last().unwrap().0;
+        let (start, end) = if last > first {
+            (first, last)
+        } else {
+            (first - Duration::days(1), last + Duration::days(1))
+        };
+        let span = (end - start).num_seconds() as f32;
+        let x = |t: DateTime<Local>| {
+            LEFT + (t - start).num_seconds() as f32 / span * (WIDTH - LEFT - RIGHT)
+        };
+
+        // y axis is the value rounded out to nice tick marks
+        let min = self.points.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+        let max = self.points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+        let (lo, hi, step) = y_ticks(min, max);
+        let y = |v: f32| TOP + (hi - v) / (hi - lo) * (HEIGHT - TOP - BOTTOM);
+
+        let mut svg = format!(
+            r#"<svg class="chart" viewBox="0 0 {WIDTH} {HEIGHT}" xmlns="http://www.w3.org/2000/svg" role="img">"#
+        );
+
+        let mut tick = lo;
+        while tick <= hi + step / 2.0 {
+            svg += &format!(
+                r#"<line class="grid" x1="{LEFT}" y1="{0:.1}" x2="{1}" y2="{0:.1}"/><text class="label" x="{2}" y="{3:.1}" text-anchor="end">{4}</text>"#,
+                y(tick),
+                WIDTH - RIGHT,
+                LEFT - 5.0,
+                y(tick) + 4.0,
+                weights::format_weight(tick, ""),
+            );
+            tick += step;
+        }
+
+        let format = if (end - start).num_days() > 365 {
+            "%b %Y"
+        } else {
+            "%-d %b"
+        };
+        let middle = start + (end - start) / 2;
+        for (t, anchor) in [(start, "start"), (middle, "middle"), (end, "end")] {
+            svg += &format!(
+                r#"<text class="label" x="{:.1}" y="{}" text-anchor="{anchor}">{}</text>"#,
+                x(t),
+                HEIGHT - 8.0,
+                t.format(format)
+            );
+        }
+
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|(t, v)| format!("{:.1},{:.1}", x(*t), y(*v)))
+            .collect();
+        svg += &format!(r#"<polyline class="line" points="{}"/>"#, points.join(" "));
+        for (t, v) in self.points.iter() {
+            svg += &format!(
+                r#"<circle class="point" cx="{:.1}" cy="{:.1}" r="3"><title>{}: {}</title></circle>"#,
+                x(*t),
+                y(*v),
+                t.format("%-d %b %Y"),
+                weights::format_weight((v * 10.0).round() / 10.0, self.unit)
+            );
+        }
+
+        svg += "</svg>";
+        svg
+    }
+}
+
+/// Returns (low, high, step) for roughly four tick marks covering min to max.
+fn y_ticks(min: f32, max: f32) -> (f32, f32, f32) {
+    let (min, max) = if max > min {
+        (min, max)
+    } else {
+        (min - 1.0, max + 1.0)
+    };
+    let raw = (max - min) / 4.0;
+    let magnitude = 10.0_f32.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 2.5, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|s| *s >= raw)
+        .unwrap_or(10.0 * magnitude);
+    let lo = (min / step).floor() * step;
+    let hi = (max / step).ceil() * step;
+    (lo, hi, step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks() {
+        assert_eq!(y_ticks(135.0, 165.0), (130.0, 170.0, 10.0));
+        assert_eq!(y_ticks(100.0, 100.0), (99.0, 101.0, 0.5));
+        assert_eq!(y_ticks(2800.0, 4100.0), (2500.0, 4500.0, 500.0));
+    }
+
+    #[test]
+    fn ranges() {
+        let now = Local::now();
+        assert_eq!(range_start("all", now).ok(), Some(None));
+        assert_eq!(
+            range_start("1m", now).ok(),
+            Some(Some(now - Duration::days(31)))
+        );
+        assert!(range_start("2w", now).is_err());
+    }
+}
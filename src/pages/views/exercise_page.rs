@@ -1,14 +1,16 @@
 use std::cmp::Ordering;
 
-use crate::app_state::SharedState;
+use crate::app_state::{SharedState, UserState};
 use crate::errors::{Error, Unwrapper};
 use crate::{
     exercise::{Exercise, ExerciseData, ExerciseName, SetIndex, VariableReps},
     history::{CompletedSets, History, Record},
-    notes::Notes,
+    options::Options,
     pages::{self},
+    plate_plan::PlatePlan,
     program::Program,
-    weights::{self, WeightSet, Weights},
+    strength::{self, PersonalRecord},
+    weights::{Units, WeightSet},
     workout::Workout,
 };
 use chrono::Local;
@@ -33,10 +35,9 @@ pub fn get_exercise_page(
     }
 
     let handlebars = &state.read().unwrap().handlebars;
-    let weights = &state.read().unwrap().user.weights;
-    let notes = &state.read().unwrap().user.notes;
-    let history = &state.read().unwrap().user.history;
-    let program = &state.read().unwrap().user.program;
+    let user = &state.read().unwrap().user;
+    let history = &user.history;
+    let program = &user.program;
 
     let template = include_str!("../../../files/exercise.html");
     let workout = program
@@ -46,16 +47,7 @@ pub fn get_exercise_page(
         .find(&exercise)
         .unwrap_or_err("failed to find exercise")?;
     let untyped = UntypedData::new(history, exercise);
-    let data = ExData::new(
-        history,
-        notes,
-        weights,
-        program,
-        workout,
-        exercise,
-        exercise.data(),
-        untyped,
-    );
+    let data = ExData::new(user, workout, exercise, exercise.data(), untyped);
     let contents = handlebars.render_template(template, &data)?;
     Ok(contents)
 }
@@ -84,6 +76,7 @@ fn reset_old(state: &SharedState, workout: &str, exercise: &str) -> bool {
 #[derive(Serialize, Deserialize)]
 struct ExerciseDataRecord {
     pub indicator: String,
+    pub pr: String, // "" or "PR 5RM, e1RM"
     pub prefix: String,
     pub kind: String,
     pub label: String,
@@ -118,6 +111,8 @@ struct UntypedData {
     reps: UntypedReps,
     target: Option<Target>,
     weight_details_suffix: String,
+    advance_label: String, // "advance weight" or "advance time (+10s)"
+    advance_time: bool,    // for durations with a target and a step
 }
 
 impl UntypedData {
@@ -150,14 +145,14 @@ impl UntypedData {
             Exercise::FixedReps(_, e) => UntypedReps::Reps {
                 min: e.set(d.current_index).reps,
                 max: e.set(d.current_index).reps,
-                unbounded: false,
+                unbounded: exercise.is_amrap(d.current_index),
             },
             Exercise::VariableReps(_, e) => {
                 let range = e.expected_range(d.current_index);
                 UntypedReps::Reps {
                     min: range.min,
                     max: range.max,
-                    unbounded: false,
+                    unbounded: exercise.is_amrap(d.current_index),
                 }
             }
             Exercise::VariableSets(_, e) => {
@@ -207,13 +202,22 @@ impl UntypedData {
         };
         let target = match exercise {
             Exercise::Durations(_, _) => None,
+            Exercise::FixedReps(_, e) if exercise.is_amrap(d.current_index) && !d.finished => {
+                // The AMRAP set needs the reps dropdown.
+                let set = e.set(d.current_index);
+                Some(Target {
+                    expected: VariableReps::new(set.reps, set.reps, set.percent),
+                    new_reps: false,
+                    reached_target: false,
+                })
+            }
             Exercise::FixedReps(_, _) => None,
             Exercise::VariableReps(_, e) => {
                 let reps = get_var_reps_done(history, exercise.name());
                 Some(Target {
                     expected: e.expected_range(d.current_index),
                     new_reps: reps != *e.expected(),
-                    reached_target: reps >= e.max_expected(),
+                    reached_target: reps >= e.max_expected() && d.amrap.is_none(), // AMRAP rule advances the weight
                 })
             }
             Exercise::VariableSets(_, e) => {
@@ -245,12 +249,37 @@ impl UntypedData {
             }
         };
         let weight_details_suffix = match exercise {
-            Exercise::Durations(_, e) => e
-                .target()
-                .map(|t| format!("target is {t}s"))
-                .unwrap_or("".to_owned()),
+            Exercise::Durations(_, e) => {
+                let done = get_durations_done(history, exercise.name());
+                match e.target() {
+                    Some(t) if d.finished && e.reached_target(&done) => {
+                        format!("reached the {t}s target, try a harder variant")
+                    }
+                    Some(t) => format!("target is {t}s"),
+                    None => "".to_owned(),
+                }
+            }
+            Exercise::VariableReps(_, e) => {
+                let done = get_var_reps_done(history, exercise.name());
+                match e.deload() {
+                    Some(deload)
+                        if d.finished && e.stalled(&done) && e.stalls() + 1 >= deload.sessions =>
+                    {
+                        format!("stalled, weight will drop {}%", deload.percent)
+                    }
+                    _ => "".to_owned(),
+                }
+            }
             _ => "".to_owned(),
         };
+        let (advance_label, advance_time) = match exercise {
+            Exercise::Durations(_, e) => {
+                let done = get_durations_done(history, exercise.name());
+                let label = format!("advance time (+{}s)", e.step().unwrap_or(0));
+                (label, d.finished && e.advanced(&done).is_some())
+            }
+            _ => ("advance weight".to_owned(), false),
+        };
 
         UntypedData {
             kind,
@@ -260,6 +289,8 @@ impl UntypedData {
             reps,
             target,
             weight_details_suffix,
+            advance_label,
+            advance_time,
         }
     }
 }
@@ -274,6 +305,7 @@ struct ExData {
     edit_weight_url: String,
     disable_edit_weight_set: String,
     edit_weight_set_url: String,
+    disable_reps_editors: String, // "disabled" or ""
 
     exercise_set: String,         // "Set 1 of 3"
     exercise_set_details: String, // "8 reps @ 145 lbs"
@@ -285,22 +317,30 @@ struct ExData {
     advance_hidden: String,       // "hidden" or ""
     update_value: String,         // "1" or "0"
     advance_value: String,        // "1" or "0"
+    advance_label: String,        // "advance weight"
     reps_title: String,           // "8 reps"
     rep_items: Vec<RepItem>,
     edit_exercise_url: String,
+    history_url: String, // history page filtered to this exercise
 }
 
 impl ExData {
     fn new(
-        history: &History,
-        notes: &Notes,
-        weights: &Weights,
-        program: &Program,
+        user: &UserState,
         workout: &Workout,
         exercise: &Exercise,
         d: &ExerciseData,
         data: UntypedData,
     ) -> ExData {
+        let UserState {
+            history,
+            notes,
+            weights,
+            options,
+            program,
+            ..
+        } = user;
+
         // Below is common to all exercise types.
         let rest = if d.finished {
             "0".to_owned()
@@ -312,7 +352,8 @@ impl ExData {
                     .map_or("0".to_owned(), |r| format!("{r}")),
             }
         };
-        let records = ExData::get_records(history, program, workout, exercise);
+        let units = weights.display_units(&d.weightset, options.units);
+        let records = ExData::get_records(history, options, units, program, workout, exercise);
         let notes = notes.html(&d.formal_name);
         let edit_weight_url = if d.weightset.is_some() {
             format!("/edit-weight/{}/{}", workout.name, exercise.name())
@@ -321,19 +362,31 @@ impl ExData {
         };
         let (disable_edit_weight_set, edit_weight_set_url) = if let Some(name) = &d.weightset {
             match weights.get(&name) {
-                Some(WeightSet::Discrete(_)) => (
+                Some(WeightSet::Discrete(_, _)) => (
                     "".to_owned(),
                     format!("/edit-discrete-weight/{}/{}", workout.name, exercise.name()),
                 ),
-                Some(WeightSet::DualPlates(_, _)) => (
+                Some(WeightSet::DualPlates(_, _, _)) => (
                     "".to_owned(),
                     format!("/edit-plates-weight/{}/{}", workout.name, exercise.name()),
                 ),
+                Some(WeightSet::SinglePlates(_, _)) => (
+                    "".to_owned(),
+                    format!(
+                        "/edit-single-plates-weight/{}/{}",
+                        workout.name,
+                        exercise.name()
+                    ),
+                ),
                 None => ("disabled".to_owned(), "#".to_owned()),
             }
         } else {
             ("disabled".to_owned(), "#".to_owned())
         };
+        let disable_reps_editors = match exercise {
+            Exercise::FixedReps(_, _) | Exercise::VariableReps(_, _) => "".to_owned(),
+            _ => "disabled".to_owned(),
+        };
 
         // Below depends on the exercise type.
         let exercise_set = if data.variable_sets {
@@ -361,13 +414,11 @@ impl ExData {
             )
         };
 
-        let w = match d.current_index {
-            SetIndex::Warmup(_) => exercise.closest_weight(weights, d.current_index),
-            SetIndex::Workset(_) => exercise.lower_weight(weights, d.current_index),
-        };
+        let week = program.current_week();
+        let w = exercise.weight_at(weights, week, d.current_index);
         let suffix = w
             .clone()
-            .map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+            .map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
         let exercise_set_details = match data.reps {
             UntypedReps::Reps {
                 min,
@@ -387,15 +438,39 @@ impl ExData {
             UntypedReps::Wait(w) => format!("{w}s{suffix}"),
         };
 
-        let wdetails = w
-            .clone()
-            .map(|w| w.details())
-            .flatten()
-            .unwrap_or("".to_owned());
-        let weight_details = if data.weight_details_suffix.is_empty() {
+        // When plates are used show how to change them from the previous set.
+        let loading = if d.finished {
+            None
+        } else {
+            PlatePlan::new(workout, weights, week).get(exercise.name(), d.current_index)
+        };
+        let wdetails = if let Some(loading) = loading {
+            loading.details()
+        } else {
+            w.clone()
+                .map(|w| w.details())
+                .flatten()
+                .unwrap_or("".to_owned())
+        };
+        let weight_details_suffix = match (&d.week_percents, d.weight, d.current_index) {
+            (Some(_), Some(max), SetIndex::Workset(_)) if !d.finished => {
+                let percent = exercise.percent(week, d.current_index);
+                let max = units.format(max);
+                if data.weight_details_suffix.is_empty() {
+                    format!("{percent}% of {max} training max")
+                } else {
+                    format!(
+                        "{percent}% of {max} training max, {}",
+                        data.weight_details_suffix
+                    )
+                }
+            }
+            _ => data.weight_details_suffix.clone(),
+        };
+        let weight_details = if weight_details_suffix.is_empty() {
             wdetails
         } else {
-            format!("{wdetails} ({})", data.weight_details_suffix) // kinda lame formatting (tho this will likely be rare)
+            format!("{wdetails} ({weight_details_suffix})") // kinda lame formatting (tho this will likely be rare)
         };
 
         let wait = match data.reps {
@@ -433,6 +508,12 @@ impl ExData {
             }
         };
         let edit_exercise_url = format!("/edit-{}/{}/{}", data.kind, workout.name, exercise.name());
+        let history_url = format!(
+            "/history?program={}&workout={}&exercise={}",
+            url_escape::encode_component(&program.name),
+            url_escape::encode_component(&workout.name),
+            url_escape::encode_component(&exercise.name().0)
+        );
 
         let mut hide_reps = "hidden".to_owned();
         let mut reps_title = "".to_owned();
@@ -453,7 +534,7 @@ impl ExData {
                 "".to_owned()
             };
             reps_title = reps_to_title(target.expected.min);
-            rep_items = reps_to_vec(target.expected);
+            rep_items = reps_to_vec(target.expected, exercise.is_amrap(d.current_index));
 
             (update_hidden, update_value) = if d.finished && target.new_reps {
                 ("".to_owned(), "1".to_owned())
@@ -468,6 +549,9 @@ impl ExData {
                 ("hidden".to_owned(), "0".to_owned())
             };
         }
+        if data.advance_time {
+            advance_hidden = "".to_owned();
+        }
 
         ExData {
             workout: workout.name.clone(),
@@ -478,6 +562,7 @@ impl ExData {
             edit_weight_url,
             disable_edit_weight_set,
             edit_weight_set_url,
+            disable_reps_editors,
 
             exercise_set,
             exercise_set_details,
@@ -485,12 +570,14 @@ impl ExData {
             wait,
             button_title,
             edit_exercise_url,
+            history_url,
 
             hide_reps,
             update_hidden,
             advance_hidden,
             update_value,
             advance_value,
+            advance_label: data.advance_label,
             reps_title,
             rep_items,
         }
@@ -498,6 +585,8 @@ impl ExData {
 
     fn get_records(
         history: &History,
+        options: &Options,
+        units: Units,
         program: &Program,
         workout: &Workout,
         exercise: &Exercise,
@@ -508,16 +597,25 @@ impl ExData {
             .map(|r| r.completed)
             .flatten();
         let in_progress = completed.is_none();
+        let (_, prs) = strength::find_prs(history.records(exercise.name()), options.one_rep_max);
         let records: Vec<&Record> = history
             .records(exercise.name())
             .rev()
             .filter(|r| r.program == program.name && r.workout == workout.name && r.sets.is_some()) // TODO add a way to disable this?
-            .take(100) // TODO add a button to pull down another 100 of history?
+            .take(100) // older records can be found using the history page
             .collect();
         records
             .iter()
             .enumerate()
-            .map(|(i, r)| record_to_record(get_delta(&records, i), r, i == 0 && in_progress))
+            .map(|(i, r)| {
+                record_to_record(
+                    get_delta(&records, i),
+                    r,
+                    prs.get(&r.id),
+                    i == 0 && in_progress,
+                    units,
+                )
+            })
             .collect()
     }
 }
@@ -530,8 +628,14 @@ fn reps_to_title(reps: i32) -> String {
     }
 }
 
-fn reps_to_vec(reps: VariableReps) -> Vec<RepItem> {
-    (1..=reps.max)
+// AMRAP sets aren't limited to max so they get extra items.
+fn reps_to_vec(reps: VariableReps, amrap: bool) -> Vec<RepItem> {
+    let max = if amrap {
+        (2 * reps.max).max(reps.max + 10)
+    } else {
+        reps.max
+    };
+    (1..=max)
         .map(|n| {
             let title = if n == 1 {
                 "1 rep".to_owned()
@@ -590,7 +694,13 @@ fn order_sets(lhs: &Vec<(i32, Option<f32>)>, rhs: &Vec<(i32, Option<f32>)>) -> O
     }
 }
 
-fn record_to_record(delta: i32, record: &Record, in_progress: bool) -> ExerciseDataRecord {
+fn record_to_record(
+    delta: i32,
+    record: &Record,
+    prs: Option<&Vec<PersonalRecord>>,
+    in_progress: bool,
+    units: Units,
+) -> ExerciseDataRecord {
     let in_progress = in_progress && record.completed.is_none();
     let indicator = if in_progress {
         "-  ".to_owned()
@@ -602,6 +712,13 @@ fn record_to_record(delta: i32, record: &Record, in_progress: bool) -> ExerciseD
         "✸ ".to_owned() // HEAVY EIGHT POINTED RECTILINEAR BLACK STAR
     };
 
+    // PRs are across all workouts and programs so they can show up even when the
+    // indicator says the record was worse than the previous one.
+    let pr = prs.map_or("".to_owned(), |prs| {
+        let labels: Vec<_> = prs.iter().map(|p| p.label()).collect();
+        format!("PR {}", labels.join(", "))
+    });
+
     // TODO
     // do we want to use stuff like "today", "yesterday", "3 days ago"?
     // wouldn't that get weird for stuff further back?
@@ -611,8 +728,8 @@ fn record_to_record(delta: i32, record: &Record, in_progress: bool) -> ExerciseD
     let (kind, mut label) = if let Some(ref sets) = record.sets {
         prefix += ", ";
         match sets {
-            CompletedSets::Durations(s) => ("durs".to_owned(), durations_to_str(s)),
-            CompletedSets::Reps(s) => ("reps".to_owned(), reps_to_str(s)),
+            CompletedSets::Durations(s) => ("durs".to_owned(), durations_to_str(s, units)),
+            CompletedSets::Reps(s) => ("reps".to_owned(), reps_to_str(s, units)),
         }
     } else {
         ("".to_owned(), "".to_owned())
@@ -632,6 +749,7 @@ fn record_to_record(delta: i32, record: &Record, in_progress: bool) -> ExerciseD
 
     ExerciseDataRecord {
         indicator,
+        pr,
         prefix,
         kind,
         label,
@@ -640,15 +758,15 @@ fn record_to_record(delta: i32, record: &Record, in_progress: bool) -> ExerciseD
     }
 }
 
-fn durations_to_str(sets: &Vec<(i32, Option<f32>)>) -> String {
-    num_to_str(sets, "secs") // TODO will need to also pass in a fn so we can get short times
+pub(super) fn durations_to_str(sets: &Vec<(i32, Option<f32>)>, units: Units) -> String {
+    num_to_str(sets, "secs", units) // TODO will need to also pass in a fn so we can get short times
 }
 
-fn reps_to_str(sets: &Vec<(i32, Option<f32>)>) -> String {
-    num_to_str(sets, "reps")
+pub(super) fn reps_to_str(sets: &Vec<(i32, Option<f32>)>, units: Units) -> String {
+    num_to_str(sets, "reps", units)
 }
 
-fn num_to_str(sets: &Vec<(i32, Option<f32>)>, unit: &str) -> String {
+fn num_to_str(sets: &Vec<(i32, Option<f32>)>, unit: &str, units: Units) -> String {
     if sets.iter().all(|s| s.1.is_none()) {
         let reps: Vec<_> = sets.iter().map(|x| format!("{}", x.0)).collect();
         let reps = pages::join_labels(reps);
@@ -656,7 +774,7 @@ fn num_to_str(sets: &Vec<(i32, Option<f32>)>, unit: &str) -> String {
     } else if !sets.is_empty() && sets[0].1.is_some() && sets.iter().all(|s| s.1 == sets[0].1) {
         let reps: Vec<_> = sets.iter().map(|x| format!("{}", x.0)).collect();
         let reps = pages::join_labels(reps);
-        let weight = weights::format_weight(sets[0].1.unwrap(), " lbs");
+        let weight = units.format(sets[0].1.unwrap());
         format!("{reps} {unit} @ {weight}")
     } else {
         pages::join_labels(
@@ -664,7 +782,7 @@ fn num_to_str(sets: &Vec<(i32, Option<f32>)>, unit: &str) -> String {
                 .map(|x| {
                     let reps = format!("{}", x.0);
                     if let Some(weight) = x.1 {
-                        let weight = weights::format_weight(weight, " lbs");
+                        let weight = units.format(weight);
                         format!("{reps} @ {weight}")
                     } else {
                         reps
@@ -675,6 +793,14 @@ fn num_to_str(sets: &Vec<(i32, Option<f32>)>, unit: &str) -> String {
     }
 }
 
+pub fn get_durations_done(history: &History, name: &ExerciseName) -> Vec<i32> {
+    let last = history.records(name).last().map_or(&None, |r| &r.sets);
+    match last {
+        Some(CompletedSets::Durations(v)) => v.iter().map(|t| t.0).collect(),
+        _ => Vec::new(),
+    }
+}
+
 pub fn get_var_reps_done(history: &History, name: &ExerciseName) -> Vec<i32> {
     let last = history.records(name).last().map_or(&None, |r| &r.sets);
     match last {
@@ -1,6 +1,7 @@
 use crate::app_state::SharedState;
 use crate::errors::Error;
 use crate::errors::Unwrapper;
+use crate::history::{CompletedSets, History};
 use crate::{
     exercise::{Exercise, ExerciseName, SetIndex},
     VarRepsOptions,
@@ -31,21 +32,11 @@ pub fn post_next_exercise(
     };
 
     if finished {
-        let name = state.write().unwrap().name.clone();
-        let user = &mut state.write().unwrap().user;
-        if let Err(e) = crate::persist::save(&name, user) {
-            println!("error saving: {e}");
-            user.errors.push(format!("{e}"));
-        }
-    };
-
-    if finished {
+        // Completing the set can change the program (e.g. deloads) so save afterwards.
         complete_set(&mut state, workout_name, exercise_name, options);
 
         let path = format!("/workout/{workout_name}");
-        let uri = url_escape::encode_path(&path);
-        let uri = uri.parse()?;
-        Ok(uri)
+        crate::pages::post_epilog(state, &path)
     } else {
         advance_set(&mut state, workout_name, exercise_name, options);
 
@@ -56,6 +47,32 @@ pub fn post_next_exercise(
     }
 }
 
+/// Called when the user finishes a durations exercise and asked to advance the times.
+pub fn post_advance_durations(
+    state: &SharedState,
+    workout_name: &str,
+    exercise_name: &str,
+) -> Result<(), Error> {
+    let name = ExerciseName(exercise_name.to_owned());
+    let done = super::get_durations_done(&state.read().unwrap().user.history, &name);
+
+    let program = &mut state.write().unwrap().user.program;
+    let workout = program
+        .find_mut(workout_name)
+        .unwrap_or_err("failed to find workout")?;
+    let exercise = workout
+        .find_mut(&name)
+        .unwrap_or_err("failed to find exercise")?;
+    if let Exercise::Durations(d, e) = exercise {
+        if d.finished {
+            if let Some(secs) = e.advanced(&done) {
+                e.try_set_durations(secs)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn post_reset_exercise(
     state: SharedState,
     workout_name: &str,
@@ -101,6 +118,7 @@ fn complete_set(
         history.finish(&exercise_name, Local::now());
     }
 
+    let adjusted = apply_amrap(state, workout_name, &exercise_name);
     if let Some(options) = options {
         let mut new_expected = {
             let history = &state.read().unwrap().user.history;
@@ -110,7 +128,16 @@ fn complete_set(
             super::get_var_reps_done(history, &exercise.name())
         };
 
-        if options.advance == 1 {
+        if adjusted {
+            // The AMRAP rule already changed the weight so start over at the min reps.
+            let program = &mut state.write().unwrap().user.program;
+            let workout = program.find_mut(workout_name).unwrap();
+            let exercise = workout.find_mut(&exercise_name).unwrap();
+            if let Exercise::VariableReps(_, e) = exercise {
+                e.reset_stalls();
+                new_expected = e.min_expected();
+            }
+        } else if options.advance == 1 {
             // Advance weight (for VariableReps)
             let new_weight = {
                 let weights = &state.read().unwrap().user.weights;
@@ -125,10 +152,20 @@ fn complete_set(
             let exercise = workout.find_mut(&exercise_name).unwrap();
             exercise.set_weight(new_weight);
             new_expected = match exercise {
-                Exercise::VariableReps(_, e) => e.min_expected().clone(),
+                Exercise::VariableReps(_, e) => {
+                    e.reset_stalls();
+                    e.min_expected().clone()
+                }
                 Exercise::VariableSets(_, _) => new_expected, // not sure what something better would be
                 _ => panic!("expected Exercise::VariableReps"),
             }
+        } else if deload_if_stalled(state, workout_name, &exercise_name, &new_expected) {
+            // Like advancing the weight the user starts over at the min reps.
+            let program = &state.read().unwrap().user.program;
+            let workout = program.find(&workout_name).unwrap();
+            let exercise = workout.find(&exercise_name).unwrap();
+            let (_, e) = exercise.expect_var_reps();
+            new_expected = e.min_expected();
         }
         if options.update == 1 {
             // Update expected (for VariableReps)
@@ -148,6 +185,108 @@ fn complete_set(
     }
 }
 
+/// Lowers the weight of a VariableReps exercise if the user has stalled too many times
+/// in a row. The reason is saved into the comment of the latest record. Returns true if
+/// the weight was lowered.
+fn deload_if_stalled(
+    state: &mut SharedState,
+    workout_name: &str,
+    exercise_name: &ExerciseName,
+    done: &[i32],
+) -> bool {
+    let user = &mut state.write().unwrap().user;
+    let units = user.exercise_units(workout_name, exercise_name);
+    let workout = user.program.find_mut(workout_name).unwrap();
+    let exercise = workout.find_mut(exercise_name).unwrap();
+    let deload = match exercise {
+        Exercise::VariableReps(_, e) => e.record_session(done),
+        _ => None,
+    };
+    let Some(deload) = deload else {
+        return false;
+    };
+
+    let old = exercise.data().weight;
+    let new = exercise
+        .deload_weight(&user.weights, deload.percent)
+        .map(|w| w.value());
+    let (Some(old), Some(new)) = (old, new) else {
+        return false;
+    };
+    if new >= old {
+        return false; // already at the smallest weight
+    }
+    exercise.set_weight(Some(new));
+
+    let sessions = if deload.sessions == 1 {
+        "1 stalled session".to_owned()
+    } else {
+        format!("{} stalled sessions", deload.sessions)
+    };
+    let reason = format!(
+        "Deloaded {}% from {} to {} after {sessions}.",
+        deload.percent,
+        units.format(old),
+        units.format(new),
+    );
+    append_comment(&mut user.history, exercise_name, reason);
+    true
+}
+
+/// Uses the reps from the AMRAP set to change the weight (or training max) of the
+/// exercise. With week percents this only happens on the last week of the cycle. The
+/// change is saved into the comment of the latest record. Returns true if the weight was
+/// changed.
+fn apply_amrap(state: &mut SharedState, workout_name: &str, exercise_name: &ExerciseName) -> bool {
+    let user = &mut state.write().unwrap().user;
+    let units = user.exercise_units(workout_name, exercise_name);
+    let week = user.program.current_week();
+    let last = user.history.records(exercise_name).last();
+    let Some(Some(CompletedSets::Reps(sets))) = last.map(|r| &r.sets) else {
+        return false;
+    };
+    let Some(&(reps, Some(set_weight))) = sets.last() else {
+        return false;
+    };
+
+    let workout = user.program.find_mut(workout_name).unwrap();
+    let exercise = workout.find_mut(exercise_name).unwrap();
+    let what = match &exercise.data().week_percents {
+        Some(percents) if !percents.last_week(week) => return false,
+        Some(_) => "training max",
+        None => "weight",
+    };
+    let old = exercise.data().weight;
+    let new = exercise
+        .amrap_weight(&user.weights, user.options.one_rep_max, reps, set_weight)
+        .map(|w| w.value());
+    let (Some(old), Some(new)) = (old, new) else {
+        return false;
+    };
+    if new == old {
+        return false;
+    }
+    exercise.set_weight(Some(new));
+
+    let reason = format!(
+        "Changed the {what} from {} to {} after {reps} reps on the AMRAP set.",
+        units.format(old),
+        units.format(new),
+    );
+    append_comment(&mut user.history, exercise_name, reason);
+    true
+}
+
+fn append_comment(history: &mut History, exercise_name: &ExerciseName, reason: String) {
+    let id = history.records(exercise_name).last().map(|r| r.id);
+    if let Some(Ok(record)) = id.map(|id| history.find_record_mut(exercise_name, id)) {
+        record.comment = match record.comment.take() {
+            Some(comment) => Some(format!("{comment} {reason}")),
+            None => Some(reason),
+        };
+    }
+}
+
 fn advance_set(
     state: &mut SharedState,
     workout_name: &str,
@@ -252,34 +391,43 @@ fn advance_set(
         let (duration, reps, weight) = {
             let weights = &state.read().unwrap().user.weights;
             let program = &state.read().unwrap().user.program;
+            let week = program.current_week();
             let workout = program.find(&workout_name).unwrap();
             let exercise = workout.find(&name).unwrap();
             match exercise {
                 Exercise::Durations(d, e) => (
                     Some(e.set(d.current_index)),
                     None,
-                    exercise.closest_weight(weights, d.current_index),
+                    exercise.closest_weight(weights, week, d.current_index),
                 ),
                 Exercise::FixedReps(d, e) => (
                     None,
-                    Some(e.set(d.current_index).reps),
+                    Some(options.map_or(e.set(d.current_index).reps, |o| o.reps)), // options are for AMRAP sets
                     match d.current_index {
-                        SetIndex::Warmup(_) => exercise.closest_weight(weights, d.current_index),
-                        SetIndex::Workset(_) => exercise.lower_weight(weights, d.current_index),
+                        SetIndex::Warmup(_) => {
+                            exercise.closest_weight(weights, week, d.current_index)
+                        }
+                        SetIndex::Workset(_) => {
+                            exercise.lower_weight(weights, week, d.current_index)
+                        }
                     },
                 ),
                 Exercise::VariableReps(d, _) => (
                     None,
                     options.map(|o| o.reps),
                     match d.current_index {
-                        SetIndex::Warmup(_) => exercise.closest_weight(weights, d.current_index),
-                        SetIndex::Workset(_) => exercise.lower_weight(weights, d.current_index),
+                        SetIndex::Warmup(_) => {
+                            exercise.closest_weight(weights, week, d.current_index)
+                        }
+                        SetIndex::Workset(_) => {
+                            exercise.lower_weight(weights, week, d.current_index)
+                        }
                     },
                 ),
                 Exercise::VariableSets(d, _) => (
                     None,
                     options.map(|o| o.reps),
-                    exercise.lower_weight(weights, d.current_index),
+                    exercise.lower_weight(weights, week, d.current_index),
                 ),
             }
         };
@@ -299,3 +447,121 @@ fn advance_set(
     }
     advance_current(state, workout_name, exercise_name);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise::{AmrapRule, BuildExercise, FixedRepsExercise, FormalName, WeekPercents};
+    use crate::exercise::{Deload, VariableReps, VariableRepsExercise};
+    use crate::workout::{Schedule, Workout};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, RwLock};
+
+    fn temp_state(test: &str, exercise: Exercise) -> (PathBuf, SharedState) {
+        let dir = std::env::temp_dir().join(format!("web-lifter-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = crate::default::make_state(&dir, "alice", false);
+        let mut workout = Workout::new("A".to_owned(), Schedule::AnyDay);
+        workout.add_exercise(exercise);
+        state.user.program.add_workout(workout);
+        (dir, Arc::new(RwLock::new(state)))
+    }
+
+    /// Does a session of the exercise with reps for each set and then finishes it.
+    fn do_session(state: &SharedState, name: &ExerciseName, reps: &[i32], weight: f32) {
+        {
+            let user = &mut state.write().unwrap().user;
+            user.history.start("My", "A", name, Local::now());
+            for r in reps {
+                user.history.append_reps(name, *r, Some(weight));
+            }
+            let workout = user.program.find_mut("A").unwrap();
+            workout.find_mut(name).unwrap().data_mut().finished = true;
+        }
+        let options = {
+            let program = &state.read().unwrap().user.program;
+            let exercise = program.find("A").unwrap().find(name).unwrap();
+            matches!(exercise, Exercise::VariableReps(_, _)).then_some(VarRepsOptions {
+                reps: 0,
+                update: 1,
+                advance: 0,
+            })
+        };
+        assert!(post_next_exercise(state.clone(), "A", &name.0, options).is_ok());
+    }
+
+    fn reload(dir: &Path, name: &ExerciseName) -> Exercise {
+        let user = crate::persist::load(dir, "alice").unwrap();
+        user.program.find("A").unwrap().find(name).unwrap().clone()
+    }
+
+    #[test]
+    fn saves_deloads() {
+        let name = ExerciseName("Squat".to_owned());
+        let worksets = vec![VariableReps::new(4, 8, 100); 2];
+        let e = VariableRepsExercise::new(Vec::new(), worksets).with_deload(Deload::new(2, 10));
+        let exercise = BuildExercise::variable_reps(name.clone(), FormalName("".to_owned()), e)
+            .with_weight(100.0)
+            .finalize();
+        let (dir, state) = temp_state("saves-deloads", exercise);
+
+        do_session(&state, &name, &[4, 4], 100.0);
+        let exercise = reload(&dir, &name);
+        assert_eq!(exercise.expect_var_reps().1.stalls(), 1);
+        assert_eq!(exercise.data().weight, Some(100.0));
+
+        do_session(&state, &name, &[4, 4], 100.0);
+        let exercise = reload(&dir, &name);
+        assert_eq!(exercise.expect_var_reps().1.stalls(), 0);
+        assert_eq!(exercise.data().weight, Some(90.0));
+        let user = crate::persist::load(&dir, "alice").unwrap();
+        let record = user.history.records(&name).last().unwrap();
+        assert!(record.completed.is_some());
+        assert!(record
+            .comment
+            .as_ref()
+            .is_some_and(|c| c.contains("Deloaded")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saves_amrap() {
+        let name = ExerciseName("Bench".to_owned());
+        let e = FixedRepsExercise::with_reps(vec![5; 3]);
+        let mut exercise = BuildExercise::fixed_reps(name.clone(), FormalName("".to_owned()), e)
+            .with_weight(200.0)
+            .finalize();
+        let percents = WeekPercents(vec![vec![65, 75, 85]]); // every week is the last week
+        assert!(exercise
+            .try_set_training_max(Some(200.0), Some(percents))
+            .is_ok());
+        assert!(exercise
+            .try_set_amrap(Some(AmrapRule::Estimate(90)))
+            .is_ok());
+        let (dir, state) = temp_state("saves-amrap", exercise);
+
+        do_session(&state, &name, &[5, 5, 10], 170.0);
+        let expected = {
+            let program = &state.read().unwrap().user.program;
+            program
+                .find("A")
+                .unwrap()
+                .find(&name)
+                .unwrap()
+                .data()
+                .weight
+        };
+        assert!(expected.is_some_and(|w| w != 200.0));
+        assert_eq!(reload(&dir, &name).data().weight, expected);
+
+        let user = crate::persist::load(&dir, "alice").unwrap();
+        let record = user.history.records(&name).last().unwrap();
+        assert!(record
+            .comment
+            .as_ref()
+            .is_some_and(|c| c.contains("training max")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::history::{CompletedSets, History, Record};
+use crate::weights::Units;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+// Weights are in the units from the user's options (history stores pounds).
+const CSV_HEADER: &str =
+    "program,workout,exercise,started,completed,set,reps,secs,weight,units,comment";
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S"; // spreadsheets understand this
+
+/// Returns the user's history as CSV with one row per set.
+pub fn get_history_csv(state: SharedState) -> Result<String, Error> {
+    let user = &state.read().unwrap().user;
+    Ok(history_to_csv(&user.history, user.options.units))
+}
+
+/// Returns the user's history as a JSON array with one entry per record.
+pub fn get_history_json(state: SharedState) -> Result<String, Error> {
+    let user = &state.read().unwrap().user;
+    let units = user.options.units;
+    let records: Vec<_> = user
+        .history
+        .all_records()
+        .into_iter()
+        .filter_map(|(name, record)| ExportRecord::new(name, record, units))
+        .collect();
+    match serde_json::to_string_pretty(&records) {
+        Ok(json) => Ok(json),
+        Err(e) => Err(Error::InternalError(format!(
+            "failed to export history: {e}"
+        ))),
+    }
+}
+
+fn history_to_csv(history: &History, units: Units) -> String {
+    let mut text = String::new();
+    text += CSV_HEADER;
+    text += "\r\n";
+    for (name, record) in history.all_records() {
+        let completed = record
+            .completed
+            .map_or(String::new(), |d| d.format(TIME_FORMAT).to_string());
+        let comment = record.comment.as_deref().unwrap_or("");
+        for (i, set) in sets(record, units).iter().enumerate() {
+            let fields = [
+                escape(&record.program),
+                escape(&record.workout),
+                escape(&name.0),
+                record.started.format(TIME_FORMAT).to_string(),
+                completed.clone(),
+                (i + 1).to_string(),
+                set.reps.map_or(String::new(), |r| r.to_string()),
+                set.secs.map_or(String::new(), |s| s.to_string()),
+                set.weight.map_or(String::new(), |w| w.to_string()),
+                set.weight.map_or("", |_| units.name()).to_owned(),
+                escape(comment),
+            ];
+            text += &fields.join(",");
+            text += "\r\n";
+        }
+    }
+    text
+}
+
+// Fields with separators, quotes, or line breaks need to be quoted (see RFC 4180).
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn sets(record: &Record, units: Units) -> Vec<ExportSet> {
+    let convert = |weight: &Option<f32>| weight.map(|w| units.round_lbs(w));
+    match &record.sets {
+        Some(CompletedSets::Durations(sets)) => sets
+            .iter()
+            .map(|(secs, weight)| ExportSet {
+                reps: None,
+                secs: Some(*secs),
+                weight: convert(weight),
+            })
+            .collect(),
+        Some(CompletedSets::Reps(sets)) => sets
+            .iter()
+            .map(|(reps, weight)| ExportSet {
+                reps: Some(*reps),
+                secs: None,
+                weight: convert(weight),
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[derive(Serialize)]
+struct ExportRecord<'a> {
+    exercise: &'a str,
+    program: &'a str,
+    workout: &'a str,
+    started: DateTime<Local>,
+    completed: Option<DateTime<Local>>,
+    units: &'static str, // for the set weights, "lbs" or "kg"
+    sets: Vec<ExportSet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'a str>,
+}
+
+impl<'a> ExportRecord<'a> {
+    // Records without sets are exercises that were started but never done.
+    fn new(name: &'a ExerciseName, record: &'a Record, units: Units) -> Option<ExportRecord<'a>> {
+        record.sets.as_ref()?;
+        Some(ExportRecord {
+            exercise: &name.0,
+            program: &record.program,
+            workout: &record.workout,
+            started: record.started,
+            completed: record.completed,
+            units: units.name(),
+            sets: sets(record, units),
+            comment: record.comment.as_deref(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ExportSet {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reps: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secs: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn csv() {
+        let mut history = History::new();
+        let squat = ExerciseName("Squat".to_owned());
+        let plank = ExerciseName("Plank".to_owned());
+        let started = Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+
+        history.start("My", "Heavy, Day", &squat, started);
+        history.append_reps(&squat, 5, Some(225.0));
+        history.append_reps(&squat, 3, Some(232.5));
+        history.finish(&squat, started + Duration::minutes(10));
+        history
+            .find_record_mut(&squat, 1)
+            .unwrap_or_else(|_| panic!("missing record"))
+            .comment = Some("felt \"heavy\"".to_owned());
+
+        history.start("My", "Light", &plank, started + Duration::days(1));
+        history.append_duration(&plank, 60, None);
+        history.finish(&plank, started + Duration::days(1) + Duration::minutes(2));
+
+        // Started but not done so no rows.
+        history.start("My", "Light", &squat, started + Duration::days(2));
+
+        let text = history_to_csv(&history, Units::Lbs);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "My,\"Heavy, Day\",Squat,2024-03-04 10:00:00,2024-03-04 10:10:00,1,5,,225,lbs,\"felt \"\"heavy\"\"\""
+        );
+        assert_eq!(
+            lines[2],
+            "My,\"Heavy, Day\",Squat,2024-03-04 10:00:00,2024-03-04 10:10:00,2,3,,232.5,lbs,\"felt \"\"heavy\"\"\""
+        );
+        assert_eq!(
+            lines[3],
+            "My,Light,Plank,2024-03-05 10:00:00,2024-03-05 10:02:00,1,,60,,,"
+        );
+
+        // Weights are written in the user's units.
+        let text = history_to_csv(&history, Units::Kgs);
+        let lines: Vec<_> = text.lines().collect();
+        assert!(lines[1].contains(",1,5,,102.05,kg,"));
+    }
+}
@@ -0,0 +1,414 @@
+use super::exercise_page::{durations_to_str, reps_to_str};
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use crate::exercise::ExerciseName;
+use crate::history::{CompletedSets, History, Record};
+use crate::validation_err;
+use crate::weights::Units;
+use axum::http::Uri;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+const PAGE_SIZE: usize = 50;
+
+/// Query parameters for the history page. Empty strings mean "don't filter".
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryFilter {
+    #[serde(default)]
+    pub program: String,
+    #[serde(default)]
+    pub workout: String,
+    #[serde(default)]
+    pub exercise: String,
+    #[serde(default)]
+    pub from: String, // "2024-03-04"
+    #[serde(default)]
+    pub to: String, // inclusive
+    #[serde(default)]
+    pub comment: String, // "on" to only include records with comments
+    #[serde(default)]
+    pub before: String, // cursor from the previous page, see Cursor
+}
+
+pub fn get_history_page(state: SharedState, filter: &HistoryFilter) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+
+    let template = include_str!("../../../files/history.html");
+    let data = HistoryData::new(&app.user.history, app.user.options.units, filter)?;
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+/// Deletes the records with the given ids and then returns to the history page with
+/// query (the filter that was used to find the records).
+pub fn post_delete_records(
+    state: SharedState,
+    ids: HashSet<u64>,
+    query: &str,
+) -> Result<Uri, Error> {
+    if ids.is_empty() {
+        return validation_err!("No records were selected.");
+    }
+    {
+        let history = &mut state.write().unwrap().user.history;
+        history.try_delete_records(&ids)?;
+    }
+
+    // post_epilog would escape the query.
+    crate::pages::post_epilog(state, "/history")?;
+    let uri = if query.is_empty() {
+        "/history".parse()?
+    } else {
+        format!("/history?{query}").parse()?
+    };
+    Ok(uri)
+}
+
+/// Position within the records, which are ordered newest to oldest. Formatted as
+/// "timestamp-id" where timestamp is milliseconds since the epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Cursor(i64, u64);
+
+impl Cursor {
+    fn new(record: &Record) -> Cursor {
+        Cursor(record.started.timestamp_millis(), record.id)
+    }
+
+    fn parse(text: &str) -> Result<Option<Cursor>, Error> {
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let parsed = text
+            .rsplit_once('-')
+            .and_then(|(t, id)| Some(Cursor(t.parse().ok()?, id.parse().ok()?)));
+        match parsed {
+            Some(cursor) => Ok(Some(cursor)),
+            None => validation_err!("Bad history cursor '{text}'."),
+        }
+    }
+
+    fn text(&self) -> String {
+        format!("{}-{}", self.0, self.1)
+    }
+}
+
+struct Filter<'a> {
+    program: &'a str,
+    workout: &'a str,
+    exercise: &'a str,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    comment: bool,
+}
+
+impl<'a> Filter<'a> {
+    fn new(filter: &'a HistoryFilter) -> Result<Filter<'a>, Error> {
+        Ok(Filter {
+            program: &filter.program,
+            workout: &filter.workout,
+            exercise: &filter.exercise,
+            from: parse_date(&filter.from)?,
+            to: parse_date(&filter.to)?,
+            comment: filter.comment == "on",
+        })
+    }
+
+    fn matches(&self, name: &ExerciseName, record: &Record) -> bool {
+        let date = record.started.date_naive();
+        (self.program.is_empty() || record.program == self.program)
+            && (self.workout.is_empty() || record.workout == self.workout)
+            && (self.exercise.is_empty() || name.0 == self.exercise)
+            && self.from.is_none_or(|d| date >= d)
+            && self.to.is_none_or(|d| date <= d)
+            && (!self.comment || record.comment.as_ref().is_some_and(|c| !c.is_empty()))
+    }
+}
+
+fn parse_date(text: &str) -> Result<Option<NaiveDate>, Error> {
+    if text.is_empty() {
+        Ok(None)
+    } else {
+        match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            Ok(d) => Ok(Some(d)),
+            Err(_) => validation_err!("Dates should be formatted like 2024-03-31 not '{text}'."),
+        }
+    }
+}
+
+/// Returns up to count matching records before cursor along with the cursor for the
+/// next page (if there is one). Only completed records are included so that the
+/// exercise the user is in the middle of can't be deleted out from under them.
+fn find_page<'a>(
+    history: &'a History,
+    filter: &Filter,
+    before: Option<Cursor>,
+    count: usize,
+) -> (Vec<(&'a ExerciseName, &'a Record)>, Option<Cursor>) {
+    let mut records = history.all_records();
+    records.retain(|(name, record)| {
+        record.completed.is_some()
+            && record.sets.is_some()
+            && before.is_none_or(|c| Cursor::new(record) < c)
+            && filter.matches(name, record)
+    });
+    records.sort_by_key(|(_, record)| std::cmp::Reverse(Cursor::new(record)));
+
+    let next = if records.len() > count {
+        records.truncate(count);
+        records.last().map(|(_, r)| Cursor::new(r))
+    } else {
+        None
+    };
+    (records, next)
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryRow {
+    id: String,
+    date: String,     // "4 Mar 2024"
+    exercise: String, // "Squat"
+    workout: String,  // "Heavy"
+    label: String,    // "3x5 reps @ 225 lbs, felt heavy"
+}
+
+#[derive(Serialize, Deserialize)]
+struct SelectItem {
+    name: String,
+    selected: String, // "selected" or ""
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryData {
+    programs: Vec<SelectItem>,
+    workouts: Vec<SelectItem>,
+    exercises: Vec<SelectItem>,
+    from: String,
+    to: String,
+    comment: String, // "checked" or ""
+    rows: Vec<HistoryRow>,
+    query: String,     // the filter (without the cursor)
+    next_url: String,  // "" if this is the last page
+    first_url: String, // "" if this is the first page
+}
+
+impl HistoryData {
+    fn new(history: &History, units: Units, query: &HistoryFilter) -> Result<HistoryData, Error> {
+        let filter = Filter::new(query)?;
+        let before = Cursor::parse(&query.before)?;
+        let (records, next) = find_page(history, &filter, before, PAGE_SIZE);
+
+        let mut programs = BTreeSet::new();
+        let mut workouts = BTreeSet::new();
+        let mut exercises = BTreeSet::new();
+        for (name, record) in history.all_records() {
+            programs.insert(record.program.as_str());
+            workouts.insert(record.workout.as_str());
+            exercises.insert(name.0.as_str());
+        }
+
+        let rows = records
+            .iter()
+            .map(|(name, record)| HistoryRow {
+                id: format!("{}", record.id),
+                date: record.started.format("%-d %b %Y").to_string(),
+                exercise: name.0.clone(),
+                workout: record.workout.clone(),
+                label: record_label(record, units),
+            })
+            .collect();
+
+        let query_text = filter_query(query);
+        let next_url = match next {
+            Some(cursor) if query_text.is_empty() => format!("/history?before={}", cursor.text()),
+            Some(cursor) => format!("/history?{query_text}&before={}", cursor.text()),
+            None => "".to_owned(),
+        };
+        let first_url = match before {
+            Some(_) if query_text.is_empty() => "/history".to_owned(),
+            Some(_) => format!("/history?{query_text}"),
+            None => "".to_owned(),
+        };
+
+        Ok(HistoryData {
+            programs: select_items(programs, &query.program),
+            workouts: select_items(workouts, &query.workout),
+            exercises: select_items(exercises, &query.exercise),
+            from: query.from.clone(),
+            to: query.to.clone(),
+            comment: if filter.comment { "checked" } else { "" }.to_owned(),
+            rows,
+            query: query_text,
+            next_url,
+            first_url,
+        })
+    }
+}
+
+fn select_items(names: BTreeSet<&str>, selected: &str) -> Vec<SelectItem> {
+    names
+        .into_iter()
+        .map(|name| SelectItem {
+            name: name.to_owned(),
+            selected: if name == selected { "selected" } else { "" }.to_owned(),
+        })
+        .collect()
+}
+
+// Query string for the filter (but not the cursor).
+fn filter_query(filter: &HistoryFilter) -> String {
+    let fields = [
+        ("program", &filter.program),
+        ("workout", &filter.workout),
+        ("exercise", &filter.exercise),
+        ("from", &filter.from),
+        ("to", &filter.to),
+        ("comment", &filter.comment),
+    ];
+    let parts: Vec<_> = fields
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("{key}={}", url_escape::encode_component(value)))
+        .collect();
+    parts.join("&")
+}
+
+pub(super) fn record_label(record: &Record, units: Units) -> String {
+    let mut label = match &record.sets {
+        Some(CompletedSets::Durations(s)) => durations_to_str(s, units),
+        Some(CompletedSets::Reps(s)) => reps_to_str(s, units),
+        None => "".to_owned(),
+    };
+    if let Some(ref comment) = record.comment {
+        label += &format!(", {comment}")
+    };
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local, TimeZone};
+
+    fn make_history() -> History {
+        let mut history = History::new();
+        let squat = ExerciseName("Squat".to_owned());
+        let bench = ExerciseName("Bench".to_owned());
+        let started = Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        for day in 0..10 {
+            let date = started + Duration::days(day);
+            let (workout, name) = if day % 2 == 0 {
+                ("Heavy", &squat)
+            } else {
+                ("Light", &bench)
+            };
+            history.start("My", workout, name, date);
+            history.append_reps(name, 5, Some(100.0 + day as f32));
+            history.finish(name, date + Duration::minutes(10));
+        }
+        history
+            .find_record_mut(&squat, 3)
+            .unwrap_or_else(|_| panic!("missing record"))
+            .comment = Some("hurt my back".to_owned());
+        history
+    }
+
+    fn weights(records: &[(&ExerciseName, &Record)]) -> Vec<f32> {
+        records
+            .iter()
+            .map(|(_, r)| match &r.sets {
+                Some(CompletedSets::Reps(s)) => s[0].1.unwrap(),
+                _ => panic!("expected reps"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paging() {
+        let history = make_history();
+        let query = HistoryFilter::default();
+        let filter = Filter::new(&query).unwrap_or_else(|_| panic!("bad filter"));
+
+        let (page, next) = find_page(&history, &filter, None, 4);
+        assert_eq!(weights(&page), vec![109.0, 108.0, 107.0, 106.0]);
+
+        let next = Cursor::parse(&next.unwrap().text()).unwrap_or(None);
+        let (page, next) = find_page(&history, &filter, next, 4);
+        assert_eq!(weights(&page), vec![105.0, 104.0, 103.0, 102.0]);
+
+        let (page, next) = find_page(&history, &filter, next, 4);
+        assert_eq!(weights(&page), vec![101.0, 100.0]);
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn filtering() {
+        let history = make_history();
+        let find = |query: HistoryFilter| {
+            let filter = Filter::new(&query).unwrap_or_else(|_| panic!("bad filter"));
+            let (page, _) = find_page(&history, &filter, None, 100);
+            weights(&page)
+        };
+
+        let query = HistoryFilter {
+            workout: "Light".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(find(query), vec![109.0, 107.0, 105.0, 103.0, 101.0]);
+
+        let query = HistoryFilter {
+            from: "2024-03-06".to_owned(),
+            to: "2024-03-08".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(find(query), vec![104.0, 103.0, 102.0]);
+
+        let query = HistoryFilter {
+            comment: "on".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(find(query), vec![102.0]);
+
+        let query = HistoryFilter {
+            from: "March 6".to_owned(),
+            ..Default::default()
+        };
+        assert!(Filter::new(&query).is_err());
+    }
+
+    #[test]
+    fn deleting() {
+        let mut history = make_history();
+        let ids = HashSet::from([2, 3, 42]);
+        assert!(history
+            .try_delete_records(&ids)
+            .is_ok_and(|count| count == 2));
+
+        let query = HistoryFilter::default();
+        let filter = Filter::new(&query).unwrap_or_else(|_| panic!("bad filter"));
+        let (page, _) = find_page(&history, &filter, None, 100);
+        assert_eq!(page.len(), 8);
+        assert!(page.iter().all(|(_, r)| !ids.contains(&r.id)));
+    }
+
+    #[test]
+    fn deleting_in_progress() {
+        let mut history = make_history();
+        let squat = ExerciseName("Squat".to_owned());
+        let started = Local.with_ymd_and_hms(2024, 3, 20, 10, 0, 0).unwrap();
+        history.start("My", "Heavy", &squat, started);
+        history.append_reps(&squat, 5, Some(120.0));
+        let id = history.records(&squat).last().unwrap().id;
+
+        let ids = HashSet::from([1, id]);
+        assert!(history.try_delete_records(&ids).is_err());
+        assert_eq!(history.records(&squat).count(), 6); // nothing was deleted
+
+        // The in progress record can still be finished.
+        history.append_reps(&squat, 5, Some(120.0));
+        history.finish(&squat, started + Duration::minutes(10));
+        assert!(history
+            .try_delete_records(&ids)
+            .is_ok_and(|count| count == 2));
+    }
+}
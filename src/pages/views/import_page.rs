@@ -0,0 +1,141 @@
+use crate::app_state::{SharedState, UserState};
+use crate::errors::Error;
+use crate::import::Import;
+use axum::http::Uri;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+pub fn get_import_page(state: SharedState, message: String) -> Result<String, Error> {
+    let app = &state.read().unwrap();
+
+    let template = include_str!("../../../files/import.html");
+    let data = ImportData::new(app.pending_import.as_ref(), &app.user, message);
+    let contents = app.handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+/// Parses an uploaded CSV file and stashes it away until the user has mapped the
+/// exercise names.
+pub fn post_import_file(state: SharedState, text: &str) -> Result<Uri, Error> {
+    let import = Import::parse(text)?;
+    state.write().unwrap().pending_import = Some(import);
+
+    let uri = "/import".parse()?;
+    Ok(uri)
+}
+
+pub fn post_cancel_import(state: SharedState) -> Result<Uri, Error> {
+    state.write().unwrap().pending_import = None;
+
+    let uri = "/import".parse()?;
+    Ok(uri)
+}
+
+/// Saves the exercise names and, if there is a pending import, adds its records to
+/// history.
+pub fn post_import_names(state: SharedState, names: Vec<(String, String)>) -> Result<Uri, Error> {
+    let path = {
+        let app = &mut *state.write().unwrap();
+        for (external, mapped) in names {
+            app.user
+                .import_names
+                .insert(external, mapped.trim().to_owned());
+        }
+
+        if let Some(import) = app.pending_import.take() {
+            let counts = import.apply(&mut app.user.history, &app.user.import_names);
+            format!(
+                "/import?added={}&duplicates={}&skipped={}",
+                counts.added, counts.duplicates, counts.unmapped
+            )
+        } else {
+            "/import".to_owned()
+        }
+    };
+
+    // post_epilog would escape the query.
+    crate::pages::post_epilog(state, "/import")?;
+    let uri = path.parse()?;
+    Ok(uri)
+}
+
+#[derive(Serialize, Deserialize)]
+struct NameData {
+    external: String,
+    mapped: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImportData {
+    message: String,
+    pending: String, // e.g. "12 exercises from Strong"
+    names: Vec<NameData>,
+    exercises: Vec<String>, // our exercise names
+}
+
+impl ImportData {
+    fn new(pending: Option<&Import>, user: &UserState, message: String) -> ImportData {
+        let mut exercises = BTreeSet::new();
+        for workout in user.program.workouts() {
+            for exercise in workout.exercises() {
+                exercises.insert(exercise.name().0.clone());
+            }
+        }
+        for name in user.history.exercise_names() {
+            exercises.insert(name.0.clone());
+        }
+
+        let names = if let Some(import) = pending {
+            import
+                .names()
+                .into_iter()
+                .map(|external| {
+                    let mapped = default_name(&external, &user.import_names, &exercises);
+                    NameData { external, mapped }
+                })
+                .collect()
+        } else {
+            let mut names: Vec<_> = user
+                .import_names
+                .iter()
+                .map(|(external, mapped)| NameData {
+                    external: external.clone(),
+                    mapped: mapped.clone(),
+                })
+                .collect();
+            names.sort_by(|lhs, rhs| lhs.external.cmp(&rhs.external));
+            names
+        };
+
+        let pending = if let Some(import) = pending {
+            let count = names.len();
+            let noun = if count == 1 { "exercise" } else { "exercises" };
+            format!("{count} {noun} from {}", import.source)
+        } else {
+            String::new()
+        };
+
+        ImportData {
+            message,
+            pending,
+            names,
+            exercises: exercises.into_iter().collect(),
+        }
+    }
+}
+
+/// Use the name the user picked last time or one of our names if it's a case-insensitive
+/// match. Otherwise records are imported using the other app's name.
+fn default_name(
+    external: &str,
+    import_names: &HashMap<String, String>,
+    exercises: &BTreeSet<String>,
+) -> String {
+    if let Some(name) = import_names.get(external) {
+        name.clone()
+    } else if let Some(name) = exercises.iter().find(|n| n.eq_ignore_ascii_case(external)) {
+        name.clone()
+    } else {
+        external.to_owned()
+    }
+}
@@ -0,0 +1,22 @@
+use crate::app_state::SharedServer;
+use crate::errors::Error;
+use serde::{Deserialize, Serialize};
+
+pub fn get_login_page(server: SharedServer, failed: bool) -> Result<String, Error> {
+    let handlebars = &server.read().unwrap().handlebars;
+
+    let template = include_str!("../../../files/login.html");
+    let error = if failed {
+        "Wrong name or password.".to_owned()
+    } else {
+        String::new()
+    };
+    let data = LoginData { error };
+    let contents = handlebars.render_template(template, &data)?;
+    Ok(contents)
+}
+
+#[derive(Serialize, Deserialize)]
+struct LoginData {
+    error: String,
+}
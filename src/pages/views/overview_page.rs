@@ -6,7 +6,7 @@ use crate::{
         VariableReps, VariableRepsExercise, VariableSetsExercise,
     },
     program::Program,
-    weights,
+    weights::{Units, Weights},
     workout::Schedule,
 };
 use serde::{Deserialize, Serialize};
@@ -14,9 +14,11 @@ use serde::{Deserialize, Serialize};
 pub fn get_overview_page(state: SharedState) -> Result<String, Error> {
     let handlebars = &state.read().unwrap().handlebars;
     let program = &state.read().unwrap().user.program;
+    let weights = &state.read().unwrap().user.weights;
+    let units = state.read().unwrap().user.options.units;
 
     let template = include_str!("../../../files/overview.html");
-    let data = OverviewData::new(program);
+    let data = OverviewData::new(program, weights, units);
     let contents = handlebars.render_template(template, &data)?;
     Ok(contents)
 }
@@ -29,7 +31,7 @@ struct OverviewData {
 const INDENT: &str = "&nbsp;&nbsp;&nbsp;&nbsp;";
 
 impl OverviewData {
-    fn new(program: &Program) -> OverviewData {
+    fn new(program: &Program, weights: &Weights, units: Units) -> OverviewData {
         let mut text = String::new();
         text += &format!("<strong>{} Program</strong><br>", program.name);
         if program.blocks().count() > 0 {
@@ -56,13 +58,22 @@ impl OverviewData {
                     .map(|d| d.to_string())
                     .collect::<Vec<String>>()
                     .join(" "),
+                Schedule::Rotation(days) => {
+                    let days: Vec<_> = days.iter().map(|d| d.to_string()).collect();
+                    format!("rotates on {}", days.join(" "))
+                }
+                Schedule::PerWeek(n) => format!("{n} times a week"),
+                Schedule::Dates(dates) => {
+                    let dates: Vec<_> = dates.iter().map(|d| d.to_string()).collect();
+                    format!("on {}", dates.join(" "))
+                }
             };
             text += "<br>";
             for exercise in workout.exercises() {
                 let d = exercise.data();
                 if d.enabled {
                     text += &format!("<u>{}</u><br>", exercise.name());
-                    text += &exercise_details(exercise);
+                    text += &exercise_details(exercise, weights, units);
                 } else {
                     text += &format!("<u>{}</u> (disabled)<br>", exercise.name());
                 }
@@ -72,7 +83,7 @@ impl OverviewData {
     }
 }
 
-fn exercise_details(exercise: &Exercise) -> String {
+fn exercise_details(exercise: &Exercise, weights: &Weights, units: Units) -> String {
     fn durations_details(e: &DurationsExercise) -> String {
         let mut text = String::new();
         let sets = &(0..e.num_sets())
@@ -81,7 +92,17 @@ fn exercise_details(exercise: &Exercise) -> String {
             .join(" ");
         text += &format!("{INDENT}sets: {sets}<br>"); // TODO use css to indent?
         if let Some(target) = e.target() {
-            text += &format!("{INDENT}target: {target}s<br>"); // TODO convert to a short time, eg secs or mins
+            // Progress is limited by the shortest set.
+            let shortest = (0..e.num_sets())
+                .map(|i| e.set(SetIndex::Workset(i)))
+                .min()
+                .unwrap_or(0);
+            let percent = (100 * shortest / target).min(100);
+            text += &format!("{INDENT}target: {target}s ({percent}% there)<br>");
+            // TODO convert to a short time, eg secs or mins
+        }
+        if let Some(step) = e.step() {
+            text += &format!("{INDENT}step: {step}s<br>");
         }
         text
     }
@@ -154,13 +175,11 @@ fn exercise_details(exercise: &Exercise) -> String {
         text
     }
 
-    fn data_details(d: &ExerciseData) -> String {
+    fn data_details(d: &ExerciseData, weights: &Weights, units: Units) -> String {
         let mut text = String::new();
         if let Some(weight) = d.weight {
-            text += &format!(
-                "{INDENT}weight: {}<br>",
-                weights::format_weight(weight, " lbs")
-            );
+            let units = weights.display_units(&d.weightset, units);
+            text += &format!("{INDENT}weight: {}<br>", units.format(weight));
         }
         if let Some(weightset) = &d.weightset {
             text += &format!("{INDENT}weight set: {weightset}<br>");
@@ -177,9 +196,9 @@ fn exercise_details(exercise: &Exercise) -> String {
         text
     }
     match exercise {
-        Exercise::Durations(d, e) => durations_details(e) + &data_details(d),
-        Exercise::FixedReps(d, e) => fixed_details(e) + &data_details(d),
-        Exercise::VariableReps(d, e) => var_reps_details(e) + &data_details(d),
-        Exercise::VariableSets(d, e) => var_sets_details(e) + &data_details(d),
+        Exercise::Durations(d, e) => durations_details(e) + &data_details(d, weights, units),
+        Exercise::FixedReps(d, e) => fixed_details(e) + &data_details(d, weights, units),
+        Exercise::VariableReps(d, e) => var_reps_details(e) + &data_details(d, weights, units),
+        Exercise::VariableSets(d, e) => var_sets_details(e) + &data_details(d, weights, units),
     }
 }
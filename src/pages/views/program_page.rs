@@ -1,7 +1,9 @@
 use crate::app_state::SharedState;
 use crate::errors::Error;
-use crate::{program::Program, workout::Workout};
-use chrono::{Datelike, Duration, Local};
+use crate::history::History;
+use crate::program::Program;
+use crate::workout::{Schedule, Workout};
+use chrono::{Datelike, Duration, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 
 pub fn get_program_page(state: SharedState) -> Result<String, Error> {
@@ -16,10 +18,11 @@ pub fn get_program_page(state: SharedState) -> Result<String, Error> {
     // Maybe use TLS?
     let handlebars = &state.read().unwrap().handlebars;
     let program = &state.read().unwrap().user.program;
+    let history = &state.read().unwrap().user.history;
 
     // Note that MDN recommends against using aria tables, see https://developer.mozilla.org/en-US/docs/Web/Accessibility/ARIA/Roles/table_role
     let template = include_str!("../../../files/program.html");
-    let data = ProgramData::new(program, error);
+    let data = ProgramData::new(program, history, error);
     let contents = handlebars.render_template(template, &data)?;
     Ok(contents)
 }
@@ -29,23 +32,34 @@ struct ProgramData {
     name: String,
     blocks: Vec<String>,
     workouts: Vec<WorkoutData>,
+    missed: Vec<MissedData>,
     error: String,
     week_disabled: String,
     notes: String,
 }
 
 impl ProgramData {
-    fn new(program: &Program, error: String) -> ProgramData {
+    fn new(program: &Program, history: &History, error: String) -> ProgramData {
         let mut workouts = Vec::new();
         for delta in 0..(20 + 1) {
             let date = Local::now() + Duration::days(delta);
-            let scheduled = program.find_workouts(date);
+            let scheduled = program.find_workouts(history, date);
             if !scheduled.is_empty() {
                 for w in scheduled.iter() {
-                    workouts.push(WorkoutData::new(w, delta));
+                    let mut data = WorkoutData::new(w, delta);
+                    if let Schedule::PerWeek(n) = w.schedule {
+                        let done = history.week_sessions(&program.name, &w.name, date);
+                        data.status_label += &format!(" ({done} of {n} this week)");
+                    }
+                    workouts.push(data);
                 }
             }
         }
+        let missed = program
+            .missed(history)
+            .iter()
+            .map(|(w, d)| MissedData::new(w, *d))
+            .collect();
         let blocks = program.blocks().map(|b| b.name.clone()).collect();
         let week_disabled = if program.blocks().count() == 0 {
             "disabled".to_string()
@@ -57,6 +71,7 @@ impl ProgramData {
             notes: program.notes.clone(),
             blocks,
             workouts,
+            missed,
             error,
             week_disabled,
         }
@@ -81,6 +96,29 @@ impl WorkoutData {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct MissedData {
+    name: String,
+    date: String, // YYYY-MM-DD for the skip and slide urls
+    label: String,
+}
+
+impl MissedData {
+    fn new(workout: &Workout, date: NaiveDate) -> MissedData {
+        let delta = (Local::now().date_naive() - date).num_days();
+        let label = if delta == 1 {
+            "Yesterday".to_owned()
+        } else {
+            format!("{}", date.format("%A %-d %B"))
+        };
+        MissedData {
+            name: workout.name.clone(),
+            date: date.to_string(),
+            label,
+        }
+    }
+}
+
 fn delta_to_label(delta: i64) -> String {
     if delta == 0 {
         "Today".to_owned()
@@ -0,0 +1,25 @@
+use crate::app_state::SharedState;
+use crate::errors::Error;
+use axum::http::Uri;
+use chrono::NaiveDate;
+
+/// Records in history that a missed session won't be done so it's no longer listed.
+pub fn post_skip_missed(state: SharedState, workout: &str, date: NaiveDate) -> Result<Uri, Error> {
+    {
+        let user = &mut state.write().unwrap().user;
+        user.program
+            .try_skip_missed(&mut user.history, workout, date)?;
+    }
+
+    crate::pages::post_epilog(state, "/")
+}
+
+pub fn post_slide_missed(state: SharedState, workout: &str, date: NaiveDate) -> Result<Uri, Error> {
+    {
+        let user = &mut state.write().unwrap().user;
+        user.program
+            .try_slide_missed(&user.history, workout, date)?;
+    }
+
+    crate::pages::post_epilog(state, "/")
+}
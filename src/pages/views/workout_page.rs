@@ -5,7 +5,7 @@ use crate::{
     exercise::{Exercise, SetIndex},
     history::History,
     program::Program,
-    weights::Weights,
+    weights::{Units, Weights},
     workout::{Schedule, Workout},
 };
 use serde::{Deserialize, Serialize};
@@ -21,9 +21,10 @@ pub fn get_workout_page(state: SharedState, workout: &str) -> Result<String, Err
     let weights = &state.read().unwrap().user.weights;
     let program = &state.read().unwrap().user.program;
     let history = &state.read().unwrap().user.history;
+    let units = state.read().unwrap().user.options.units;
 
     let template = include_str!("../../../files/workout.html");
-    let data = WorkoutData::new(history, weights, program, workout, error)?;
+    let data = WorkoutData::new(history, weights, units, program, workout, error)?;
     let contents = handlebars.render_template(template, &data)?;
     Ok(contents)
 }
@@ -41,15 +42,17 @@ impl WorkoutData {
     fn new(
         history: &History,
         weights: &Weights,
+        units: Units,
         program: &Program,
         name: &str,
         error: String,
     ) -> Result<WorkoutData, Error> {
         if let Some(workout) = program.find(name) {
+            let week = program.current_week();
             let exercises: Vec<ExerciseData> = workout
                 .exercises()
                 .filter(|e| e.data().enabled)
-                .map(|e| ExerciseData::new(history, weights, workout, e))
+                .map(|e| ExerciseData::new(history, weights, units, week, workout, e))
                 .collect();
             let total_duration = if let Some(started) = history.first_started(name) {
                 if let Some(finished) = history.last_completed(name) {
@@ -96,6 +99,8 @@ impl ExerciseData {
     fn new(
         history: &History,
         weights: &Weights,
+        units: Units,
+        week: Option<i32>,
         workout: &Workout,
         exercise: &Exercise,
     ) -> ExerciseData {
@@ -122,21 +127,21 @@ impl ExerciseData {
             color,
             workout: workout.name.clone(),
             name: exercise.name().0.clone(),
-            summary: summarize(weights, exercise),
+            summary: summarize(weights, units, week, exercise),
             duration,
         }
     }
 }
 
-fn summarize(weights: &Weights, exercise: &Exercise) -> String {
+fn summarize(weights: &Weights, units: Units, week: Option<i32>, exercise: &Exercise) -> String {
     let sets = match exercise {
         // TODO: convert to a short time, eg secs or mins
         Exercise::Durations(_, e) => (0..e.num_sets())
             .map(|i| {
                 let index = SetIndex::Workset(i);
                 let d = e.set(index);
-                let w = exercise.lower_weight(weights, index);
-                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+                let w = exercise.lower_weight(weights, week, index);
+                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
                 format!("{d}s{suffix}")
             })
             .collect(),
@@ -144,16 +149,16 @@ fn summarize(weights: &Weights, exercise: &Exercise) -> String {
             let p1 = e.set(SetIndex::Workset(0)).percent;
             if e.worksets().all(|r| r.reps == 1 && r.percent == p1) {
                 let index = SetIndex::Workset(0);
-                let w = exercise.lower_weight(weights, index);
-                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+                let w = exercise.lower_weight(weights, week, index);
+                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
                 vec![format!("{} sets{suffix}", e.num_worksets())]
             } else {
                 (0..e.num_worksets())
                     .map(|i| {
                         let index = SetIndex::Workset(i); // workout page only shows work sets
                         let r = e.set(index).reps;
-                        let w = exercise.lower_weight(weights, index);
-                        let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+                        let w = exercise.lower_weight(weights, week, index);
+                        let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
                         format!("{r} reps{suffix}")
                     })
                     .collect()
@@ -163,8 +168,8 @@ fn summarize(weights: &Weights, exercise: &Exercise) -> String {
             .map(|i| {
                 let index = SetIndex::Workset(i);
                 let r = e.expected_range(index);
-                let w = exercise.lower_weight(weights, index);
-                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+                let w = exercise.lower_weight(weights, week, index);
+                let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
                 if r.min < r.max {
                     format!("{}-{} reps{suffix}", r.min, r.max)
                 } else {
@@ -175,8 +180,8 @@ fn summarize(weights: &Weights, exercise: &Exercise) -> String {
         Exercise::VariableSets(_, e) => {
             let previous = e.get_previous().iter().sum();
             let index = SetIndex::Workset(0);
-            let w = exercise.lower_weight(weights, index);
-            let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text()));
+            let w = exercise.lower_weight(weights, week, index);
+            let suffix = w.map_or("".to_owned(), |w| format!(" @ {}", w.text(units)));
             vec![if previous == 0 {
                 format!("{} reps over 1+ sets{suffix}", e.target())
             } else if e.target() == previous {
@@ -4,40 +4,329 @@
 // until we start getting many thousands of entries. TODO would be nice to track this
 // via some sort of stat (maybe by monitoring saved file sizes).
 //
-// The serde_flow crate can help if backward compatibility starts to become an issue.
-// There's also serde_columnar but that isn't production ready (in early 2024).
+// Saved files include a schema version and older files are upgraded as they are loaded,
+// see migrations.rs. There's also serde_columnar but that isn't production ready (in
+// early 2024).
+//
+// Saves are written to a temp file which is then renamed over the real file so that a
+// crash (or a full disk) can't leave us with a truncated file. We also keep a handful of
+// timestamped backups next to the data file which load will fall back onto if the data
+// file cannot be parsed. Other errors, like a file from a newer build, are returned
+// instead: falling back would load stale data which the next save would then write over
+// the good file.
+use crate::accounts::Accounts;
 use crate::app_state::UserState;
+use crate::migrations::{self, CURRENT_VERSION};
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use serde_json::Value;
 use std::fs::File;
-use std::io::{BufWriter, Result};
-use std::path::PathBuf;
-
-pub fn save(name: &str, state: &UserState) -> Result<()> {
-    let path = get_data_path(name)?;
-    let file = File::create(path)?;
-    let file = BufWriter::new(file);
-    serde_json::to_writer(file, state)?;
-    Ok(())
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+const NUM_BACKUPS: usize = 10;
+const BACKUP_INTERVAL_MINS: i64 = 24 * 60; // don't make a new backup more often than this
+const BACKUP_FORMAT: &str = "%Y%m%d-%H%M%S";
+// User names can't contain '.' so these can't collide with user files (including their
+// temp files), see accounts::validate_name.
+const ACCOUNTS_FILE: &str = "accounts.json";
+const ACCOUNTS_TEMP: &str = ".accounts.tmp";
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u64,
+    #[serde(flatten)]
+    state: &'a UserState,
+}
+
+pub fn save(dir: &Path, name: &str, state: &UserState) -> Result<()> {
+    let path = get_data_path(dir, name)?;
+    let temp = path.with_file_name(format!("{name}.tmp"));
+    let doc = Document {
+        version: CURRENT_VERSION,
+        state,
+    };
+    write_temp(&temp, &doc)?;
+
+    if path.exists() && backup_due(name, &path)? {
+        backup(name, &path)?;
+    }
+
+    replace(&temp, &path)
 }
 
-pub fn load(name: &str) -> Result<UserState> {
-    let path = get_data_path(name)?;
+/// Returns the user accounts or an empty set of accounts if there is no accounts file.
+pub fn load_accounts(dir: &Path) -> Result<Accounts> {
+    let path = dir.join(ACCOUNTS_FILE);
+    if !path.exists() {
+        return Ok(Accounts::default());
+    }
     let file = File::open(path)?;
-    let state = serde_json::from_reader(file)?;
-    Ok(state)
+    let reader = BufReader::new(file);
+    let accounts = serde_json::from_reader(reader)?;
+    Ok(accounts)
 }
 
-// On my machine this is /Users/jessejones/Library/Application\ Support/web-lifter/mine
-fn get_data_path(name: &str) -> Result<PathBuf> {
-    if let Some(mut path) = dirs::data_dir() {
-        path.push("web-lifter");
-        if !path.exists() {
-            std::fs::create_dir(path.clone())?
+pub fn save_accounts(dir: &Path, accounts: &Accounts) -> Result<()> {
+    let path = get_data_path(dir, ACCOUNTS_FILE)?;
+    let temp = path.with_file_name(ACCOUNTS_TEMP);
+    write_temp(&temp, accounts)?;
+    replace(&temp, &path)
+}
+
+pub fn data_exists(dir: &Path, name: &str) -> bool {
+    dir.join(name).exists()
+}
+
+fn write_temp<T: Serialize>(temp: &Path, value: &T) -> Result<()> {
+    let file = File::create(temp)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, value)?;
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()
+}
+
+fn replace(temp: &Path, path: &Path) -> Result<()> {
+    std::fs::rename(temp, path)?;
+    if let Some(dir) = path.parent() {
+        // Make sure that the rename itself hits the disk. This isn't supported on all
+        // platforms so we don't treat failures as an error.
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
         }
+    }
+    Ok(())
+}
+
+enum LoadError {
+    Fatal(Error),   // e.g. permission denied or a newer version, backups won't help
+    Corrupt(Error), // missing or couldn't be parsed so try a backup
+}
+
+/// Loads the user's data file. If that is missing or cannot be parsed then the newest
+/// backup that can be read is used instead (and a note about that is added to errors).
+pub fn load(dir: &Path, name: &str) -> Result<UserState> {
+    let path = get_data_path(dir, name)?;
+    match load_path(&path) {
+        Ok(state) => Ok(state),
+        Err(LoadError::Fatal(err)) => Err(err),
+        Err(LoadError::Corrupt(err)) => {
+            for backup in find_backups(name, &path)?.iter().rev() {
+                if let Ok(mut state) = load_path(backup) {
+                    let file_name = backup.file_name().unwrap_or_default().to_string_lossy();
+                    state.errors.push(format!(
+                        "Failed to load data ({err}), using backup {file_name}"
+                    ));
+                    return Ok(state);
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+fn load_path(path: &Path) -> std::result::Result<UserState, LoadError> {
+    let file = File::open(path).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => LoadError::Corrupt(e),
+        _ => LoadError::Fatal(e),
+    })?;
+    let reader = BufReader::new(file);
+    let mut doc: Value = serde_json::from_reader(reader).map_err(|e| {
+        if e.is_io() {
+            LoadError::Fatal(e.into())
+        } else {
+            LoadError::Corrupt(e.into())
+        }
+    })?;
+    migrations::migrate(&mut doc).map_err(|e| match e.kind() {
+        ErrorKind::Unsupported => LoadError::Fatal(e),
+        _ => LoadError::Corrupt(e),
+    })?;
+    serde_json::from_value(doc).map_err(|e| LoadError::Corrupt(e.into()))
+}
+
+fn backup(name: &str, path: &Path) -> Result<()> {
+    let stamp = Local::now().format(BACKUP_FORMAT);
+    let backup = path.with_file_name(format!("{name}-{stamp}.bak"));
+    std::fs::copy(path, backup)?;
+
+    let backups = find_backups(name, path)?;
+    if backups.len() > NUM_BACKUPS {
+        for old in backups.iter().take(backups.len() - NUM_BACKUPS) {
+            std::fs::remove_file(old)?;
+        }
+    }
+    Ok(())
+}
+
+fn backup_due(name: &str, path: &Path) -> Result<bool> {
+    if let Some(newest) = find_backups(name, path)?.last() {
+        if let Some(stamp) = backup_stamp(name, newest) {
+            let elapsed = Local::now().naive_local() - stamp;
+            return Ok(elapsed.num_minutes() >= BACKUP_INTERVAL_MINS);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the backups for name sorted from oldest to newest.
+fn find_backups(name: &str, path: &Path) -> Result<Vec<PathBuf>> {
+    let mut backups = Vec::new();
+    if let Some(dir) = path.parent() {
+        for entry in std::fs::read_dir(dir)? {
+            let candidate = entry?.path();
+            if backup_stamp(name, &candidate).is_some() {
+                backups.push(candidate);
+            }
+        }
+    }
+    backups.sort(); // the timestamps sort lexicographically
+    Ok(backups)
+}
+
+// Backups look like "mine-20240315-093000.bak".
+fn backup_stamp(name: &str, path: &Path) -> Option<NaiveDateTime> {
+    let file_name = path.file_name()?.to_str()?;
+    let stamp = file_name
+        .strip_prefix(name)?
+        .strip_prefix("-")?
+        .strip_suffix(".bak")?;
+    NaiveDateTime::parse_from_str(stamp, BACKUP_FORMAT).ok()
+}
+
+// The data directory comes from the config, see config.rs.
+fn get_data_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?
+    }
+
+    Ok(dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a new empty directory for a test.
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("web-lifter-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn new_state(dir: &Path) -> UserState {
+        crate::default::make_state(dir, "alice", false).user
+    }
+
+    fn backup_path(dir: &Path, minutes_ago: i64) -> PathBuf {
+        let stamp = Local::now() - chrono::Duration::minutes(minutes_ago);
+        dir.join(format!("alice-{}.bak", stamp.format(BACKUP_FORMAT)))
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = temp_dir("rotation");
+        let path = dir.join("alice");
+        save(&dir, "alice", &new_state(&dir)).unwrap();
+        let oldest = backup_path(&dir, 10_000);
+        std::fs::write(&oldest, "old").unwrap();
+        for i in 1..NUM_BACKUPS as i64 {
+            std::fs::write(backup_path(&dir, 10_000 - i), "old").unwrap();
+        }
+        assert_eq!(find_backups("alice", &path).unwrap().len(), NUM_BACKUPS);
+
+        backup("alice", &path).unwrap();
+        let backups = find_backups("alice", &path).unwrap();
+        assert_eq!(backups.len(), NUM_BACKUPS);
+        assert!(!backups.contains(&oldest));
+        assert!(!dir.join("alice.bak").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn backup_interval() {
+        let dir = temp_dir("backup-interval");
+        let path = dir.join("alice");
+        assert!(backup_due("alice", &path).unwrap()); // no backups yet
+
+        let recent = backup_path(&dir, BACKUP_INTERVAL_MINS - 5);
+        std::fs::write(&recent, "").unwrap();
+        assert!(!backup_due("alice", &path).unwrap());
+
+        std::fs::remove_file(&recent).unwrap();
+        std::fs::write(backup_path(&dir, BACKUP_INTERVAL_MINS + 5), "").unwrap();
+        assert!(backup_due("alice", &path).unwrap());
+
+        // Saving doesn't make a new backup until the interval has passed.
+        let state = new_state(&dir);
+        save(&dir, "alice", &state).unwrap();
+        save(&dir, "alice", &state).unwrap();
+        assert_eq!(find_backups("alice", &path).unwrap().len(), 2);
+        save(&dir, "alice", &state).unwrap();
+        assert_eq!(find_backups("alice", &path).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("alice");
+        let mut state = new_state(&dir);
+        state.program.name = "Older".to_owned();
+        state.errors.clear();
+        save(&dir, "alice", &state).unwrap();
+        std::fs::rename(&path, backup_path(&dir, 60)).unwrap();
+        std::fs::write(backup_path(&dir, 30), "{\"version\": 1, ").unwrap(); // newest is bad too
+        std::fs::write(&path, "not json").unwrap();
+
+        let state = load(&dir, "alice").unwrap();
+        assert_eq!(state.program.name, "Older");
+        assert_eq!(state.errors.len(), 1);
+        assert!(state.errors[0].contains("using backup"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn newer_version() {
+        let dir = temp_dir("newer-version");
+        let state = new_state(&dir);
+        save(&dir, "alice", &state).unwrap();
+        save(&dir, "alice", &state).unwrap(); // makes a backup
+        let path = dir.join("alice");
+        assert_eq!(find_backups("alice", &path).unwrap().len(), 1);
+
+        let mut doc: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        doc["version"] = Value::from(CURRENT_VERSION + 1);
+        std::fs::write(&path, doc.to_string()).unwrap();
+
+        // The backup is readable but using it would lose the newer data.
+        let err = load(&dir, "alice").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        assert!(crate::default::make_state(&dir, "alice", false).readonly);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn accounts_user() {
+        // A user named "accounts" is mid-save while the accounts are saved.
+        let dir = temp_dir("accounts-user");
+        std::fs::write(dir.join("accounts.tmp"), "user data").unwrap();
+        save_accounts(&dir, &Accounts::default()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("accounts.tmp")).unwrap(),
+            "user data"
+        );
+        assert!(load_accounts(&dir).is_ok_and(|a| !a.any()));
+
+        save(&dir, "accounts", &new_state(&dir)).unwrap();
+        assert!(load_accounts(&dir).is_ok());
 
-        //TODO  In the future we can map my name/password to "mine".
-        path.push(name);
-        Ok(path)
-    } else {
-        Err(std::io::Error::other("Couldn't find data_dir"))
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
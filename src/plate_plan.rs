@@ -0,0 +1,308 @@
+//! Plans which plates to use for each set of a [`Workout`]. There are often several ways
+//! to load a weight, e.g. 45 + 10 or 25 x2 + 5, so when exercises share a plate weight
+//! set we pick the loadings that need the fewest plate changes from one set to the next.
+use crate::exercise::{ExerciseName, SetIndex};
+use crate::weights::{self, Plate, Weights};
+use crate::workout::Workout;
+use std::collections::HashMap;
+
+/// The plates to use for a set along with how they differ from the previous set. For
+/// DualPlates these are the plates for one side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loading {
+    pub plates: Vec<Plate>, // largest to smallest, count is the number used
+    pub added: Vec<Plate>,
+    pub removed: Vec<Plate>,
+}
+
+impl Loading {
+    fn new(previous: &[Plate], plates: &[Plate]) -> Loading {
+        Loading {
+            plates: plates.to_vec(),
+            added: subtract(plates, previous),
+            removed: subtract(previous, plates),
+        }
+    }
+
+    /// Returns something like "45 + 10 (add 10, remove 5)".
+    pub fn details(&self) -> String {
+        let mut changes = Vec::new();
+        if !self.added.is_empty() {
+            changes.push(format!("add {}", format_plates(&self.added)));
+        }
+        if !self.removed.is_empty() {
+            changes.push(format!("remove {}", format_plates(&self.removed)));
+        }
+        if changes.is_empty() {
+            changes.push("no change".to_owned());
+        }
+
+        let plates = if self.plates.is_empty() {
+            "no plates".to_owned()
+        } else {
+            format_plates(&self.plates)
+        };
+        format!("{plates} ({})", changes.join(", "))
+    }
+
+    fn num_changes(&self) -> i32 {
+        self.added
+            .iter()
+            .chain(self.removed.iter())
+            .map(|p| p.count)
+            .sum()
+    }
+}
+
+type SetKey = (ExerciseName, SetIndex);
+
+/// Loadings for every set in a workout that uses plates.
+pub struct PlatePlan {
+    loadings: HashMap<SetKey, Loading>,
+}
+
+impl PlatePlan {
+    /// Sets are assumed to be done in order, exercise by exercise, and the bar is assumed
+    /// to start out empty. Disabled exercises are skipped. Week is from
+    /// Program::current_week.
+    pub fn new(workout: &Workout, weights: &Weights, week: Option<i32>) -> PlatePlan {
+        // Each weight set is loaded independently so group the sets by weight set.
+        let mut sequences: HashMap<&str, Vec<_>> = HashMap::new();
+        for exercise in workout.exercises() {
+            let d = exercise.data();
+            let Some(name) = &d.weightset else {
+                continue;
+            };
+            if !d.enabled {
+                continue;
+            }
+            for index in exercise.set_indexes() {
+                if let Some(weight) = exercise.weight_at(weights, week, index) {
+                    let candidates = weights.loadings(name, &weight);
+                    if !candidates.is_empty() {
+                        let key = (exercise.name().clone(), index);
+                        sequences.entry(name).or_default().push((key, candidates));
+                    }
+                }
+            }
+        }
+
+        let mut loadings = HashMap::new();
+        for sequence in sequences.into_values() {
+            let (keys, candidates): (Vec<_>, Vec<_>) = sequence.into_iter().unzip();
+            for (key, loading) in keys.into_iter().zip(plan_sequence(&candidates)) {
+                loadings.insert(key, loading);
+            }
+        }
+        PlatePlan { loadings }
+    }
+
+    /// VariableSets only have a loading for their first set so worksets that weren't
+    /// planned reuse the first workset's loading with no changes.
+    pub fn get(&self, exercise: &ExerciseName, index: SetIndex) -> Option<Loading> {
+        if let Some(loading) = self.loadings.get(&(exercise.clone(), index)) {
+            Some(loading.clone())
+        } else if let SetIndex::Workset(i) = index {
+            let first = self
+                .loadings
+                .get(&(exercise.clone(), SetIndex::Workset(0)))
+                .filter(|_| i > 0)?;
+            Some(Loading::new(&first.plates, &first.plates))
+        } else {
+            None
+        }
+    }
+}
+
+/// Candidates has the ways of loading each set. Returns the loadings for each set that
+/// minimize the total number of plates added and removed. Ties go to the earlier
+/// candidate, i.e. the one that Weights would normally use.
+fn plan_sequence(candidates: &[Vec<Vec<Plate>>]) -> Vec<Loading> {
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    // costs[i][j] is the fewest changes needed to get to candidates[i][j] along with the
+    // index of the candidate used for the set before it.
+    let mut costs: Vec<Vec<(i32, usize)>> = Vec::with_capacity(candidates.len());
+    costs.push(
+        candidates[0]
+            .iter()
+            .map(|c| (Loading::new(&[], c).num_changes(), 0))
+            .collect(),
+    );
+    for i in 1..candidates.len() {
+        let row = candidates[i]
+            .iter()
+            .map(|c| {
+                let mut best = (i32::MAX, 0);
+                for (j, previous) in candidates[i - 1].iter().enumerate() {
+                    let cost = costs[i - 1][j].0 + Loading::new(previous, c).num_changes();
+                    if cost < best.0 {
+                        best = (cost, j);
+                    }
+                }
+                best
+            })
+            .collect();
+        costs.push(row);
+    }
+
+    // Walk backwards from the cheapest final loading.
+    let last = costs.len() - 1;
+    let mut choice = 0;
+    for (j, cost) in costs[last].iter().enumerate() {
+        if cost.0 < costs[last][choice].0 {
+            choice = j;
+        }
+    }
+    let mut choices = vec![0; candidates.len()];
+    for i in (0..candidates.len()).rev() {
+        choices[i] = choice;
+        choice = costs[i][choice].1;
+    }
+
+    let mut previous: &[Plate] = &[];
+    let mut loadings = Vec::with_capacity(candidates.len());
+    for (i, choice) in choices.into_iter().enumerate() {
+        let plates = &candidates[i][choice];
+        loadings.push(Loading::new(previous, plates));
+        previous = plates;
+    }
+    loadings
+}
+
+/// Returns the plates in lhs that aren't in rhs, largest to smallest.
+fn subtract(lhs: &[Plate], rhs: &[Plate]) -> Vec<Plate> {
+    let same = |a: &Plate, b: &Plate| {
+        weights::scaled(a.weight) == weights::scaled(b.weight) && a.bumper == b.bumper
+    };
+    let mut result: Vec<_> = lhs
+        .iter()
+        .filter_map(|p| {
+            let other: i32 = rhs.iter().filter(|q| same(p, q)).map(|q| q.count).sum();
+            if p.count > other {
+                Some(Plate {
+                    count: p.count - other,
+                    ..*p
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap());
+    result
+}
+
+fn format_plates(plates: &[Plate]) -> String {
+    plates
+        .iter()
+        .map(|p| {
+            if p.count == 1 {
+                weights::format_weight(p.weight, "")
+            } else {
+                weights::format_weight(p.weight, &format!(" x{}", p.count))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plates(weights: &[f32]) -> Vec<Plate> {
+        let mut plates: Vec<Plate> = Vec::new();
+        for w in weights {
+            match plates.iter_mut().find(|p| p.weight == *w) {
+                Some(p) => p.count += 1,
+                None => plates.push(Plate::new(*w, 1)),
+            }
+        }
+        plates
+    }
+
+    #[test]
+    fn details() {
+        let loading = Loading::new(&plates(&[45.0, 5.0]), &plates(&[45.0, 10.0]));
+        assert_eq!(loading.details(), "45 + 10 (add 10, remove 5)");
+
+        let loading = Loading::new(&[], &plates(&[25.0, 25.0]));
+        assert_eq!(loading.details(), "25 x2 (add 25 x2)");
+
+        let loading = Loading::new(&plates(&[45.0]), &plates(&[45.0]));
+        assert_eq!(loading.details(), "45 (no change)");
+
+        let loading = Loading::new(&plates(&[45.0]), &[]);
+        assert_eq!(loading.details(), "no plates (remove 45)");
+    }
+
+    #[test]
+    fn fewest_changes() {
+        // 50 could be 45 + 5 or 25 x2 but 25 x2 means not having to strip the 25 for 75
+        let candidates = vec![
+            vec![plates(&[25.0])],
+            vec![plates(&[45.0, 5.0]), plates(&[25.0, 25.0])],
+            vec![plates(&[45.0, 25.0, 5.0]), plates(&[25.0, 25.0, 25.0])],
+        ];
+        let loadings = plan_sequence(&candidates);
+        assert_eq!(loadings[1].details(), "25 x2 (add 25)");
+        assert_eq!(loadings[2].details(), "25 x3 (add 25)");
+
+        // ties use the normal loading
+        let candidates = vec![vec![plates(&[45.0, 5.0]), plates(&[25.0, 25.0])]];
+        let loadings = plan_sequence(&candidates);
+        assert_eq!(loadings[0].details(), "45 + 5 (add 45 + 5)");
+    }
+
+    #[test]
+    fn workout() {
+        use crate::exercise::{BuildExercise, FixedRepsExercise, FormalName};
+        use crate::weights::WeightSet;
+        use crate::workout::Schedule;
+
+        let mut weights = Weights::new();
+        let set = WeightSet::DualPlates(
+            vec![
+                Plate::new(5.0, 4),
+                Plate::new(10.0, 4),
+                Plate::new(25.0, 4),
+                Plate::new(45.0, 4),
+            ],
+            Some(45.0),
+            None,
+        );
+        weights.add("Bar".to_owned(), set);
+
+        let mut workout = Workout::new("Heavy".to_owned(), Schedule::AnyDay);
+        for (name, weight) in [("Squat", 145.0), ("Bench", 115.0)] {
+            let name = ExerciseName(name.to_owned());
+            let formal_name = FormalName("".to_owned());
+            let e = FixedRepsExercise::with_reps(vec![5, 5]);
+            let exercise = BuildExercise::fixed_reps(name, formal_name, e)
+                .with_weightset("Bar".to_owned())
+                .with_weight(weight)
+                .finalize();
+            workout.add_exercise(exercise);
+        }
+
+        let plan = PlatePlan::new(&workout, &weights, None);
+        let squat = ExerciseName("Squat".to_owned());
+        let bench = ExerciseName("Bench".to_owned());
+
+        // 145 is normally 45 + 5 a side but 25 x2 is closer to the bench's 25 + 10
+        let loading = plan.get(&squat, SetIndex::Workset(0)).unwrap();
+        assert_eq!(loading.details(), "25 x2 (add 25 x2)");
+        let loading = plan.get(&squat, SetIndex::Workset(1)).unwrap();
+        assert_eq!(loading.details(), "25 x2 (no change)");
+        let loading = plan.get(&bench, SetIndex::Workset(0)).unwrap();
+        assert_eq!(loading.details(), "25 + 10 (add 10, remove 25)");
+
+        // sets past the planned ones, e.g. for VariableSets, don't change anything
+        let loading = plan.get(&bench, SetIndex::Workset(2)).unwrap();
+        assert_eq!(loading.details(), "25 + 10 (no change)");
+        assert_eq!(plan.get(&bench, SetIndex::Warmup(0)), None);
+    }
+}
@@ -2,9 +2,10 @@ use crate::days::Days;
 use crate::validation_err;
 use crate::{
     errors::Error,
+    history::History,
     workout::{Schedule, Workout},
 };
-use chrono::{DateTime, Datelike, Duration, Local, Weekday};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -27,6 +28,14 @@ impl Block {
     }
 }
 
+/// A missed session that the user slid forward to a later day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Moved {
+    pub workout: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
 /// Set of [`Workout`]`s to perform.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Program {
@@ -35,10 +44,11 @@ pub struct Program {
     blocks_start: Option<DateTime<Local>>, // TODO user can set current week to adjust this
     workouts: Vec<Workout>,
     pub notes: String,
+    pub shift_missed: bool, // sliding a missed session also pushes back the rest of the week
+    moved: Vec<Moved>,
 }
 
 impl Program {
-    #[allow(dead_code)]
     pub fn new(name: String) -> Program {
         Program {
             name,
@@ -46,6 +56,8 @@ impl Program {
             blocks_start: None,
             workouts: Vec::new(),
             notes: String::new(),
+            shift_missed: false,
+            moved: Vec::new(),
         }
     }
 
@@ -68,6 +80,8 @@ impl Program {
             blocks_start: None,
             workouts: Vec::new(),
             notes: String::new(),
+            shift_missed: false,
+            moved: Vec::new(),
         };
         program.set_week(now, week);
         program
@@ -118,6 +132,31 @@ impl Program {
         Ok(())
     }
 
+    /// Moves a missed session to today. If shift_missed is set then the sessions
+    /// remaining in the week are also pushed back.
+    pub fn try_slide_missed(
+        &mut self,
+        history: &History,
+        workout: &str,
+        from: NaiveDate,
+    ) -> Result<(), Error> {
+        self.validate_slide_missed(history, workout, from)?;
+        self.do_slide_missed(history, workout, from);
+        Ok(())
+    }
+
+    /// Records in history that a missed session won't be done.
+    pub fn try_skip_missed(
+        &self,
+        history: &mut History,
+        workout: &str,
+        date: NaiveDate,
+    ) -> Result<(), Error> {
+        self.validate_skip_missed(history, workout, date)?;
+        history.skip(&self.name, workout, date);
+        Ok(())
+    }
+
     pub fn try_set_blocks(&mut self, blocks: Vec<String>) -> Result<(), Error> {
         self.validate_set_blocks(&blocks)?;
         self.do_set_blocks(blocks);
@@ -158,6 +197,22 @@ impl Program {
         }
     }
 
+    /// The week within the blocks starting at 1, e.g. with a three week block followed by
+    /// a one week block this cycles from 1 to 4. None if there are no blocks.
+    pub fn current_week(&self) -> Option<i32> {
+        self.date_to_week(Local::now())
+    }
+
+    pub fn date_to_week(&self, date: DateTime<Local>) -> Option<i32> {
+        let blocks_start = self.blocks_start?;
+        if self.blocks.is_empty() || date < blocks_start {
+            return None;
+        }
+        let (i, block_start) = find_active(blocks_start, &self.blocks, date);
+        let before: i32 = self.blocks[..i].iter().map(|b| b.num_weeks).sum();
+        Some(before + (date - block_start).num_weeks() as i32 + 1)
+    }
+
     pub fn in_block(&self, workout: &Workout) -> bool {
         for block in &self.blocks {
             if block.workouts.contains(&workout.name) {
@@ -171,8 +226,69 @@ impl Program {
         self.workouts.iter()
     }
 
-    /// Return all workouts that should be performed on the specified date.
-    pub fn find_workouts(&self, date: DateTime<Local>) -> Vec<&Workout> {
+    /// Return all workouts that should be performed on the specified date. History is
+    /// used to find when workouts were last done.
+    pub fn find_workouts(&self, history: &History, date: DateTime<Local>) -> Vec<&Workout> {
+        let day = date.date_naive();
+        let mut workouts: Vec<&Workout> = self
+            .planned_workouts(history, date)
+            .into_iter()
+            .filter(|w| {
+                !self
+                    .moved
+                    .iter()
+                    .any(|m| m.workout == w.name && m.from == day)
+            })
+            .collect();
+        for m in self.moved.iter().filter(|m| m.to == day) {
+            if let Some(workout) = self.find(&m.workout) {
+                if workout.enabled && !workouts.iter().any(|w| w.name == workout.name) {
+                    workouts.insert(0, workout);
+                }
+            }
+        }
+        workouts
+    }
+
+    /// Returns the fixed sessions from the last week that weren't done, oldest first.
+    /// A session counts as done if the workout was finished on that day or later but
+    /// before its next session. Workouts that have never been done are ignored so that
+    /// starting a new program doesn't show a week of missed sessions.
+    pub fn missed(&self, history: &History) -> Vec<(&Workout, NaiveDate)> {
+        let now = Local::now();
+        let today = now.date_naive();
+        let mut missed = Vec::new();
+        for delta in (1..=7).rev() {
+            let date = now - Duration::days(delta);
+            let day = date.date_naive();
+            for workout in self.find_workouts(history, date) {
+                if !matches!(workout.schedule, Schedule::Days(_) | Schedule::Dates(_))
+                    || history.was_skipped(&self.name, &workout.name, day)
+                {
+                    continue;
+                }
+                let completed = history.completed_days(&self.name, &workout.name);
+                if completed.is_empty() {
+                    continue;
+                }
+                let next = (1..delta)
+                    .map(|d| date + Duration::days(d))
+                    .find(|d| {
+                        self.find_workouts(history, *d)
+                            .iter()
+                            .any(|w| w.name == workout.name)
+                    })
+                    .map(|d| d.date_naive())
+                    .unwrap_or(today + Duration::days(1));
+                if completed.range(day..next).next().is_none() {
+                    missed.push((workout, day));
+                }
+            }
+        }
+        missed
+    }
+
+    fn planned_workouts(&self, history: &History, date: DateTime<Local>) -> Vec<&Workout> {
         fn valid(workout: &Workout, block: Option<&Block>) -> bool {
             match block {
                 Some(b) => b.workouts.contains(&workout.name),
@@ -195,24 +311,98 @@ impl Program {
                         workouts.push(workout); // like any day
                     }
                     Schedule::Every(1) => {}
-                    Schedule::Every(n) => match workout.days_since_last_completed() {
-                        Some(days) if days.value % n == 0 => {
+                    Schedule::Every(n) => {
+                        let last = history
+                            .workout_completed(&self.name, &workout.name)
+                            .map(Days::new);
+                        if every_due(*n, last, today, then) {
                             workouts.insert(0, workout);
                         }
-                        Some(_) => {}
-                        None => workouts.insert(0, workout),
-                    },
+                    }
                     Schedule::Days(days) => {
                         if days.contains(&date.weekday()) {
                             workouts.insert(0, workout)
                         }
                     }
+                    Schedule::Rotation(days) => {
+                        if days.contains(&date.weekday())
+                            && self.rotation_turn(history, days, date) == Some(&workout.name)
+                        {
+                            workouts.insert(0, workout)
+                        }
+                    }
+                    Schedule::PerWeek(n) if then == today => {
+                        // Like any day but only until enough sessions were done this week.
+                        let done_today = history
+                            .workout_completed(&self.name, &workout.name)
+                            .is_some_and(|d| Days::new(d) == today);
+                        if !done_today
+                            && history.week_sessions(&self.name, &workout.name, date) < *n as usize
+                        {
+                            workouts.push(workout);
+                        }
+                    }
+                    Schedule::PerWeek(_) => {}
+                    Schedule::Dates(dates) => {
+                        if dates.contains(&date.date_naive()) {
+                            workouts.insert(0, workout)
+                        }
+                    }
                 }
             }
         }
         workouts
     }
 
+    /// Returns the name of the workout whose turn it is on date for the rotation using
+    /// days. The rotation continues after whichever of its workouts was done most
+    /// recently and advances once for each rotation day after today.
+    fn rotation_turn(
+        &self,
+        history: &History,
+        days: &[Weekday],
+        date: DateTime<Local>,
+    ) -> Option<&String> {
+        let rotation: Vec<&Workout> = self
+            .workouts
+            .iter()
+            .filter(|w| w.enabled && matches!(&w.schedule, Schedule::Rotation(d) if d == days))
+            .collect();
+        if rotation.is_empty() {
+            return None;
+        }
+        let last = rotation
+            .iter()
+            .enumerate()
+            .filter_map(|(i, w)| {
+                history
+                    .workout_completed(&self.name, &w.name)
+                    .map(|d| (d, i))
+            })
+            .max();
+
+        let now = Local::now();
+        let (mut turn, day) = match last {
+            Some((completed, i)) if Days::new(completed) == Days::new(now) => {
+                (i + 1, now + Duration::days(1)) // today's session is done
+            }
+            Some((_, i)) => (i + 1, now),
+            None => (0, now),
+        };
+        if Days::new(date) < Days::new(day) {
+            return None;
+        }
+
+        // Count the rotation days in [day, date) a week at a time because date can be far
+        // in the future (e.g. from the calendar).
+        let is_turn = |delta: i32| days.contains(&(day + Duration::days(delta as i64)).weekday());
+        let delta = Days::new(date) - Days::new(day);
+        let per_week = (0..7).filter(|d| is_turn(*d)).count();
+        turn += (delta / 7) as usize * per_week;
+        turn += (0..delta % 7).filter(|d| is_turn(*d)).count();
+        rotation.get(turn % rotation.len()).map(|w| &w.name)
+    }
+
     pub fn find(&self, workout: &str) -> Option<&Workout> {
         self.workouts.iter().find(|w| w.name == workout)
     }
@@ -221,6 +411,30 @@ impl Program {
         self.workouts.iter_mut().find(|w| w.name == workout)
     }
 
+    /// Names of the weight sets used by at least one exercise.
+    pub fn weight_sets_in_use(&self) -> HashSet<String> {
+        self.workouts
+            .iter()
+            .flat_map(|w| w.exercises())
+            .filter_map(|e| e.data().weightset.clone())
+            .collect()
+    }
+
+    /// Points exercises at their weight set's new name. Renames are (old, new) pairs and
+    /// are applied all at once so names can be swapped. Weights should already have
+    /// been updated.
+    pub fn rename_weight_sets(&mut self, renames: &[(String, String)]) {
+        let renames: HashMap<_, _> = renames.iter().cloned().collect();
+        for workout in self.workouts.iter_mut() {
+            for exercise in workout.exercises_mut() {
+                let d = exercise.data_mut();
+                if let Some(new_name) = d.weightset.as_ref().and_then(|n| renames.get(n)) {
+                    d.weightset = Some(new_name.clone());
+                }
+            }
+        }
+    }
+
     fn validate_add_workout(&self, name: &str) -> Result<(), Error> {
         if self.workouts.iter().find(|&w| w.name == name).is_some() {
             // Other checks would be done when creating workouts.
@@ -233,6 +447,96 @@ impl Program {
         self.workouts.push(workout);
     }
 
+    fn validate_skip_missed(
+        &self,
+        history: &History,
+        workout: &str,
+        date: NaiveDate,
+    ) -> Result<(), Error> {
+        if !self
+            .missed(history)
+            .iter()
+            .any(|(w, d)| w.name == workout && *d == date)
+        {
+            return validation_err!("'{workout}' wasn't missed on {date}.");
+        }
+        Ok(())
+    }
+
+    fn validate_slide_missed(
+        &self,
+        history: &History,
+        workout: &str,
+        from: NaiveDate,
+    ) -> Result<(), Error> {
+        self.validate_skip_missed(history, workout, from)?;
+        if self
+            .find_workouts(history, Local::now())
+            .iter()
+            .any(|w| w.name == workout)
+        {
+            return validation_err!("'{workout}' is already scheduled for today.");
+        }
+        Ok(())
+    }
+
+    fn do_slide_missed(&mut self, history: &History, workout: &str, from: NaiveDate) {
+        let now = Local::now();
+        let today = now.date_naive();
+
+        // Find the rest of the week's fixed sessions before anything moves.
+        let mut shifts = Vec::new();
+        if self.shift_missed {
+            let delta = today - from;
+            let sunday = 6 - now.weekday().num_days_from_monday() as i64;
+            let completed = |name: &str| history.completed_days(&self.name, name).contains(&today);
+            for d in 0..=sunday {
+                let date = now + Duration::days(d);
+                for w in self.find_workouts(history, date) {
+                    if matches!(w.schedule, Schedule::Days(_) | Schedule::Dates(_))
+                        && !(d == 0 && completed(&w.name))
+                    {
+                        let to = (date + delta)
+                            .date_naive()
+                            .min(today + Duration::days(sunday));
+                        shifts.push((w.name.clone(), date.date_naive(), to));
+                    }
+                }
+            }
+        }
+
+        // Latest first so that a session moving onto a later one doesn't get chained
+        // into that one's move.
+        for (name, from, to) in shifts.into_iter().rev() {
+            self.add_move(&name, from, to);
+        }
+        self.add_move(workout, from, today);
+
+        let oldest = today - Duration::days(8);
+        self.moved.retain(|m| m.from >= oldest || m.to >= oldest);
+    }
+
+    /// If the session was already moved onto from then that move is extended instead of
+    /// adding another.
+    fn add_move(&mut self, workout: &str, from: NaiveDate, to: NaiveDate) {
+        if let Some(i) = self
+            .moved
+            .iter()
+            .position(|m| m.workout == workout && m.to == from)
+        {
+            self.moved[i].to = to;
+            if self.moved[i].from == to {
+                self.moved.remove(i);
+            }
+        } else if from != to {
+            self.moved.push(Moved {
+                workout: workout.to_owned(),
+                from,
+                to,
+            });
+        }
+    }
+
     fn validate_change_workout_name(&self, old_name: &str, new_name: &str) -> Result<(), Error> {
         if self.find(old_name).is_none() {
             return validation_err!("Didn't find old workout.");
@@ -256,6 +560,9 @@ impl Program {
                 block.workouts[i] = new_name.to_string();
             }
         }
+        for m in self.moved.iter_mut().filter(|m| m.workout == old_name) {
+            m.workout = new_name.to_string();
+        }
     }
 
     fn validate_set_block(
@@ -378,12 +685,31 @@ impl Program {
     }
 }
 
+/// Every N days workouts are due N days after they were last done, or today if that has
+/// already passed (or they've never been done). After that they're due every N days so a
+/// late session pushes the later ones back.
+fn every_due(n: i32, last: Option<Days>, today: Days, date: Days) -> bool {
+    let due = match last {
+        Some(last) if last + n > today => last + n,
+        _ => today,
+    };
+    date >= due && (date - due) % n == 0
+}
+
 fn find_active(
     blocks_start: DateTime<Local>,
     blocks: &Vec<Block>,
     now: DateTime<Local>,
 ) -> (usize, DateTime<Local>) {
-    let mut block_start = blocks_start; // TODO should we bump this forward in somewhere?
+    // Skip whole cycles of the blocks since now can be far in the future (e.g. from the
+    // calendar).
+    let cycle: i64 = blocks.iter().map(|b| b.num_weeks as i64).sum();
+    let cycles = if now > blocks_start && cycle > 0 {
+        (now - blocks_start).num_weeks() / cycle
+    } else {
+        0
+    };
+    let mut block_start = blocks_start + Duration::weeks(cycles * cycle);
     loop {
         // loop because blocks_start might be way in the past
         assert!(blocks_start.weekday() == Weekday::Mon);
@@ -397,3 +723,173 @@ fn find_active(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every() {
+        let today = Days { value: 1000 };
+        let due = |last: Option<i32>, delta: i32| {
+            every_due(3, last.map(|v| Days { value: v }), today, today + delta)
+        };
+
+        // never done so due today and every 3 days after that
+        assert!(due(None, 0));
+        assert!(!due(None, 1));
+        assert!(due(None, 3));
+        assert!(!due(None, -3)); // not due in the past
+
+        // done yesterday so due in two days
+        assert!(!due(Some(999), 0));
+        assert!(due(Some(999), 2));
+        assert!(due(Some(999), 5));
+
+        // done today
+        assert!(!due(Some(1000), 0));
+        assert!(due(Some(1000), 3));
+
+        // late so due today and the later sessions move back
+        assert!(due(Some(990), 0));
+        assert!(!due(Some(990), 2));
+        assert!(due(Some(990), 3));
+    }
+
+    #[test]
+    fn rotation() {
+        let days = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+        let mut program = Program::new("My".to_owned());
+        for name in ["A", "B", "Other"] {
+            program.add_workout(Workout::new(
+                name.to_owned(),
+                Schedule::Rotation(days.clone()),
+            ));
+        }
+        assert!(program
+            .find_mut("Other")
+            .unwrap()
+            .try_set_schedule(Schedule::Rotation(vec![Weekday::Sat]))
+            .is_ok());
+
+        let mut history = History::new();
+        let names = |history: &History, delta: i64| {
+            let date = Local::now() + Duration::days(delta);
+            program
+                .find_workouts(history, date)
+                .iter()
+                .filter(|w| w.name != "Other")
+                .map(|w| w.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&history, 0), vec!["A"]);
+        assert_eq!(names(&history, 1), vec!["B"]);
+        assert_eq!(names(&history, 2), vec!["A"]);
+
+        // A was done today so B is next
+        let name = crate::exercise::ExerciseName("Squat".to_owned());
+        history.start("My", "A", &name, Local::now());
+        history.finish(&name, Local::now());
+        assert!(names(&history, 0).is_empty());
+        assert_eq!(names(&history, 1), vec!["B"]);
+        assert_eq!(names(&history, 2), vec!["A"]);
+
+        // With fewer days the rotation only advances on its days.
+        let days = vec![Weekday::Mon, Weekday::Thu];
+        let mut program = Program::new("My".to_owned());
+        for name in ["C", "D"] {
+            program.add_workout(Workout::new(
+                name.to_owned(),
+                Schedule::Rotation(days.clone()),
+            ));
+        }
+        let history = History::new();
+        let now = Local::now();
+        let names = |delta: i64| {
+            program
+                .find_workouts(&history, now + Duration::days(delta))
+                .iter()
+                .map(|w| w.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut turn = 0;
+        for delta in 0..30 {
+            if days.contains(&(now + Duration::days(delta)).weekday()) {
+                assert_eq!(names(delta), vec![["C", "D"][turn % 2]]);
+                turn += 1;
+            } else {
+                assert!(names(delta).is_empty());
+            }
+        }
+
+        // Four sessions every two weeks so it's the same far in the future.
+        let far = 14 * 26 * 1000; // about a thousand years
+        for delta in 0..7 {
+            assert_eq!(names(delta), names(delta + far));
+        }
+    }
+
+    #[test]
+    fn missed() {
+        let now = Local::now();
+        let yesterday = now - Duration::days(1);
+        let mut program = Program::new("My".to_owned());
+        program.add_workout(Workout::new(
+            "A".to_owned(),
+            Schedule::Days(vec![yesterday.weekday()]),
+        ));
+
+        // never done so nothing is missed
+        let mut history = History::new();
+        assert!(program.missed(&history).is_empty());
+
+        // done last week but not yesterday
+        let name = crate::exercise::ExerciseName("Squat".to_owned());
+        history.start("My", "A", &name, now - Duration::days(8));
+        history.finish(&name, now - Duration::days(8));
+        let day = yesterday.date_naive();
+        let missed: Vec<_> = program
+            .missed(&history)
+            .iter()
+            .map(|(w, d)| (w.name.clone(), *d))
+            .collect();
+        assert_eq!(missed, vec![("A".to_owned(), day)]);
+
+        // sessions from other programs (e.g. imported ones) with the same workout name
+        // don't count
+        let bench = crate::exercise::ExerciseName("Bench".to_owned());
+        history.start("Strong", "A", &bench, yesterday);
+        history.finish(&bench, yesterday);
+        assert_eq!(program.missed(&history).len(), 1);
+
+        // sliding it moves it to today
+        assert!(program.try_slide_missed(&history, "B", day).is_err());
+        assert!(program.try_slide_missed(&history, "A", day).is_ok());
+        assert!(program.missed(&history).is_empty());
+        assert_eq!(program.find_workouts(&history, now).len(), 1);
+        assert!(program.find_workouts(&history, yesterday).is_empty());
+        assert!(program.try_slide_missed(&history, "A", day).is_err());
+
+        // skipping it removes it from missed
+        let mut program = Program::new("My".to_owned());
+        program.add_workout(Workout::new(
+            "A".to_owned(),
+            Schedule::Days(vec![yesterday.weekday()]),
+        ));
+        assert!(program.try_skip_missed(&mut history, "B", day).is_err());
+        assert!(program
+            .try_skip_missed(&mut history, "A", day - Duration::days(7))
+            .is_err());
+        assert!(program.try_skip_missed(&mut history, "A", day).is_ok());
+        assert!(program.missed(&history).is_empty());
+        assert!(program.try_skip_missed(&mut history, "A", day).is_err());
+    }
+}
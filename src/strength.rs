@@ -0,0 +1,217 @@
+//! Strength metrics computed from [`History`] records: estimated one rep maxes and
+//! personal records.
+use crate::history::{CompletedSets, Record};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Formulas used to estimate the most weight that could be lifted for a single rep.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OneRepMax {
+    #[default]
+    Epley,
+    Brzycki,
+}
+
+impl OneRepMax {
+    /// Returns None if reps is out of range for the formula.
+    pub fn estimate(self, reps: i32, weight: f32) -> Option<f32> {
+        if reps <= 0 {
+            None
+        } else if reps == 1 {
+            Some(weight)
+        } else {
+            match self {
+                OneRepMax::Epley => Some(weight * (1.0 + reps as f32 / 30.0)),
+                OneRepMax::Brzycki if reps < 37 => Some(weight * 36.0 / (37.0 - reps as f32)),
+                OneRepMax::Brzycki => None,
+            }
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OneRepMax::Epley => "Epley",
+            OneRepMax::Brzycki => "Brzycki",
+        }
+    }
+}
+
+/// Best estimated one rep max across the record's sets.
+pub fn record_e1rm(record: &Record, formula: OneRepMax) -> Option<f32> {
+    weighted_sets(record)
+        .filter_map(|(reps, weight)| formula.estimate(reps, weight))
+        .reduce(f32::max)
+}
+
+/// Sum of reps x weight across the record's sets. Sets without a weight don't count.
+pub fn record_volume(record: &Record) -> Option<f32> {
+    weighted_sets(record)
+        .map(|(reps, weight)| reps as f32 * weight)
+        .reduce(|sum, v| sum + v)
+}
+
+/// Heaviest weight used across the record's sets.
+pub fn record_top_weight(record: &Record) -> Option<f32> {
+    weighted_sets(record).map(|(_, w)| w).reduce(f32::max)
+}
+
+fn weighted_sets(record: &Record) -> impl Iterator<Item = (i32, f32)> + '_ {
+    let sets = match &record.sets {
+        Some(CompletedSets::Reps(sets)) => sets.as_slice(),
+        _ => &[],
+    };
+    sets.iter()
+        .filter_map(|(reps, weight)| weight.map(|w| (*reps, w)))
+        .filter(|(reps, _)| *reps > 0)
+}
+
+/// A record that beat everything before it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PersonalRecord {
+    Weight(i32), // heaviest weight for that many reps
+    E1rm,
+    Volume,
+}
+
+impl PersonalRecord {
+    pub fn label(&self) -> String {
+        match self {
+            PersonalRecord::Weight(reps) => format!("{reps}RM"),
+            PersonalRecord::E1rm => "e1RM".to_owned(),
+            PersonalRecord::Volume => "volume".to_owned(),
+        }
+    }
+}
+
+/// Best results for an exercise.
+#[derive(Debug, Default)]
+pub struct PersonalBests {
+    pub weights: BTreeMap<i32, f32>, // reps => heaviest weight
+    pub e1rm: Option<f32>,
+    pub volume: Option<f32>,
+}
+
+impl PersonalBests {
+    /// Folds record into the bests and returns the PRs it set. Note that a weight PR
+    /// needs to beat what was done for the same or more reps, i.e. the first set of
+    /// triples isn't a PR if it used less weight than an earlier set of fives.
+    pub fn update(&mut self, record: &Record, formula: OneRepMax) -> Vec<PersonalRecord> {
+        let mut prs = Vec::new();
+
+        let mut weights: BTreeMap<i32, f32> = BTreeMap::new();
+        for (reps, weight) in weighted_sets(record) {
+            let best = weights.entry(reps).or_insert(weight);
+            *best = best.max(weight);
+        }
+        for (&reps, &weight) in weights.iter() {
+            let previous = self.weights.range(reps..).map(|(_, w)| *w).reduce(f32::max);
+            if previous.is_some_and(|p| weight > p) {
+                prs.push(PersonalRecord::Weight(reps));
+            }
+        }
+        for (reps, weight) in weights {
+            let best = self.weights.entry(reps).or_insert(weight);
+            *best = best.max(weight);
+        }
+
+        if let Some(e1rm) = record_e1rm(record, formula) {
+            if self.e1rm.is_some_and(|p| e1rm > p) {
+                prs.push(PersonalRecord::E1rm);
+            }
+            self.e1rm = Some(self.e1rm.map_or(e1rm, |p| p.max(e1rm)));
+        }
+
+        if let Some(volume) = record_volume(record) {
+            if self.volume.is_some_and(|p| volume > p) {
+                prs.push(PersonalRecord::Volume);
+            }
+            self.volume = Some(self.volume.map_or(volume, |p| p.max(volume)));
+        }
+
+        prs
+    }
+}
+
+/// Returns the bests across records along with the PRs set by each record (keyed by
+/// record id). Records should be ordered from oldest to newest.
+pub fn find_prs<'a>(
+    records: impl Iterator<Item = &'a Record>,
+    formula: OneRepMax,
+) -> (PersonalBests, HashMap<u64, Vec<PersonalRecord>>) {
+    let mut bests = PersonalBests::default();
+    let mut prs = HashMap::new();
+    for record in records {
+        let found = bests.update(record, formula);
+        if !found.is_empty() {
+            prs.insert(record.id, found);
+        }
+    }
+    (bests, prs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise::ExerciseName;
+    use crate::history::History;
+    use chrono::{Duration, Local, TimeZone};
+
+    fn add(history: &mut History, day: i64, sets: &[(i32, f32)]) {
+        let name = ExerciseName("Squat".to_owned());
+        let started = Local.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap() + Duration::days(day);
+        history.start("My", "Heavy", &name, started);
+        for (reps, weight) in sets {
+            history.append_reps(&name, *reps, Some(*weight));
+        }
+        history.finish(&name, started + Duration::minutes(10));
+    }
+
+    #[test]
+    fn estimates() {
+        assert_eq!(OneRepMax::Epley.estimate(1, 200.0), Some(200.0));
+        assert_eq!(OneRepMax::Brzycki.estimate(1, 200.0), Some(200.0));
+        assert_eq!(OneRepMax::Epley.estimate(0, 200.0), None);
+
+        let epley = OneRepMax::Epley.estimate(5, 225.0).unwrap();
+        assert!((epley - 262.5).abs() < 0.01);
+
+        let brzycki = OneRepMax::Brzycki.estimate(5, 225.0).unwrap();
+        assert!((brzycki - 253.125).abs() < 0.01);
+        assert_eq!(OneRepMax::Brzycki.estimate(37, 100.0), None);
+    }
+
+    #[test]
+    fn prs() {
+        let mut history = History::new();
+        add(&mut history, 0, &[(5, 200.0), (5, 200.0), (5, 200.0)]);
+        add(&mut history, 2, &[(5, 205.0), (5, 205.0), (5, 205.0)]);
+        add(&mut history, 4, &[(3, 205.0), (3, 205.0)]); // nothing new
+        add(&mut history, 6, &[(3, 215.0), (3, 215.0)]); // beats the 5s
+        add(
+            &mut history,
+            8,
+            &[(5, 200.0), (5, 200.0), (5, 200.0), (5, 200.0)],
+        );
+
+        let name = ExerciseName("Squat".to_owned());
+        let ids: Vec<_> = history.records(&name).map(|r| r.id).collect();
+        let (bests, prs) = find_prs(history.records(&name), OneRepMax::Epley);
+
+        assert!(!prs.contains_key(&ids[0]));
+        assert_eq!(
+            prs[&ids[1]],
+            vec![
+                PersonalRecord::Weight(5),
+                PersonalRecord::E1rm,
+                PersonalRecord::Volume
+            ]
+        );
+        assert!(!prs.contains_key(&ids[2]));
+        assert_eq!(prs[&ids[3]], vec![PersonalRecord::Weight(3)]);
+        assert_eq!(prs[&ids[4]], vec![PersonalRecord::Volume]);
+
+        assert_eq!(bests.weights[&3], 215.0);
+        assert_eq!(bests.weights[&5], 205.0);
+        assert_eq!(bests.volume, Some(4.0 * 5.0 * 200.0));
+    }
+}
@@ -9,11 +9,68 @@ use std::{
     fmt::Formatter,
 };
 
-// TODO Might want to support bumper plates though that would get quite annoying because
-// we'd want to use them whenever possible. For example, if we had [15 bumper x8, 20 x6]
-// and want 60 lbs we'd normally select 20 x3 (for single plates) but with bumpers we'd
-// want 15 bumper x4. Though this is probably more doable now that we're enumerating
-// plates.
+pub const LBS_PER_KG: f32 = 2.204_622_6;
+
+// Upper bound on the number of ways to load a weight that Weights::loadings will return.
+const MAX_LOADINGS: usize = 64;
+
+// Add-on combos are expanded up to each count and multiplied together so this is kept
+// small (counts are also encoded as single digits).
+const MAX_ADD_ON_COUNT: i32 = 9;
+
+/// Weights are always stored in pounds but can be displayed using kilograms. Weight sets
+/// also have units: those are the units the weights and plates are entered in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    #[default]
+    Lbs,
+    Kgs,
+}
+
+impl Units {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Units::Lbs => " lbs",
+            Units::Kgs => " kg",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Units::Lbs => "lbs",
+            Units::Kgs => "kg",
+        }
+    }
+
+    /// Converts a weight in pounds into these units.
+    pub fn in_units(self, lbs: f32) -> f32 {
+        match self {
+            Units::Lbs => lbs,
+            Units::Kgs => lbs / LBS_PER_KG,
+        }
+    }
+
+    /// Converts a weight in these units into pounds.
+    pub fn to_lbs(self, value: f32) -> f32 {
+        match self {
+            Units::Lbs => value,
+            Units::Kgs => value * LBS_PER_KG,
+        }
+    }
+
+    /// Converts a weight in pounds into these units, rounded for display.
+    pub fn round_lbs(self, lbs: f32) -> f32 {
+        match self {
+            Units::Lbs => lbs,
+            Units::Kgs => (20.0 * self.in_units(lbs)).round() / 20.0,
+        }
+    }
+
+    /// Formats a weight in pounds using these units, e.g. "100 kg".
+    pub fn format(self, lbs: f32) -> String {
+        format_weight(self.round_lbs(lbs), self.suffix())
+    }
+}
 
 pub fn format_weight(weight: f32, suffix: &str) -> String {
     let mut s = format!("{weight:.3}");
@@ -29,54 +86,125 @@ pub fn format_weight(weight: f32, suffix: &str) -> String {
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Plate {
     pub weight: f32,
-    pub count: i32, // how many of this plate the user has
+    pub count: i32,   // how many of this plate the user has
+    pub bumper: bool, // bumper plates are used whenever possible
 }
 
 impl Plate {
     pub fn new(weight: f32, count: i32) -> Plate {
-        Plate { weight, count }
+        Plate {
+            weight,
+            count,
+            bumper: false,
+        }
+    }
+
+    pub fn with_bumper(self) -> Plate {
+        Plate {
+            bumper: true,
+            ..self
+        }
+    }
+}
+
+/// Optional extra weight that can be added onto a discrete weight, e.g. a magnet that
+/// sticks onto a dumbbell.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddOn {
+    pub name: String,
+    pub weight: f32,
+    pub count: i32, // max number that can be added at once
+}
+
+impl AddOn {
+    pub fn new(name: &str, weight: f32, count: i32) -> AddOn {
+        AddOn {
+            name: name.to_owned(),
+            weight,
+            count,
+        }
+    }
+}
+
+impl fmt::Display for AddOn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.count == 1 {
+            write!(f, "{} {}", format_weight(self.weight, ""), self.name)
+        } else {
+            write!(
+                f,
+                "{} {} x{}",
+                format_weight(self.weight, ""),
+                self.name,
+                self.count
+            )
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Weight {
     weight: InternalWeight,
+    units: Option<Units>, // units of the weight set, if None weight is in lbs
 }
 
 impl Weight {
     fn discrete(value: f32) -> Weight {
         Weight {
             weight: InternalWeight::Discrete(value),
+            units: None,
         }
     }
 
     fn error(mesg: String, target: f32) -> Weight {
         Weight {
             weight: InternalWeight::Error(mesg, target),
+            units: None,
         }
     }
 
     fn plates(plates: Plates) -> Weight {
         Weight {
             weight: InternalWeight::Plates(plates),
+            units: None,
         }
     }
 
-    /// The actual weight, may include stuff like a bar weight.
+    fn extras(extras: Extras) -> Weight {
+        if extras.add_ons.is_empty() {
+            Weight::discrete(extras.base)
+        } else {
+            Weight {
+                weight: InternalWeight::Extras(extras),
+                units: None,
+            }
+        }
+    }
+
+    fn with_units(self, units: Units) -> Weight {
+        Weight {
+            units: Some(units),
+            ..self
+        }
+    }
+
+    /// The actual weight in lbs, may include stuff like a bar weight.
     pub fn value(&self) -> f32 {
-        match &self.weight {
+        let value = match &self.weight {
             InternalWeight::Discrete(v) => *v,
-            InternalWeight::Error(_, v) => *v,
+            InternalWeight::Error(_, v) => return *v,
             InternalWeight::Plates(p) => p.weight(),
-        }
+            InternalWeight::Extras(e) => e.weight(),
+        };
+        self.units.unwrap_or_default().to_lbs(value)
     }
 
-    /// The weight as a string, e.g. "165 lbs".
-    pub fn text(&self) -> String {
+    /// The weight as a string, e.g. "165 lbs". Uses the units of the weight set or, if
+    /// the weight didn't come from a weight set, preferred.
+    pub fn text(&self, preferred: Units) -> String {
         match &self.weight {
-            InternalWeight::Discrete(v) => format_weight(*v, " lbs"),
             InternalWeight::Error(_, _) => String::new(),
-            InternalWeight::Plates(p) => format_weight(p.weight(), " lbs"),
+            _ => self.units.unwrap_or(preferred).format(self.value()),
         }
     }
 
@@ -88,6 +216,7 @@ impl Weight {
             InternalWeight::Discrete(_) => None,
             InternalWeight::Error(m, _) => Some(m.clone()),
             InternalWeight::Plates(p) => Some(format!("{}", p)),
+            InternalWeight::Extras(e) => Some(format!("{}", e)),
         }
     }
 }
@@ -95,12 +224,40 @@ impl Weight {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum WeightSet {
     /// Used for stuff like dumbbells and cable machines. Weights should be sorted from
-    /// smallest to largest.
-    Discrete(Vec<f32>), // TODO: support extra weights, eg magnets for dumbbells, maybe allow user to name them "magnet" or whatever
+    /// smallest to largest. Also includes optional add-ons, e.g. magnets for dumbbells.
+    Discrete(Vec<f32>, Vec<AddOn>),
 
     /// Used for stuff like barbell exercises and leg presses. Plates are added in pairs.
-    /// Includes an optional bar weight. Plates should be sorted from smallest to largest.
-    DualPlates(Vec<Plate>, Option<f32>),
+    /// Includes an optional bar weight and an optional limit on the number of plates
+    /// that can go on each side (e.g. because of sleeve length). Plates should be sorted
+    /// from smallest to largest.
+    DualPlates(Vec<Plate>, Option<f32>, Option<i32>),
+
+    /// Used for stuff like landmines and single horn plate loaded machines. Plates are
+    /// added one at a time. Includes an optional base weight, e.g. for a machine's sled.
+    /// Plates should be sorted from smallest to largest.
+    SinglePlates(Vec<Plate>, Option<f32>),
+}
+
+impl WeightSet {
+    /// Returns every plate combination for plate sets or None for other sets.
+    fn combos(&self) -> Option<Vec<Vec<Plate>>> {
+        match self {
+            WeightSet::Discrete(_, _) => None,
+            WeightSet::DualPlates(plates, _, max_plates) => {
+                Some(enumerate_weights(plates, *max_plates))
+            }
+            WeightSet::SinglePlates(plates, _) => Some(enumerate_single_weights(plates)),
+        }
+    }
+}
+
+/// Where a weight set in the weight set list pages came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SetSource {
+    New,              // uses a default set of weights
+    Existing(String), // the set's old name, the set is renamed if the name changed
+    Copy(String),     // the name of the set that was duplicated
 }
 
 /// Collections of weight sets that are shared across programs, e.g. there could be sets
@@ -109,10 +266,14 @@ pub enum WeightSet {
 pub struct Weights {
     sets: HashMap<String, WeightSet>,
 
-    // All non-duplicate combinations of DualPlates for every weight sorted by smallest
-    // weight to largest. Note that these are the plates added to one side of the bar.
+    // All non-duplicate combinations of plates for every weight sorted by smallest weight
+    // to largest. Note that for DualPlates these are the plates added to one side of the
+    // bar.
     #[serde(default)]
     combos: HashMap<String, Vec<Vec<Plate>>>,
+
+    // Sets that are not listed here use lbs.
+    units: HashMap<String, Units>,
 }
 
 impl Weights {
@@ -120,6 +281,7 @@ impl Weights {
         Weights {
             sets: HashMap::new(),
             combos: HashMap::new(),
+            units: HashMap::new(),
         }
     }
 
@@ -139,9 +301,27 @@ impl Weights {
         self.sets.get(name)
     }
 
+    /// Returns the units the weight set uses or None if there is no such set.
+    pub fn units(&self, name: &str) -> Option<Units> {
+        if self.sets.contains_key(name) {
+            Some(self.units.get(name).copied().unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
+    /// Units to show an exercise's weights with: exercises that use a weight set use the
+    /// units of the set, otherwise preferred is used.
+    pub fn display_units(&self, weightset: &Option<String>, preferred: Units) -> Units {
+        weightset
+            .as_ref()
+            .and_then(|name| self.units(name))
+            .unwrap_or(preferred)
+    }
+
     pub fn add(&mut self, name: String, set: WeightSet) {
-        if let WeightSet::DualPlates(plates, _) = &set {
-            let old = self.combos.insert(name.clone(), enumerate_weights(plates));
+        if let Some(combos) = set.combos() {
+            let old = self.combos.insert(name.clone(), combos);
             assert!(old.is_none());
         }
 
@@ -149,16 +329,32 @@ impl Weights {
         assert!(old.is_none());
     }
 
-    /// Used for warmups and backoff sets. May return a weight larger than target.
+    /// Used for warmups and backoff sets. May return a weight larger than target. Note
+    /// that target is in lbs.
     pub fn closest(&self, name: &str, target: f32) -> Weight {
         if let Some(set) = self.sets.get(name) {
-            match set {
-                WeightSet::Discrete(weights) => Weight::discrete(closest_discrete(target, weights)),
-                WeightSet::DualPlates(_, bar) => match self.combos.get(name) {
-                    Some(enums) => Weight::plates(closest_dual(target, enums, bar)),
-                    None => Weight::error(format!("There is no combos named '{name}'"), target),
+            let units = self.units(name).unwrap_or_default();
+            let t = units.in_units(target);
+            let weight = match set {
+                WeightSet::Discrete(weights, add_ons) => {
+                    let combos = discrete_combos(weights, add_ons);
+                    let totals: Vec<_> = combos.iter().map(|c| c.weight()).collect();
+                    find_extras(closest_discrete(t, &totals), combos)
+                }
+                WeightSet::DualPlates(_, bar, _) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(closest_plates(t, enums, bar, true)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
                 },
-            }
+                WeightSet::SinglePlates(_, base) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(closest_plates(t, enums, base, false)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
+                },
+            };
+            weight.with_units(units)
         } else if name.is_empty() {
             Weight::discrete(target)
         } else {
@@ -169,13 +365,28 @@ impl Weights {
     /// Used for worksets. Will not return a weight larger than target.
     pub fn lower(&self, name: &str, target: f32) -> Weight {
         if let Some(set) = self.sets.get(name) {
-            match set {
-                WeightSet::Discrete(weights) => Weight::discrete(find_discrete(target, weights).0),
-                WeightSet::DualPlates(_, bar) => match self.combos.get(name) {
-                    Some(enums) => Weight::plates(lower_dual(target, enums, bar)),
-                    None => Weight::error(format!("There is no combos named '{name}'"), target),
+            let units = self.units(name).unwrap_or_default();
+            let t = units.in_units(target);
+            let weight = match set {
+                WeightSet::Discrete(weights, add_ons) => {
+                    let combos = discrete_combos(weights, add_ons);
+                    let totals: Vec<_> = combos.iter().map(|c| c.weight()).collect();
+                    find_extras(find_discrete(t, &totals).0, combos)
+                }
+                WeightSet::DualPlates(_, bar, _) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(lower_plates(t, enums, bar, true)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
                 },
-            }
+                WeightSet::SinglePlates(_, base) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(lower_plates(t, enums, base, false)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
+                },
+            };
+            weight.with_units(units)
         } else if name.is_empty() {
             Weight::discrete(target)
         } else {
@@ -186,53 +397,139 @@ impl Weights {
     /// Return the next weight larger than target.
     pub fn advance(&self, name: &str, target: f32) -> Weight {
         if let Some(set) = self.sets.get(name) {
-            match set {
-                WeightSet::Discrete(weights) => {
-                    Weight::discrete(find_discrete(target + 0.001, weights).1)
+            let units = self.units(name).unwrap_or_default();
+            let t = units.in_units(target);
+            let weight = match set {
+                WeightSet::Discrete(weights, add_ons) => {
+                    let combos = discrete_combos(weights, add_ons);
+                    let totals: Vec<_> = combos.iter().map(|c| c.weight()).collect();
+                    find_extras(find_discrete(t + 0.01, &totals).1, combos)
                 }
-                WeightSet::DualPlates(_, bar) => match self.combos.get(name) {
-                    Some(enums) => Weight::plates(upper_dual(target, enums, bar)),
-                    None => Weight::error(format!("There is no combos named '{name}'"), target),
+                WeightSet::DualPlates(_, bar, _) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(upper_plates(t, enums, bar, true)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
                 },
-            }
+                WeightSet::SinglePlates(_, base) => match self.combos.get(name) {
+                    Some(enums) => Weight::plates(upper_plates(t, enums, base, false)),
+                    None => {
+                        return Weight::error(format!("There is no combos named '{name}'"), target)
+                    }
+                },
+            };
+            weight.with_units(units)
         } else {
             Weight::error(format!("There is no weight set named '{name}'"), target)
         }
     }
 
-    pub fn try_set_discrete_weights(&mut self, sets: Vec<String>) -> Result<(), Error> {
-        let valid = |w: &WeightSet| matches!(w, WeightSet::Discrete(_));
-        self.validate_set_weight_sets(&sets, valid)?;
-        self.do_set_weight_sets(sets, valid, default::default_discrete());
-        Ok(())
+    /// Returns every way of loading the plates for weight, e.g. "45 + 10" and "25 x2 + 5".
+    /// For DualPlates these are the plates for one side. The first entry is the one used
+    /// by closest, lower, and advance and the others use the same amount of bumper weight.
+    /// Empty if weight doesn't use plates.
+    pub fn loadings(&self, name: &str, weight: &Weight) -> Vec<Vec<Plate>> {
+        let InternalWeight::Plates(loaded) = &weight.weight else {
+            return Vec::new();
+        };
+        let (plates, max_plates) = match self.sets.get(name) {
+            Some(WeightSet::DualPlates(plates, _, max_plates)) => (plates, *max_plates),
+            Some(WeightSet::SinglePlates(plates, _)) => (plates, None),
+            _ => return Vec::new(),
+        };
+
+        let multiplier = if loaded.dual { 2 } else { 1 };
+        let current: Vec<_> = loaded
+            .plates
+            .iter()
+            .map(|p| Plate {
+                count: p.count / multiplier,
+                ..*p
+            })
+            .collect();
+        let target = scaled(summed_weight(&current));
+        let mut found = Vec::new();
+        find_loadings(
+            plates,
+            multiplier,
+            max_plates,
+            target,
+            &mut Vec::new(),
+            &mut found,
+        );
+
+        let bumpers = bumper_weight(&current);
+        found.retain(|l| bumper_weight(l) == bumpers && *l != current);
+        found.insert(0, current);
+        found
     }
 
-    pub fn try_set_plate_weights(&mut self, sets: Vec<String>) -> Result<(), Error> {
-        let valid = |w: &WeightSet| matches!(w, WeightSet::Discrete(_));
-        self.validate_set_weight_sets(&sets, valid)?;
-        self.do_set_weight_sets(sets, valid, default::default_plates());
-        Ok(())
+    /// Replaces the Discrete sets with sets, leaving other types of sets alone. Sets that
+    /// aren't listed are deleted which fails if they're in_use. Returns (old, new) names
+    /// for the sets that were renamed so that exercises can be updated.
+    pub fn try_set_discrete_weights(
+        &mut self,
+        sets: Vec<(String, SetSource)>,
+        in_use: &HashSet<String>,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let valid = |w: &WeightSet| matches!(w, WeightSet::Discrete(_, _));
+        self.validate_set_weight_sets(&sets, in_use, valid)?;
+        Ok(self.do_set_weight_sets(sets, valid, default::default_discrete()))
     }
 
+    pub fn try_set_plate_weights(
+        &mut self,
+        sets: Vec<(String, SetSource)>,
+        in_use: &HashSet<String>,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let valid = |w: &WeightSet| matches!(w, WeightSet::DualPlates(_, _, _));
+        self.validate_set_weight_sets(&sets, in_use, valid)?;
+        Ok(self.do_set_weight_sets(sets, valid, default::default_plates()))
+    }
+
+    pub fn try_set_single_plate_weights(
+        &mut self,
+        sets: Vec<(String, SetSource)>,
+        in_use: &HashSet<String>,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let valid = |w: &WeightSet| matches!(w, WeightSet::SinglePlates(_, _));
+        self.validate_set_weight_sets(&sets, in_use, valid)?;
+        Ok(self.do_set_weight_sets(sets, valid, default::default_single_plates()))
+    }
+
+    /// Replaces the old_name set with weights, renaming it if new_name is different. Note
+    /// that callers will normally want [`UserState::try_change_set`] so that exercises
+    /// are also updated.
+    ///
+    /// [`UserState::try_change_set`]: crate::app_state::UserState::try_change_set
     pub fn try_change_set(
         &mut self,
         old_name: &str,
         new_name: &str,
         weights: WeightSet,
+        units: Units,
     ) -> Result<(), Error> {
         self.validate_change_set(old_name, new_name, &weights)?;
-        self.do_change_set(old_name, new_name, weights);
+        self.do_change_set(old_name, new_name, weights, units);
         Ok(())
     }
 
-    fn validate_set_weight_sets<F>(&self, sets: &[String], valid: F) -> Result<(), Error>
+    fn validate_set_weight_sets<F>(
+        &self,
+        sets: &[(String, SetSource)],
+        in_use: &HashSet<String>,
+        valid: F,
+    ) -> Result<(), Error>
     where
         F: Fn(&WeightSet) -> bool,
     {
         let mut names = HashSet::new();
-        for name in sets.iter() {
+        let mut kept = HashSet::new();
+        for (name, source) in sets.iter() {
             if name.trim().is_empty() {
                 return validation_err!("Weight set names cannot be empty.");
+            } else if name == "None" {
+                return validation_err!("Weight set names cannot be 'None'.");
             }
 
             let added = names.insert(name.clone());
@@ -240,6 +537,22 @@ impl Weights {
                 return validation_err!("'{name}' appears more than once.");
             }
 
+            match source {
+                SetSource::New => (),
+                SetSource::Existing(old) | SetSource::Copy(old) => match self.sets.get(old) {
+                    Some(set) if valid(set) => (),
+                    Some(_) => {
+                        return validation_err!("'{old}' is a weight set with a different type.")
+                    }
+                    None => return validation_err!("'{old}' is no longer a weight set."),
+                },
+            }
+            if let SetSource::Existing(old) = source {
+                if !kept.insert(old.clone()) {
+                    return validation_err!("'{old}' appears more than once.");
+                }
+            }
+
             if let Some(old) = self.sets.get(name) {
                 if !valid(old) {
                     return validation_err!(
@@ -248,10 +561,27 @@ impl Weights {
                 }
             }
         }
+
+        let mut deleted: Vec<_> = self
+            .sets
+            .iter()
+            .filter(|(name, set)| valid(set) && !kept.contains(*name))
+            .map(|(name, _)| name)
+            .filter(|name| in_use.contains(*name))
+            .collect();
+        deleted.sort();
+        if let Some(name) = deleted.first() {
+            return validation_err!("'{name}' can't be deleted because exercises are using it.");
+        }
         Ok(())
     }
 
-    fn do_set_weight_sets<F>(&mut self, sets: Vec<String>, valid: F, exemplar: WeightSet)
+    fn do_set_weight_sets<F>(
+        &mut self,
+        sets: Vec<(String, SetSource)>,
+        valid: F,
+        exemplar: WeightSet,
+    ) -> Vec<(String, String)>
     where
         F: Fn(&WeightSet) -> bool,
     {
@@ -264,25 +594,44 @@ impl Weights {
                 old_invalid.insert(name, set);
             }
         }
+        let old_units = std::mem::take(&mut self.units);
 
         // Note that this will implicitly delete sets that are no longer named.
+        let mut renames = Vec::new();
         let mut new_sets = HashMap::new();
-        for name in sets.into_iter() {
-            if let Some(set) = old_valid.remove(&name) {
-                new_sets.insert(name, set);
-            } else {
-                new_sets.insert(name, exemplar.clone());
+        for (name, source) in sets.into_iter() {
+            let (old_name, renamed) = match source {
+                SetSource::New => {
+                    new_sets.insert(name, exemplar.clone());
+                    continue;
+                }
+                SetSource::Existing(old) => (old, true),
+                SetSource::Copy(old) => (old, false),
+            };
+            let set = old_valid
+                .get(&old_name)
+                .expect("validation should have checked this");
+            new_sets.insert(name.clone(), set.clone());
+            if let Some(units) = old_units.get(&old_name) {
+                self.units.insert(name.clone(), *units);
+            }
+            if renamed && old_name != name {
+                renames.push((old_name, name));
             }
         }
 
         self.sets = new_sets;
         for (name, set) in old_invalid.drain() {
+            if let Some(units) = old_units.get(&name) {
+                self.units.insert(name.clone(), *units);
+            }
             let old = self.sets.insert(name, set);
             assert!(old.is_none(), "validation should have prevented this");
         }
 
         self.combos.clear();
         self.rebuild_combos();
+        renames
     }
 
     fn validate_change_set(
@@ -291,7 +640,7 @@ impl Weights {
         new_name: &str,
         weights: &WeightSet,
     ) -> Result<(), Error> {
-        fn validate_discrete(weights: &[f32]) -> Result<(), Error> {
+        fn validate_discrete(weights: &[f32], add_ons: &[AddOn]) -> Result<(), Error> {
             if weights.is_empty() {
                 return validation_err!("There should be at least one weight.");
             }
@@ -306,10 +655,31 @@ impl Weights {
                     return validation_err!("Weights should be from smaller to larger.",);
                 }
             }
+            let mut names = HashSet::new();
+            for add_on in add_ons.iter() {
+                if add_on.name.trim().is_empty() {
+                    return validation_err!("Add-on names cannot be empty.");
+                } else if !names.insert(add_on.name.clone()) {
+                    return validation_err!("Add-on '{}' appears more than once.", add_on.name);
+                } else if add_on.weight <= 0.0 {
+                    return validation_err!("Add-on weights should be larger than zero.");
+                } else if add_on.count <= 0 {
+                    return validation_err!("Add-on counts should be at least one.");
+                } else if add_on.count > MAX_ADD_ON_COUNT {
+                    return validation_err!(
+                        "Add-on counts should be at most {MAX_ADD_ON_COUNT} not {}.",
+                        add_on.count
+                    );
+                }
+            }
             Ok(())
         }
 
-        fn validate_dual(plates: &[Plate], bar: &Option<f32>) -> Result<(), Error> {
+        fn validate_plates(
+            plates: &[Plate],
+            bar: &Option<f32>,
+            max_plates: &Option<i32>,
+        ) -> Result<(), Error> {
             if plates.is_empty() {
                 return validation_err!("There should be at least one plate.");
             }
@@ -328,9 +698,14 @@ impl Weights {
             }
             if let Some(weight) = bar {
                 if *weight < 0.0 {
-                    return validation_err!("Bar weight cannot be negative.");
+                    return validation_err!("Bar or base weight cannot be negative.");
                 } else if *weight == 0.0 {
-                    return validation_err!("Bar weight cannot be zero.");
+                    return validation_err!("Bar or base weight cannot be zero.");
+                }
+            }
+            if let Some(max) = max_plates {
+                if *max <= 0 {
+                    return validation_err!("Max plates should be at least one.");
                 }
             }
             Ok(())
@@ -345,28 +720,36 @@ impl Weights {
         }
 
         match weights {
-            WeightSet::Discrete(weights) => validate_discrete(weights)?,
-            WeightSet::DualPlates(plates, bar) => validate_dual(plates, bar)?,
+            WeightSet::Discrete(weights, add_ons) => validate_discrete(weights, add_ons)?,
+            WeightSet::DualPlates(plates, bar, max_plates) => {
+                validate_plates(plates, bar, max_plates)?
+            }
+            WeightSet::SinglePlates(plates, base) => validate_plates(plates, base, &None)?,
         }
 
         Ok(())
     }
 
-    fn do_change_set(&mut self, _old_name: &str, new_name: &str, weights: WeightSet) {
-        // Might make more sense to remove the old weightset but if we do that we'll
-        // also need to change each exercise to use the new name.
-        if let WeightSet::DualPlates(plates, _) = &weights {
-            self.combos
-                .insert(new_name.to_string(), enumerate_weights(plates));
+    fn do_change_set(&mut self, old_name: &str, new_name: &str, weights: WeightSet, units: Units) {
+        // Exercises using old_name need to be renamed too, see UserState::try_change_set.
+        if old_name != new_name {
+            self.sets.remove(old_name);
+            self.units.remove(old_name);
+        }
+        self.combos.remove(old_name);
+        self.combos.remove(new_name);
+        if let Some(combos) = weights.combos() {
+            self.combos.insert(new_name.to_string(), combos);
         }
         self.sets.insert(new_name.to_string(), weights);
+        self.units.insert(new_name.to_string(), units);
     }
 
     fn rebuild_combos(&mut self) {
         for (name, set) in self.sets.iter() {
-            if let WeightSet::DualPlates(plates, _) = set {
-                if !self.combos.contains_key(name) {
-                    self.combos.insert(name.clone(), enumerate_weights(plates));
+            if !self.combos.contains_key(name) {
+                if let Some(combos) = set.combos() {
+                    self.combos.insert(name.clone(), combos);
                 }
             }
         }
@@ -378,6 +761,37 @@ enum InternalWeight {
     Discrete(f32),
     Error(String, f32),
     Plates(Plates),
+    Extras(Extras),
+}
+
+/// A discrete weight along with the add-ons used with it. Here AddOn::count is the number
+/// of that add-on being used.
+#[derive(Clone, Debug)]
+struct Extras {
+    base: f32,
+    add_ons: Vec<AddOn>,
+}
+
+impl Extras {
+    fn weight(&self) -> f32 {
+        self.add_ons
+            .iter()
+            .fold(self.base, |sum, a| sum + (a.weight * (a.count as f32)))
+    }
+
+    fn num_add_ons(&self) -> i32 {
+        self.add_ons.iter().fold(0, |sum, a| sum + a.count)
+    }
+}
+
+impl fmt::Display for Extras {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut v = vec![format_weight(self.base, "")];
+        for add_on in self.add_ons.iter() {
+            v.push(format!("{add_on}"));
+        }
+        write!(f, "{}", v.join(" + "))
+    }
 }
 
 #[derive(Clone)]
@@ -389,9 +803,9 @@ struct Plates {
 
 impl PartialEq for Plate {
     fn eq(&self, other: &Self) -> bool {
-        let a = (1000.0 * self.weight) as i32;
-        let b = (1000.0 * other.weight) as i32;
-        self.count == other.count && a == b
+        let a = scaled(self.weight);
+        let b = scaled(other.weight);
+        self.count == other.count && a == b && self.bumper == other.bumper
     }
 }
 
@@ -449,12 +863,54 @@ fn closest_discrete(target: f32, weights: &[f32]) -> f32 {
     }
 }
 
+/// Returns every way of combining weights with add-ons sorted from smallest total weight to
+/// largest. When there are several ways to get a weight the one with the fewest add-ons
+/// is used.
+fn discrete_combos(weights: &[f32], add_ons: &[AddOn]) -> Vec<Extras> {
+    let mut combos: Vec<_> = weights
+        .iter()
+        .map(|w| Extras {
+            base: *w,
+            add_ons: Vec::new(),
+        })
+        .collect();
+    for add_on in add_ons.iter() {
+        let mut extended = Vec::new();
+        for combo in combos.iter() {
+            for count in 1..=add_on.count {
+                let mut combo = combo.clone();
+                combo.add_ons.push(AddOn {
+                    count,
+                    ..add_on.clone()
+                });
+                extended.push(combo);
+            }
+        }
+        combos.extend(extended);
+    }
+
+    let key = |e: &Extras| scaled(e.weight());
+    combos.sort_by_key(|e| (key(e), e.num_add_ons()));
+    combos.dedup_by(|a, b| key(a) == key(b)); // keeps the first, i.e. fewest add-ons
+    combos
+}
+
+fn find_extras(value: f32, combos: Vec<Extras>) -> Weight {
+    match combos
+        .into_iter()
+        .find(|c| (c.weight() - value).abs() < 0.001)
+    {
+        Some(extras) => Weight::extras(extras),
+        None => Weight::discrete(value),
+    }
+}
+
 fn make_dual(plates: &[Plate]) -> Vec<Plate> {
     plates
         .iter()
         .map(|p| Plate {
-            weight: p.weight,
             count: p.count * 2,
+            ..*p
         })
         .collect()
 }
@@ -465,116 +921,166 @@ fn summed_weight(plates: &[Plate]) -> f32 {
         .fold(0.0, |sum, p| sum + (p.weight * (p.count as f32)))
 }
 
-fn closest_dual(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>) -> Plates {
-    fn find_best(target: i32, lhs: &[Plate], rhs: &[Plate]) -> Vec<Plate> {
-        let l = (1000.0 * summed_weight(lhs)) as i32;
-        let r = (1000.0 * summed_weight(rhs)) as i32;
+/// Plates for one combination of plates, i.e. both sides for DualPlates.
+fn load_plates(plates: &[Plate], bar: &Option<f32>, dual: bool) -> Plates {
+    if dual {
+        Plates::new(make_dual(plates), *bar, true)
+    } else {
+        Plates::new(plates.to_vec(), *bar, false)
+    }
+}
+
+/// Returns the target weight for one combination of plates, i.e. one side for DualPlates.
+fn plates_target(target: f32, bar: &Option<f32>, dual: bool) -> f32 {
+    let target = target - bar.unwrap_or(0.0);
+    if dual {
+        target / 2.0
+    } else {
+        target
+    }
+}
+
+fn closest_plates(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>, dual: bool) -> Plates {
+    fn find_best<'a>(target: i32, lhs: &'a [Plate], rhs: &'a [Plate]) -> &'a [Plate] {
+        let l = scaled(summed_weight(lhs));
+        let r = scaled(summed_weight(rhs));
         if i32::abs(target - l) < i32::abs(target - r) {
-            make_dual(lhs)
+            lhs
         } else {
-            make_dual(rhs)
+            rhs
         }
     }
 
-    let target = target - bar.unwrap_or(0.0);
-    let target = target / 2.0; // because this is dual plates
-                               // println!("target: {target:.1} (adjusted)");
-
-    let t = (1000.0 * target) as i32;
+    let target = plates_target(target, bar, dual);
+    let t = scaled(target);
     let i = enums.binary_search_by(|p| {
-        let w = (1000.0 * summed_weight(p)) as i32;
+        let w = scaled(summed_weight(p));
         w.cmp(&t)
     });
-    // println!("search: {i:?}");
     match i {
-        Ok(i) => Plates::new(make_dual(&enums[i]), *bar, true), // exact match
+        Ok(i) => load_plates(&enums[i], bar, dual), // exact match
         Err(i) => {
-            if i > 0 {
-                let plates = find_best(t, &enums[i - 1], &enums[i]);
-                Plates::new(plates, *bar, true)
+            if i > 0 && i < enums.len() {
+                load_plates(find_best(t, &enums[i - 1], &enums[i]), bar, dual)
+            } else if i > 0 {
+                load_plates(&enums[i - 1], bar, dual)
             } else if !enums.is_empty() {
-                let plates = find_best(t, &Vec::new(), &enums[i]);
-                Plates::new(plates, *bar, true)
+                load_plates(find_best(t, &[], &enums[i]), bar, dual)
             } else {
-                Plates::new(Vec::new(), *bar, true)
+                load_plates(&[], bar, dual)
             }
         }
     }
 }
 
-fn lower_dual(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>) -> Plates {
-    let target = target - bar.unwrap_or(0.0);
-    let target = target / 2.0; // because this is dual plates
-                               // println!("target: {target:.1} (adjusted)");
-
-    let t = (1000.0 * target) as i32;
+fn lower_plates(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>, dual: bool) -> Plates {
+    let target = plates_target(target, bar, dual);
+    let t = scaled(target);
     let i = enums.binary_search_by(|p| {
-        let w = (1000.0 * summed_weight(p)) as i32;
+        let w = scaled(summed_weight(p));
         w.cmp(&t)
     });
-    // println!("search: {i:?}");
     match i {
-        Ok(i) => Plates::new(make_dual(&enums[i]), *bar, true), // exact match
+        Ok(i) => load_plates(&enums[i], bar, dual), // exact match
         Err(i) => {
             if i > 0 {
-                Plates::new(make_dual(&enums[i - 1]), *bar, true)
+                load_plates(&enums[i - 1], bar, dual)
             } else {
-                Plates::new(Vec::new(), *bar, true)
+                load_plates(&[], bar, dual)
             }
         }
     }
 }
 
-fn upper_dual(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>) -> Plates {
-    // println!("target: {target:.1}");
-    let target = target - bar.unwrap_or(0.0);
-    let target = target / 2.0; // because this is dual plates
-                               // println!("target: {target:.1} (adjusted)");
-                               // println!("enums: {enums:?}");
-
-    let t = (1000.0 * target) as i32;
+fn upper_plates(target: f32, enums: &[Vec<Plate>], bar: &Option<f32>, dual: bool) -> Plates {
+    let target = plates_target(target, bar, dual);
+    let t = scaled(target);
     let i = enums.binary_search_by(|p| {
-        let w = (1000.0 * summed_weight(p)) as i32;
+        let w = scaled(summed_weight(p));
         w.cmp(&t)
     });
-    // println!("search: {i:?}");
     match i {
         Ok(i) => {
             if i + 1 < enums.len() {
-                Plates::new(make_dual(&enums[i + 1]), *bar, true)
+                load_plates(&enums[i + 1], bar, dual)
             } else {
-                Plates::new(make_dual(&enums[i]), *bar, true)
+                load_plates(&enums[i], bar, dual)
             }
         }
         Err(i) => {
             if target < 0.0 {
                 if bar.is_some() || enums.is_empty() {
-                    Plates::new(Vec::new(), *bar, true)
+                    load_plates(&[], bar, dual)
                 } else {
-                    Plates::new(make_dual(&enums[0]), *bar, true)
+                    load_plates(&enums[0], bar, dual)
                 }
             } else if !enums.is_empty() {
                 if i < enums.len() {
-                    Plates::new(make_dual(&enums[i]), *bar, true)
+                    load_plates(&enums[i], bar, dual)
                 } else {
-                    Plates::new(make_dual(&enums[i - 1]), *bar, true)
+                    load_plates(&enums[i - 1], bar, dual)
                 }
             } else {
-                Plates::new(Vec::new(), *bar, true)
+                load_plates(&[], bar, dual)
             }
         }
     }
 }
 
+/// Pushes every combination of plates that adds up to target (which is scaled) onto found.
+/// Plates are listed from largest to smallest and multiplier is 2 for DualPlates. This is
+/// exhaustive so it gives up after MAX_LOADINGS have been found.
+fn find_loadings(
+    plates: &[Plate],
+    multiplier: i32,
+    max_plates: Option<i32>,
+    target: i32,
+    partial: &mut Vec<Plate>,
+    found: &mut Vec<Vec<Plate>>,
+) {
+    if target == 0 {
+        found.push(partial.clone());
+        return;
+    }
+    let Some((plate, smaller)) = plates.split_last() else {
+        return;
+    };
+
+    let used: i32 = partial.iter().map(|p| p.count).sum();
+    for count in (0..=plate.count / multiplier).rev() {
+        let weight = scaled(plate.weight * count as f32);
+        if found.len() >= MAX_LOADINGS {
+            break;
+        } else if weight > target || max_plates.is_some_and(|max| used + count > max) {
+            continue;
+        }
+        if count > 0 {
+            partial.push(Plate { count, ..*plate });
+        }
+        find_loadings(
+            smaller,
+            multiplier,
+            max_plates,
+            target - weight,
+            partial,
+            found,
+        );
+        if count > 0 {
+            partial.pop();
+        }
+    }
+}
+
 fn find_discrete(target: f32, weights: &[f32]) -> (f32, f32) {
     let mut lower = weights.first().copied().unwrap_or(0.0);
     let mut upper = f32::MAX;
 
     for &candidate in weights.iter() {
-        if candidate > lower && candidate <= target {
+        // Weights may have been converted from other units so allow for a bit of slop.
+        if candidate > lower && candidate <= target + 0.001 {
             lower = candidate;
         }
-        if candidate < upper && candidate >= target {
+        if candidate < upper && candidate >= target - 0.001 {
             upper = candidate;
         }
     }
@@ -584,10 +1090,31 @@ fn find_discrete(target: f32, weights: &[f32]) -> (f32, f32) {
 
 impl fmt::Debug for Plate {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{} x{}", format_weight(self.weight, ""), self.count)
+        let suffix = if self.bumper { " bumper" } else { "" };
+        write!(
+            f,
+            "{}{suffix} x{}",
+            format_weight(self.weight, ""),
+            self.count
+        )
     }
 }
 
+// Scaled by 1000 so that it can be compared exactly.
+fn bumper_weight(plates: &[Plate]) -> i32 {
+    let weight = plates
+        .iter()
+        .filter(|p| p.bumper)
+        .fold(0.0, |sum, p| sum + (p.weight * (p.count as f32)));
+    scaled(weight)
+}
+
+// Weights are compared as integers to avoid problems with floating point precision.
+// Note that this rounds so that weights converted from other units are still matched.
+pub fn scaled(weight: f32) -> i32 {
+    (1000.0 * weight).round() as i32
+}
+
 struct IterN {
     n: u64,
     i: usize,
@@ -622,8 +1149,21 @@ impl Iterator for IterN {
 }
 
 /// Returns all non-duplicate weight combinations of plates sorted from smallest total
-/// weight to largest. TODO probably want to make this a method on Weights
-fn enumerate_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
+/// weight to largest. Combinations with more than max_plates plates are skipped and,
+/// when there are several ways to make a weight, the one using the most bumper weight
+/// is chosen. Note that these are the plates for one side of the bar. TODO probably want
+/// to make this a method on Weights
+fn enumerate_weights(plates: &[Plate], max_plates: Option<i32>) -> Vec<Vec<Plate>> {
+    enumerate_combos(plates, max_plates, true)
+}
+
+/// Like enumerate_weights except that plates are added one at a time so every plate
+/// can be used.
+fn enumerate_single_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
+    enumerate_combos(plates, None, false)
+}
+
+fn enumerate_combos(plates: &[Plate], max_plates: Option<i32>, dual: bool) -> Vec<Vec<Plate>> {
     enum Status {
         Valid,
         Invalid,
@@ -631,23 +1171,23 @@ fn enumerate_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
     }
 
     // TODO: need to restrict max plate count to 9
-    fn is_valid(n: u64, plates: &[Plate]) -> Status {
+    fn is_valid(n: u64, plates: &[Plate], dual: bool) -> Status {
+        let multiplier = if dual { 2 } else { 1 };
         for (count, index) in IterN::new(n) {
             if index >= plates.len() {
                 return Status::Overflow;
-            } else if 2 * count > plates[index].count {
-                // 2* should only be done for dual plates
+            } else if multiplier * count > plates[index].count {
                 return Status::Invalid;
             }
         }
         Status::Valid
     }
 
-    fn increment(n: u64, plates: &[Plate]) -> Option<u64> {
+    fn increment(n: u64, plates: &[Plate], dual: bool) -> Option<u64> {
         let mut n = n;
         loop {
             n += 1;
-            match is_valid(n, plates) {
+            match is_valid(n, plates, dual) {
                 Status::Valid => return Some(n),
                 Status::Overflow => return None,
                 Status::Invalid => (),
@@ -660,7 +1200,10 @@ fn enumerate_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
         for (count, index) in IterN::new(n) {
             assert!(count <= plates[index].count);
             if count > 0 {
-                possible.push(Plate::new(plates[index].weight, count));
+                possible.push(Plate {
+                    count,
+                    ..plates[index]
+                });
             }
         }
         possible
@@ -671,20 +1214,28 @@ fn enumerate_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
     // 2) This will work even for those unfortunates with really weird collections of plates.
     let mut n: u64 = 0; // where n = 2045 means 5 of the largest plate, 4 of the next largest, etc
     let mut candidates: HashMap<i32, Vec<Plate>> = HashMap::new();
-    while let Some(new) = increment(n, plates) {
+    while let Some(new) = increment(n, plates, dual) {
         n = new;
         let candidate = get_candidate(n, plates);
 
         let weight = candidate
             .iter()
             .fold(0.0, |acc, e| acc + (e.count as f32) * e.weight);
-        let candidate_weight = 1000 * (weight as i32); // f32 isn't Hash
+        let candidate_weight = scaled(weight); // f32 isn't Hash
         let candidate_count = candidate.iter().fold(0, |acc, e| acc + e.count);
+        if max_plates.is_some_and(|max| candidate_count > max) {
+            continue;
+        }
         match candidates.entry(candidate_weight) {
             Entry::Occupied(mut occupied) => {
-                // Prefer solutions with the least number of plates.
+                // Prefer solutions that use the most bumpers and then those with the
+                // least number of plates.
                 let old_count = occupied.get().iter().fold(0, |acc, e| acc + e.count);
-                if candidate_count < old_count {
+                let old_bumpers = bumper_weight(occupied.get());
+                let candidate_bumpers = bumper_weight(&candidate);
+                if candidate_bumpers > old_bumpers
+                    || (candidate_bumpers == old_bumpers && candidate_count < old_count)
+                {
                     occupied.insert(candidate);
                 }
             }
@@ -700,8 +1251,8 @@ fn enumerate_weights(plates: &[Plate]) -> Vec<Vec<Plate>> {
         let b = b
             .iter()
             .fold(0.0, |acc, e| acc + (e.count as f32) * e.weight);
-        let a = (1000.0 * a) as i32;
-        let b = (1000.0 * b) as i32;
+        let a = scaled(a);
+        let b = scaled(b);
         a.cmp(&b)
     });
     result
@@ -714,14 +1265,14 @@ mod tests {
     #[test]
     fn enumerate0() {
         let plates = vec![];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         assert_eq!(v.len(), 0);
     }
 
     #[test]
     fn enumerate1() {
         let plates = vec![Plate::new(45.0, 2)];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         println!("{v:?}");
         assert_eq!(v.len(), 1);
         assert_eq!(v[0], vec![Plate::new(45.0, 1)]);
@@ -730,7 +1281,7 @@ mod tests {
     #[test]
     fn enumerate2a() {
         let plates = vec![Plate::new(45.0, 4)];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         println!("{v:?}");
         assert_eq!(v.len(), 2);
         assert_eq!(v[0], vec![Plate::new(45.0, 1)]);
@@ -740,7 +1291,7 @@ mod tests {
     #[test]
     fn enumerate2b() {
         let plates = vec![Plate::new(45.0, 2), Plate::new(25.0, 2)];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         println!("{v:?}");
         assert_eq!(v.len(), 3);
         assert_eq!(v[0], vec![Plate::new(25.0, 1)]);
@@ -748,10 +1299,23 @@ mod tests {
         assert_eq!(v[2], vec![Plate::new(45.0, 1), Plate::new(25.0, 1)]);
     }
 
+    #[test]
+    fn enumerate_single() {
+        let plates = vec![Plate::new(25.0, 1), Plate::new(45.0, 2)];
+        let v = enumerate_single_weights(&plates);
+        println!("{v:?}");
+        assert_eq!(v.len(), 5);
+        assert_eq!(v[0], vec![Plate::new(25.0, 1)]);
+        assert_eq!(v[1], vec![Plate::new(45.0, 1)]);
+        assert_eq!(v[2], vec![Plate::new(25.0, 1), Plate::new(45.0, 1)]);
+        assert_eq!(v[3], vec![Plate::new(45.0, 2)]);
+        assert_eq!(v[4], vec![Plate::new(25.0, 1), Plate::new(45.0, 2)]);
+    }
+
     #[test]
     fn enumerate4() {
         let plates = vec![Plate::new(45.0, 4), Plate::new(25.0, 4)];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         println!("{v:?}");
         assert_eq!(v.len(), 8);
         assert_eq!(v[0], vec![Plate::new(25.0, 1)]);
@@ -775,7 +1339,7 @@ mod tests {
             Plate::new(2.5, 2),
             Plate::new(1.25, 2),
         ];
-        let v = enumerate_weights(&plates);
+        let v = enumerate_weights(&plates, None);
         assert_eq!(v.len(), 247);
     }
 
@@ -784,9 +1348,9 @@ mod tests {
         // Return something half-way sane if there aren't any weights.
         let mut weights = Weights::new();
         let name = "dumbbells";
-        weights.add(name.to_owned(), WeightSet::Discrete(vec![]));
+        weights.add(name.to_owned(), WeightSet::Discrete(vec![], vec![]));
         assert_eq!(weights.closest(name, 10.0).value(), 0.0); // if there are no dumbbells at all then we can't use a weight
-        assert_eq!(weights.closest(name, 10.0).text(), "0 lbs");
+        assert_eq!(weights.closest(name, 10.0).text(Units::Lbs), "0 lbs");
     }
 
     #[test]
@@ -807,42 +1371,42 @@ mod tests {
         let name = "dumbbells";
         weights.add(
             name.to_owned(),
-            WeightSet::Discrete(vec![5.0, 10.0, 15.0, 20.0]),
+            WeightSet::Discrete(vec![5.0, 10.0, 15.0, 20.0], vec![]),
         );
         assert_eq!(weights.closest(name, 0.0).value(), 5.0);
-        assert_eq!(weights.closest(name, 0.0).text(), "5 lbs");
+        assert_eq!(weights.closest(name, 0.0).text(Units::Lbs), "5 lbs");
 
         assert_eq!(weights.closest(name, 4.0).value(), 5.0);
-        assert_eq!(weights.closest(name, 4.0).text(), "5 lbs");
+        assert_eq!(weights.closest(name, 4.0).text(Units::Lbs), "5 lbs");
 
         assert_eq!(weights.closest(name, 5.0).value(), 5.0);
-        assert_eq!(weights.closest(name, 5.0).text(), "5 lbs");
+        assert_eq!(weights.closest(name, 5.0).text(Units::Lbs), "5 lbs");
 
         assert_eq!(weights.closest(name, 6.0).value(), 5.0);
-        assert_eq!(weights.closest(name, 6.0).text(), "5 lbs");
+        assert_eq!(weights.closest(name, 6.0).text(Units::Lbs), "5 lbs");
 
         assert_eq!(weights.closest(name, 9.0).value(), 10.0);
-        assert_eq!(weights.closest(name, 9.0).text(), "10 lbs");
+        assert_eq!(weights.closest(name, 9.0).text(Units::Lbs), "10 lbs");
 
         assert_eq!(weights.closest(name, 18.0).value(), 20.0);
-        assert_eq!(weights.closest(name, 18.0).text(), "20 lbs");
+        assert_eq!(weights.closest(name, 18.0).text(Units::Lbs), "20 lbs");
 
         assert_eq!(weights.closest(name, 30.0).value(), 20.0);
-        assert_eq!(weights.closest(name, 30.0).text(), "20 lbs");
+        assert_eq!(weights.closest(name, 30.0).text(Units::Lbs), "20 lbs");
     }
 
     fn check2(target: f32, lower: &str, upper: &str, plates: &Plates) {
         println!("-----------------------------------------------------");
-        let enums = enumerate_weights(&plates.plates);
+        let enums = enumerate_weights(&plates.plates, None);
         println!("target: {target:.1}");
         println!("plates: {:?}", &plates.plates);
         println!("bar: {:?}", plates.bar);
         println!("enums: {enums:?}");
 
         println!("-----------------------");
-        let l = lower_dual(target, &enums, &plates.bar);
+        let l = lower_plates(target, &enums, &plates.bar, true);
         println!("-----------------------");
-        let u = upper_dual(target, &enums, &plates.bar);
+        let u = upper_plates(target, &enums, &plates.bar, true);
 
         println!("l: {l:?}");
         println!("u: {u:?}");
@@ -864,22 +1428,10 @@ mod tests {
 
     #[test]
     fn dual_plates() {
-        let plate1 = Plate {
-            weight: 5.0,
-            count: 6,
-        };
-        let plate2 = Plate {
-            weight: 10.0,
-            count: 6,
-        };
-        let plate3 = Plate {
-            weight: 25.0,
-            count: 4,
-        };
-        let plate4 = Plate {
-            weight: 45.0,
-            count: 4,
-        };
+        let plate1 = Plate::new(5.0, 6);
+        let plate2 = Plate::new(10.0, 6);
+        let plate3 = Plate::new(25.0, 4);
+        let plate4 = Plate::new(45.0, 4);
         let plates = Plates::new(vec![plate1, plate2, plate3, plate4], None, true);
 
         check2(11.0, "5", "10", &plates); // on one side
@@ -929,23 +1481,10 @@ mod tests {
 
     #[test]
     fn dual_plates_with_bar() {
-        let plate1 = Plate {
-            // we'll use a somewhat unusual plate distribution here
-            weight: 5.0,
-            count: 3,
-        };
-        let plate2 = Plate {
-            weight: 10.0,
-            count: 2,
-        };
-        let plate3 = Plate {
-            weight: 25.0,
-            count: 6,
-        };
-        let plate4 = Plate {
-            weight: 45.0,
-            count: 2,
-        };
+        let plate1 = Plate::new(5.0, 3); // we'll use a somewhat unusual plate distribution here
+        let plate2 = Plate::new(10.0, 2);
+        let plate3 = Plate::new(25.0, 6);
+        let plate4 = Plate::new(45.0, 2);
         let plates = Plates::new(vec![plate1, plate2, plate3, plate4], Some(45.0), true);
 
         check2(60.0, "5", "10", &plates); // can only add a max of 2 5's
@@ -963,15 +1502,15 @@ mod tests {
     }
 
     #[test]
-    fn closest_dual_test() {
+    fn closest_plates_test() {
         fn check(target: f32, expected: &str, plates: &[Plate], bar: Option<f32>) {
             println!("-----------------------------------------------------");
-            let enums = enumerate_weights(plates);
+            let enums = enumerate_weights(plates, None);
             println!("target: {target:.1}");
             println!("plates: {plates:?}");
             println!("bar: {bar:?}");
             println!("enums: {enums:?}");
-            let actual = closest_dual(target, &enums, &bar);
+            let actual = closest_plates(target, &enums, &bar, true);
             println!("actual: {actual:?}");
 
             let actual = format!("{}", actual);
@@ -981,22 +1520,10 @@ mod tests {
             );
         }
 
-        let plate1 = Plate {
-            weight: 5.0,
-            count: 6,
-        };
-        let plate2 = Plate {
-            weight: 10.0,
-            count: 6,
-        };
-        let plate3 = Plate {
-            weight: 25.0,
-            count: 4,
-        };
-        let plate4 = Plate {
-            weight: 45.0,
-            count: 4,
-        };
+        let plate1 = Plate::new(5.0, 6);
+        let plate2 = Plate::new(10.0, 6);
+        let plate3 = Plate::new(25.0, 4);
+        let plate4 = Plate::new(45.0, 4);
         let plates = vec![plate1, plate2, plate3, plate4];
 
         check(0.0, "", &plates, None); // degenerate case
@@ -1019,7 +1546,7 @@ mod tests {
         let name = "dumbbells";
         weights.add(
             name.to_owned(),
-            WeightSet::Discrete(vec![5.0, 10.0, 15.0, 20.0]),
+            WeightSet::Discrete(vec![5.0, 10.0, 15.0, 20.0], vec![]),
         );
         assert_eq!(weights.advance(name, 0.0).value(), 5.0);
         assert_eq!(weights.advance(name, 4.0).value(), 5.0);
@@ -1029,26 +1556,14 @@ mod tests {
 
     #[test]
     fn advance_dual_plates() {
-        let plate1 = Plate {
-            weight: 5.0,
-            count: 6,
-        };
-        let plate2 = Plate {
-            weight: 10.0,
-            count: 6,
-        };
-        let plate3 = Plate {
-            weight: 25.0,
-            count: 4,
-        };
-        let plate4 = Plate {
-            weight: 45.0,
-            count: 4,
-        };
+        let plate1 = Plate::new(5.0, 6);
+        let plate2 = Plate::new(10.0, 6);
+        let plate3 = Plate::new(25.0, 4);
+        let plate4 = Plate::new(45.0, 4);
         let plates = vec![plate1, plate2, plate3, plate4];
         let mut weights = Weights::new();
         let name = "plates";
-        weights.add(name.to_owned(), WeightSet::DualPlates(plates, None));
+        weights.add(name.to_owned(), WeightSet::DualPlates(plates, None, None));
 
         assert_eq!(weights.advance(name, 0.0).value(), 10.0);
         assert_eq!(weights.advance(name, 4.0).value(), 10.0);
@@ -1058,30 +1573,325 @@ mod tests {
 
     #[test]
     fn advance_dual_plates_with_bar() {
-        let plate1 = Plate {
-            weight: 5.0,
-            count: 6,
-        };
-        let plate2 = Plate {
-            weight: 10.0,
-            count: 6,
-        };
-        let plate3 = Plate {
-            weight: 25.0,
-            count: 4,
-        };
-        let plate4 = Plate {
-            weight: 45.0,
-            count: 4,
-        };
+        let plate1 = Plate::new(5.0, 6);
+        let plate2 = Plate::new(10.0, 6);
+        let plate3 = Plate::new(25.0, 4);
+        let plate4 = Plate::new(45.0, 4);
         let plates = vec![plate1, plate2, plate3, plate4];
         let mut weights = Weights::new();
         let name = "plates";
-        weights.add(name.to_owned(), WeightSet::DualPlates(plates, Some(45.0)));
+        weights.add(
+            name.to_owned(),
+            WeightSet::DualPlates(plates, Some(45.0), None),
+        );
 
         assert_eq!(weights.advance(name, 0.0).value(), 45.0);
         assert_eq!(weights.advance(name, 45.0).value(), 55.0);
         assert_eq!(weights.advance(name, 50.0).value(), 55.0);
         assert_eq!(weights.advance(name, 55.0).value(), 65.0);
     }
+
+    #[test]
+    fn single_plates() {
+        let plates = vec![
+            Plate::new(10.0, 2),
+            Plate::new(25.0, 2),
+            Plate::new(45.0, 2),
+        ];
+        let mut weights = Weights::new();
+        let name = "landmine";
+        weights.add(name.to_owned(), WeightSet::SinglePlates(plates, None));
+
+        let weight = weights.lower(name, 64.0);
+        assert_eq!(weight.value(), 60.0);
+        assert_eq!(weight.details().unwrap(), "25 x2 + 10");
+
+        let weight = weights.closest(name, 68.0);
+        assert_eq!(weight.value(), 70.0);
+        assert_eq!(weight.details().unwrap(), "45 + 25");
+
+        let weight = weights.advance(name, 90.0);
+        assert_eq!(weight.value(), 95.0);
+        assert_eq!(weight.details().unwrap(), "45 + 25 x2");
+
+        // more than the set can make
+        assert_eq!(weights.closest(name, 500.0).value(), 160.0);
+        assert_eq!(weights.advance(name, 160.0).value(), 160.0);
+    }
+
+    #[test]
+    fn single_plates_with_base() {
+        let plates = vec![Plate::new(5.0, 1), Plate::new(10.0, 2)];
+        let mut weights = Weights::new();
+        let name = "sled";
+        let set = WeightSet::SinglePlates(plates, Some(50.0));
+        assert!(weights.try_change_set("", name, set, Units::Lbs).is_ok());
+
+        assert_eq!(weights.lower(name, 40.0).value(), 50.0);
+        assert_eq!(weights.advance(name, 50.0).value(), 55.0);
+        assert_eq!(weights.closest(name, 64.0).value(), 65.0);
+        assert_eq!(weights.closest(name, 64.0).details().unwrap(), "10 + 5");
+
+        let set = WeightSet::SinglePlates(vec![Plate::new(5.0, 1)], Some(0.0));
+        assert!(weights.try_change_set(name, name, set, Units::Lbs).is_err());
+    }
+
+    #[test]
+    fn bumpers() {
+        let plates = vec![
+            Plate::new(10.0, 4).with_bumper(),
+            Plate::new(15.0, 8).with_bumper(),
+            Plate::new(20.0, 6),
+            Plate::new(45.0, 2),
+        ];
+        let mut weights = Weights::new();
+        let name = "plates";
+        weights.add(
+            name.to_owned(),
+            WeightSet::DualPlates(plates, Some(45.0), None),
+        );
+
+        // 15 x2 would normally lose to 10 + 20 but bumpers are preferred
+        let weight = weights.lower(name, 105.0);
+        assert_eq!(weight.value(), 105.0);
+        assert_eq!(weight.details().unwrap(), "15 x2");
+
+        // even when that means using more plates
+        let weight = weights.closest(name, 135.0);
+        assert_eq!(weight.details().unwrap(), "15 x3");
+        let weight = weights.closest(name, 225.0);
+        assert_eq!(weight.details().unwrap(), "20 + 15 x4 + 10");
+
+        // prefers the 15s over the 10s
+        let weight = weights.advance(name, 100.0);
+        assert_eq!(weight.value(), 105.0);
+        assert_eq!(weight.details().unwrap(), "15 x2");
+    }
+
+    #[test]
+    fn max_plates() {
+        let plates = vec![Plate::new(5.0, 8), Plate::new(10.0, 8), Plate::new(45.0, 4)];
+        let mut weights = Weights::new();
+        let name = "plates";
+        weights.add(
+            name.to_owned(),
+            WeightSet::DualPlates(plates, Some(45.0), Some(2)),
+        );
+
+        assert_eq!(weights.lower(name, 135.0).details().unwrap(), "45");
+        assert_eq!(weights.lower(name, 225.0).details().unwrap(), "45 x2");
+
+        // 45 + 10 + 5 needs three plates so we have to drop down
+        let weight = weights.lower(name, 165.0);
+        assert_eq!(weight.value(), 155.0);
+        assert_eq!(weight.details().unwrap(), "45 + 10");
+        assert_eq!(weights.advance(name, 155.0).value(), 225.0);
+    }
+
+    #[test]
+    fn invalid_max_plates() {
+        let plates = vec![Plate::new(45.0, 4)];
+        let mut weights = Weights::new();
+        let set = WeightSet::DualPlates(plates, Some(45.0), Some(0));
+        assert!(weights
+            .try_change_set("", "plates", set, Units::Lbs)
+            .is_err());
+    }
+
+    #[test]
+    fn add_ons() {
+        let dumbbells = (1..=10).map(|i| 5.0 * i as f32).collect();
+        let add_ons = vec![AddOn::new("magnet", 2.5, 1), AddOn::new("clip", 1.25, 2)];
+        let mut weights = Weights::new();
+        let name = "dumbbells";
+        weights.add(name.to_owned(), WeightSet::Discrete(dumbbells, add_ons));
+
+        let weight = weights.lower(name, 42.5);
+        assert_eq!(weight.value(), 42.5);
+        assert_eq!(weight.text(Units::Lbs), "42.5 lbs");
+        assert_eq!(weight.details().unwrap(), "40 + 2.5 magnet");
+
+        // exact weights don't need add-ons
+        let weight = weights.closest(name, 40.0);
+        assert_eq!(weight.value(), 40.0);
+        assert_eq!(weight.details(), None);
+
+        let weight = weights.lower(name, 44.0);
+        assert_eq!(weight.value(), 43.75);
+        assert_eq!(weight.details().unwrap(), "40 + 2.5 magnet + 1.25 clip");
+
+        let weight = weights.advance(name, 40.0);
+        assert_eq!(weight.value(), 41.25);
+        assert_eq!(weight.details().unwrap(), "40 + 1.25 clip");
+
+        // one magnet is better than two clips
+        let weight = weights.advance(name, 52.0);
+        assert_eq!(weight.details().unwrap(), "50 + 2.5 magnet");
+
+        let weight = weights.lower(name, 60.0);
+        assert_eq!(weight.value(), 55.0);
+        assert_eq!(weight.details().unwrap(), "50 + 2.5 magnet + 1.25 clip x2");
+    }
+
+    #[test]
+    fn invalid_add_ons() {
+        let mut weights = Weights::new();
+        let set = WeightSet::Discrete(vec![5.0], vec![AddOn::new("magnet", 2.5, 0)]);
+        assert!(weights
+            .try_change_set("", "dumbbells", set, Units::Lbs)
+            .is_err());
+
+        let set = WeightSet::Discrete(vec![5.0], vec![AddOn::new("magnet", 2.5, 10)]);
+        assert!(weights
+            .try_change_set("", "dumbbells", set, Units::Lbs)
+            .is_err());
+        let set = WeightSet::Discrete(vec![5.0], vec![AddOn::new("magnet", 2.5, 9)]);
+        assert!(weights
+            .try_change_set("", "magnet dumbbells", set, Units::Lbs)
+            .is_ok());
+
+        let add_ons = vec![AddOn::new("magnet", 2.5, 1), AddOn::new("magnet", 1.0, 1)];
+        let set = WeightSet::Discrete(vec![5.0], add_ons);
+        assert!(weights
+            .try_change_set("", "dumbbells", set, Units::Lbs)
+            .is_err());
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Units::Lbs.format(225.0), "225 lbs");
+        assert_eq!(Units::Kgs.format(225.0), "102.05 kg");
+        assert_eq!(Units::Kgs.format(Units::Kgs.to_lbs(101.25)), "101.25 kg");
+        assert_eq!(Units::Kgs.format(220.5), "100 kg"); // imported weights are rounded
+    }
+
+    #[test]
+    fn kg_plates() {
+        let plates = vec![
+            Plate::new(0.5, 2),
+            Plate::new(1.25, 2),
+            Plate::new(2.5, 2),
+            Plate::new(5.0, 2),
+            Plate::new(10.0, 2),
+            Plate::new(20.0, 4),
+        ];
+        let mut weights = Weights::new();
+        let name = "plates";
+        let set = WeightSet::DualPlates(plates, Some(20.0), None);
+        assert!(weights.try_change_set("", name, set, Units::Kgs).is_ok());
+        assert_eq!(weights.units(name), Some(Units::Kgs));
+        assert_eq!(
+            weights.display_units(&Some(name.to_owned()), Units::Lbs),
+            Units::Kgs
+        );
+        assert_eq!(weights.display_units(&None, Units::Lbs), Units::Lbs);
+
+        // Targets are in lbs so they don't convert back to exactly 100 kg.
+        let weight = weights.lower(name, Units::Kgs.to_lbs(100.0));
+        assert_eq!(weight.text(Units::Lbs), "100 kg");
+        assert_eq!(weight.details().unwrap(), "20 x2");
+        assert!((weight.value() - 220.462).abs() < 0.01);
+
+        // 0.5 kg plates used to collide with whole numbers
+        let weight = weights.lower(name, Units::Kgs.to_lbs(26.0));
+        assert_eq!(weight.text(Units::Lbs), "26 kg");
+        assert_eq!(weight.details().unwrap(), "2.5 + 0.5");
+        let weight = weights.advance(name, weight.value());
+        assert_eq!(weight.text(Units::Lbs), "27.5 kg");
+        assert_eq!(weight.details().unwrap(), "2.5 + 1.25");
+    }
+
+    #[test]
+    fn kg_discrete() {
+        let mut weights = Weights::new();
+        let name = "dumbbells";
+        let set = WeightSet::Discrete(vec![2.0, 4.0, 6.0, 8.0], vec![]);
+        assert!(weights.try_change_set("", name, set, Units::Kgs).is_ok());
+
+        let weight = weights.lower(name, Units::Kgs.to_lbs(6.0));
+        assert_eq!(weight.text(Units::Lbs), "6 kg");
+        let weight = weights.advance(name, weight.value());
+        assert_eq!(weight.text(Units::Lbs), "8 kg");
+        let weight = weights.closest(name, 10.0);
+        assert_eq!(weight.text(Units::Lbs), "4 kg");
+
+        // weights that aren't from a weight set use the preferred units
+        let weight = weights.closest("", 100.0);
+        assert_eq!(weight.text(Units::Kgs), "45.35 kg");
+        assert_eq!(weight.text(Units::Lbs), "100 lbs");
+    }
+
+    #[test]
+    fn set_weight_sets() {
+        let mut weights = Weights::new();
+        let set = WeightSet::DualPlates(vec![Plate::new(45.0, 4)], Some(45.0), None);
+        assert!(weights.try_change_set("", "bar", set, Units::Kgs).is_ok());
+        let set = WeightSet::DualPlates(vec![Plate::new(25.0, 4)], None, None);
+        assert!(weights
+            .try_change_set("", "unused", set, Units::Lbs)
+            .is_ok());
+        let set = WeightSet::Discrete(vec![5.0, 10.0], vec![]);
+        assert!(weights
+            .try_change_set("", "dumbbells", set, Units::Lbs)
+            .is_ok());
+        let in_use: HashSet<_> = ["bar".to_owned(), "dumbbells".to_owned()].into();
+
+        // plate sets used to be checked against discrete sets
+        let existing = |name: &str| SetSource::Existing(name.to_owned());
+        let sets = vec![
+            ("bar".to_owned(), existing("bar")),
+            ("unused".to_owned(), existing("unused")),
+        ];
+        let renames = weights.try_set_plate_weights(sets, &in_use);
+        assert!(renames.is_ok_and(|r| r.is_empty()));
+
+        // sets in use can't be deleted
+        let sets = vec![("unused".to_owned(), existing("unused"))];
+        assert!(weights.try_set_plate_weights(sets, &in_use).is_err());
+        let sets = vec![("dumbbells".to_owned(), SetSource::New)];
+        assert!(weights.try_set_plate_weights(sets, &in_use).is_err());
+
+        // rename, copy, and delete
+        let sets = vec![
+            ("barbell".to_owned(), existing("bar")),
+            ("bar copy".to_owned(), SetSource::Copy("bar".to_owned())),
+            ("new".to_owned(), SetSource::New),
+        ];
+        let renames = weights.try_set_plate_weights(sets, &in_use);
+        assert!(renames.is_ok_and(|r| r == vec![("bar".to_owned(), "barbell".to_owned())]));
+        assert!(weights.get("bar").is_none());
+        assert!(weights.get("unused").is_none());
+        assert!(weights.get("new").is_some());
+        assert!(weights.get("dumbbells").is_some());
+        assert_eq!(weights.units("barbell"), Some(Units::Kgs));
+        assert_eq!(weights.units("bar copy"), Some(Units::Kgs));
+        let weight = weights.lower("bar copy", Units::Kgs.to_lbs(140.0));
+        assert_eq!(weight.text(Units::Lbs), "135 kg");
+    }
+
+    #[test]
+    fn rename_set() {
+        let mut weights = Weights::new();
+        let set = WeightSet::DualPlates(vec![Plate::new(45.0, 4)], Some(45.0), None);
+        assert!(weights
+            .try_change_set("", "bar", set.clone(), Units::Kgs)
+            .is_ok());
+        let other = WeightSet::Discrete(vec![5.0], vec![]);
+        assert!(weights
+            .try_change_set("", "other", other, Units::Lbs)
+            .is_ok());
+
+        assert!(weights
+            .try_change_set("bar", "other", set.clone(), Units::Kgs)
+            .is_err());
+        assert!(weights
+            .try_change_set("bar", "barbell", set, Units::Kgs)
+            .is_ok());
+        assert!(weights.get("bar").is_none());
+        assert_eq!(weights.units("bar"), None);
+        assert_eq!(weights.units("barbell"), Some(Units::Kgs));
+        let weight = weights.lower("barbell", Units::Kgs.to_lbs(140.0));
+        assert_eq!(weight.text(Units::Lbs), "135 kg");
+        assert_eq!(weight.details().unwrap(), "45");
+    }
 }
@@ -1,15 +1,14 @@
 use crate::validation_err;
 use crate::{
-    days::Days,
     errors::Error,
     exercise::{
         BuildExercise, Exercise, ExerciseName, FixedReps, FormalName, VariableReps,
         VariableRepsExercise,
     },
 };
-use chrono::{DateTime, Local, Weekday};
+use chrono::{NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Schedule {
@@ -22,6 +21,17 @@ pub enum Schedule {
     // TODO: don't allow this to be empty
     /// Workout is scheduled for specified list of days, e.g. Mon/Wed/Fri.
     Days(Vec<Weekday>),
+
+    /// Workouts with the same rotation days take turns on those days, e.g. A on Monday,
+    /// B on Wednesday, A on Friday, and B on the next Monday. Missed days don't advance
+    /// the rotation.
+    Rotation(Vec<Weekday>),
+
+    /// Workout should be done N times a week on whichever days the user likes.
+    PerWeek(i32),
+
+    /// Workout is scheduled for specific dates, e.g. a one-off test day.
+    Dates(Vec<NaiveDate>),
 }
 
 /// Set of [`Exercise`]s to perform all together. These are typically all performed
@@ -33,7 +43,6 @@ pub struct Workout {
     pub schedule: Schedule,
     pub enabled: bool,
     exercises: Vec<Exercise>,
-    completed: HashMap<ExerciseName, DateTime<Local>>, // when the user last did an exercise, for this workout
 }
 
 impl Workout {
@@ -43,7 +52,6 @@ impl Workout {
             schedule,
             enabled: true,
             exercises: Vec::new(),
-            completed: HashMap::new(),
         }
     }
 
@@ -99,6 +107,10 @@ impl Workout {
         self.exercises.iter()
     }
 
+    pub fn exercises_mut(&mut self) -> impl Iterator<Item = &mut Exercise> + '_ {
+        self.exercises.iter_mut()
+    }
+
     pub fn find(&self, name: &ExerciseName) -> Option<&Exercise> {
         self.exercises.iter().find(|e| e.name() == name)
     }
@@ -107,10 +119,6 @@ impl Workout {
         self.exercises.iter_mut().find(|e| e.name() == name)
     }
 
-    pub fn days_since_last_completed(&self) -> Option<Days> {
-        self.completed.values().max().map(|d| Days::new(*d))
-    }
-
     fn validate_change_exercise_name(
         &self,
         old_name: &ExerciseName,
@@ -158,11 +166,21 @@ impl Workout {
                     return validation_err!("N should be greater than zero.");
                 }
             }
-            Schedule::Days(days) => {
+            Schedule::Days(days) | Schedule::Rotation(days) => {
                 if days.is_empty() {
                     return validation_err!("At least one day should be scheduled.",);
                 }
             }
+            Schedule::PerWeek(n) => {
+                if *n <= 0 || *n > 7 {
+                    return validation_err!("Times per week should be between 1 and 7.");
+                }
+            }
+            Schedule::Dates(dates) => {
+                if dates.is_empty() {
+                    return validation_err!("At least one date should be scheduled.");
+                }
+            }
         }
         Ok(())
     }